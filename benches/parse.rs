@@ -0,0 +1,122 @@
+use clap_subopt_parser::subopt;
+use clap_subopt_parser::{parse_kv, parse_str, KeyInfo, KvMap, SubOpt, SubOptError, SubOptParser};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[derive(Default)]
+struct Buf {
+    source: u32,
+    offset: u64,
+    addr: String,
+    sync: bool,
+}
+
+impl SubOpt for Buf {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        match k {
+            "sync" => self.sync = true,
+            _ => return Err(SubOptError::UnknownKey(k.into())),
+        }
+        Ok(())
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        match k {
+            "source" => self.source = parse_kv(k, v)?,
+            "offset" => self.offset = parse_kv(k, v)?,
+            "addr" => self.addr = v.into(),
+            _ => return Err(SubOptError::UnknownKey(k.into())),
+        }
+        Ok(())
+    }
+    fn keys() -> Vec<KeyInfo> {
+        vec![
+            KeyInfo::new("source").value_name("N"),
+            KeyInfo::new("offset").value_name("BYTES"),
+            KeyInfo::new("addr").value_name("ADDR"),
+            KeyInfo::new("sync"),
+        ]
+    }
+}
+
+fn short(c: &mut Criterion) {
+    let parser = SubOptParser::<Buf>::default();
+    c.bench_function("short", |b| {
+        b.iter(|| parser.parse(black_box("source=0:offset=4096:sync")))
+    });
+    c.bench_function("short_error", |b| {
+        b.iter(|| parser.parse(black_box("source=0:size=4096:sync")))
+    });
+    c.bench_function("short_kvmap", |b| {
+        b.iter(|| parse_str::<KvMap>(black_box("source=0:offset=4096:sync")))
+    });
+}
+
+subopt! {
+    struct Encoder {
+        source: u32,
+        offset: u32,
+        length: u32,
+        stride: u32,
+        width: u32,
+        height: u32,
+        depth: u32,
+        format: u32,
+        colorspace: u32,
+        gamma: u32,
+        quality: u32,
+        preset: u32,
+        profile: u32,
+        level: u32,
+        tune: u32,
+        threads: u32,
+        bitrate: u32,
+        maxrate: u32,
+        bufsize: u32,
+        keyint: u32,
+        minkeyint: u32,
+        scenecut: u32,
+        bframes: u32,
+        refs: u32,
+        partitions: u32,
+        subme: u32,
+        trellis: u32,
+        psy: u32,
+        deblock: u32,
+        aq: u32,
+        lookahead: u32,
+        cache: u32,
+    }
+}
+
+fn many_fields(c: &mut Criterion) {
+    let parser = SubOptParser::<Encoder>::default();
+    let arg = "source=0:offset=1:length=2:stride=3:width=4:height=5:depth=6:format=7:colorspace=8:gamma=9:quality=10:preset=11:profile=12:level=13:tune=14:threads=15:bitrate=16:maxrate=17:bufsize=18:keyint=19:minkeyint=20:scenecut=21:bframes=22:refs=23:partitions=24:subme=25:trellis=26:psy=27:deblock=28:aq=29:lookahead=30:cache=31";
+    c.bench_function("many_fields", |b| b.iter(|| parser.parse(black_box(arg))));
+}
+
+fn hundred_keys(c: &mut Criterion) {
+    let arg = (0..100)
+        .map(|i| format!("key{}=value{}", i, i))
+        .collect::<Vec<_>>()
+        .join(":");
+    c.bench_function("hundred_keys", |b| {
+        b.iter(|| parse_str::<KvMap>(black_box(&arg)))
+    });
+}
+
+fn escaped(c: &mut Criterion) {
+    let parser = SubOptParser::<Buf>::default();
+    c.bench_function("escaped", |b| {
+        b.iter(|| parser.parse(black_box("addr=[[::1]:8080]:source=1:offset=[4096]")))
+    });
+    let arg = format!("addr=[{}]", "host:port=1,".repeat(200));
+    c.bench_function("escaped_long", |b| b.iter(|| parser.parse(black_box(&arg))));
+    let arg = format!(
+        "source=1:addr={}:offset=[{}]",
+        "x".repeat(8192),
+        "y".repeat(8192)
+    );
+    c.bench_function("huge", |b| b.iter(|| parser.parse(black_box(&arg))));
+}
+
+criterion_group!(benches, short, many_fields, hundred_keys, escaped);
+criterion_main!(benches);
@@ -0,0 +1,174 @@
+use crate::{tokenize, SubOptError};
+#[cfg(feature = "clap")]
+use clap::builder::TypedValueParser;
+use std::sync::Arc;
+
+type KeyFn<S> = dyn Fn(&mut S, &str) -> Result<(), SubOptError> + Send + Sync;
+type FlagFn<S> = dyn Fn(&mut S) -> Result<(), SubOptError> + Send + Sync;
+
+/// A [`TypedValueParser`] with keys registered at runtime, for keys that aren't known at
+/// compile time, like plugin options or user-defined fields.
+///
+/// Parsing starts from a clone of the initial state, and the closure of each given key
+/// updates it.
+///
+/// ```
+/// # #[cfg(feature = "clap")] {
+/// use clap::{Arg, Command};
+/// use clap_subopt_parser::{parse_kv, SubOptBuilder};
+///
+/// #[derive(Debug, Default, Clone)]
+/// struct State {
+///     offset: u64,
+///     sync: bool,
+/// }
+///
+/// let parser = SubOptBuilder::new(State::default())
+///     .key("offset", |s, v| {
+///         s.offset = parse_kv("offset", v)?;
+///         Ok(())
+///     })
+///     .flag("sync", |s| {
+///         s.sync = true;
+///         Ok(())
+///     });
+/// assert_eq!(parser.keys().collect::<Vec<_>>(), ["offset", "sync"]);
+/// let m = Command::new("myapp")
+///     .arg(Arg::new("buf").long("buf").value_parser(parser))
+///     .get_matches_from(["myapp", "--buf", "offset=512:sync"]);
+/// let state = m.get_one::<State>("buf").unwrap();
+/// assert_eq!(state.offset, 512);
+/// assert!(state.sync);
+/// # }
+/// ```
+pub struct SubOptBuilder<S> {
+    init: S,
+    keys: Vec<(Arc<str>, Handler<S>)>,
+    separator: char,
+}
+
+enum Handler<S> {
+    Key(Arc<KeyFn<S>>),
+    Flag(Arc<FlagFn<S>>),
+}
+
+impl<S> Clone for Handler<S> {
+    fn clone(&self) -> Self {
+        match self {
+            Handler::Key(f) => Handler::Key(f.clone()),
+            Handler::Flag(f) => Handler::Flag(f.clone()),
+        }
+    }
+}
+
+impl<S: Clone> Clone for SubOptBuilder<S> {
+    fn clone(&self) -> Self {
+        Self {
+            init: self.init.clone(),
+            keys: self.keys.clone(),
+            separator: self.separator,
+        }
+    }
+}
+
+impl<S> SubOptBuilder<S> {
+    /// Create a builder without keys, starting from `init`
+    pub fn new(init: S) -> Self {
+        Self {
+            init,
+            keys: Vec::new(),
+            separator: crate::DEFAULT_SEPARATOR,
+        }
+    }
+    /// Separate sub-options with `separator` instead of [`DEFAULT_SEPARATOR`].
+    ///
+    /// ```
+    /// use clap_subopt_parser::SubOptBuilder;
+    ///
+    /// let parser = SubOptBuilder::new(Vec::new())
+    ///     .key("dir", |dirs: &mut Vec<String>, v| {
+    ///         dirs.push(v.to_owned());
+    ///         Ok(())
+    ///     })
+    ///     .separator(';');
+    /// assert_eq!(parser.parse("dir=C:\\Temp;dir=D:\\").unwrap(), ["C:\\Temp", "D:\\"]);
+    /// ```
+    ///
+    /// [`DEFAULT_SEPARATOR`]: crate::DEFAULT_SEPARATOR
+    #[must_use]
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+    /// Add a key taking a value, like `offset=512`
+    #[must_use]
+    pub fn key<F>(self, name: impl Into<String>, f: F) -> Self
+    where
+        F: Fn(&mut S, &str) -> Result<(), SubOptError> + Send + Sync + 'static,
+    {
+        self.add(name.into(), Handler::Key(Arc::new(f)))
+    }
+    /// Add a key without a value, like `sync`
+    #[must_use]
+    pub fn flag<F>(self, name: impl Into<String>, f: F) -> Self
+    where
+        F: Fn(&mut S) -> Result<(), SubOptError> + Send + Sync + 'static,
+    {
+        self.add(name.into(), Handler::Flag(Arc::new(f)))
+    }
+    /// The names of the keys, in the order they were added.
+    ///
+    /// These are not reported to clap as possible values, as clap's help would list them as the
+    /// only valid values of the argument.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.keys.iter().map(|(name, _)| &**name)
+    }
+    fn add(mut self, name: String, handler: Handler<S>) -> Self {
+        self.keys.push((name.into(), handler));
+        self
+    }
+}
+
+impl<S: Clone> SubOptBuilder<S> {
+    /// Parse `s`, without clap
+    pub fn parse(&self, s: &str) -> Result<S, SubOptError> {
+        if s.trim().is_empty() {
+            return Err(SubOptError::EmptyArgument);
+        }
+        let mut state = self.init.clone();
+        for (span, k, v) in tokenize(s, self.separator, '=') {
+            self.update(&mut state, k, v)
+                .map_err(|e| e.in_segment(s, span, '='))?;
+        }
+        Ok(state)
+    }
+    fn update(&self, state: &mut S, k: &str, v: Option<&str>) -> Result<(), SubOptError> {
+        let handler = self
+            .keys
+            .iter()
+            .find(|(name, _)| &**name == k)
+            .map(|(_, handler)| handler)
+            .ok_or_else(|| SubOptError::UnknownKey(k.into()))?;
+        match (handler, v) {
+            (Handler::Key(f), Some(v)) => f(state, v),
+            (Handler::Key(_), None) => Err(SubOptError::MissingValueForKey(k.into())),
+            (Handler::Flag(f), None) => f(state),
+            (Handler::Flag(_), Some(_)) => Err(SubOptError::invalid_value(k, "takes no value")),
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<S: Clone + Send + Sync + 'static> TypedValueParser for SubOptBuilder<S> {
+    type Value = S;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        self.parse(crate::to_str(value))
+            .map_err(|e| e.format(cmd, arg))
+    }
+}
@@ -0,0 +1,343 @@
+//! Shell completion of sub-option keys.
+//!
+//! clap_complete's generated scripts only complete possible values as a whole. The functions
+//! here complete `key=value:key=value` strings, and can be called from an application's own
+//! completion entry point.
+
+use crate::{KeyInfo, SubOpt};
+
+/// A completion candidate for the value of a key, see [`SubOpt::value_candidates`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CompletionCandidate {
+    /// The value
+    pub value: String,
+    /// Help text
+    pub help: Option<String>,
+}
+
+impl CompletionCandidate {
+    /// Create a candidate for `value`
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            help: None,
+        }
+    }
+    /// Set the help text
+    #[must_use]
+    pub fn help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+/// Complete the partially typed argument value `current` for `T`.
+///
+/// Returns the candidates for the whole value. Only the last segment is completed.
+///
+/// Keys are completed in the order of [`SubOpt::keys`], and keys already given are not
+/// suggested again. Keys with a value name are completed with a trailing `=`.
+/// After the `=`, the value is completed using [`SubOpt::value_candidates`].
+///
+/// ```
+/// use clap_subopt_parser::{completion, KeyInfo};
+///
+/// let keys = [
+///     KeyInfo::new("source").value_name("INDEX"),
+///     KeyInfo::new("offset").value_name("BYTES"),
+/// ];
+/// assert_eq!(completion::complete_keys(&keys, "sou"), ["source="]);
+/// assert_eq!(completion::complete_keys(&keys, "source=0:"), ["source=0:offset="]);
+/// ```
+pub fn complete<T: SubOpt>(current: &str) -> Vec<String> {
+    let seps = (T::SEPARATOR, T::KV_SEPARATOR);
+    let (done, last) = split_last(current, seps.0);
+    match last.split_once(seps.1) {
+        Some((key, value)) => T::value_candidates(key)
+            .into_iter()
+            .filter(|c| c.value.starts_with(value))
+            .map(|c| format!("{}{}{}{}", done, key, seps.1, c.value))
+            .collect(),
+        None => keys_for(&T::keys(), current, seps),
+    }
+}
+
+/// The [value hint](KeyInfo::value_hint) of the key whose value is being typed in `current`.
+///
+/// Returns `None` if no value is being typed, or the key has no hint. Applications can use
+/// this to fall back to filesystem or host completion.
+///
+/// ```
+/// use clap::ValueHint;
+/// use clap_subopt_parser::{completion, KeyInfo};
+///
+/// let keys = [
+///     KeyInfo::new("path").value_name("PATH").value_hint(ValueHint::FilePath),
+///     KeyInfo::new("offset").value_name("BYTES"),
+/// ];
+/// assert_eq!(completion::hint_for(&keys, "offset=0:path=/us"), Some(ValueHint::FilePath));
+/// assert_eq!(completion::hint_for(&keys, "path=/usr:offset="), None);
+/// assert_eq!(completion::hint_for(&keys, "pa"), None);
+/// ```
+#[cfg(feature = "clap")]
+pub fn hint_for(keys: &[KeyInfo], current: &str) -> Option<clap::ValueHint> {
+    hint_with(keys, current, (crate::DEFAULT_SEPARATOR, '='))
+}
+
+#[cfg(feature = "clap")]
+fn hint_with(keys: &[KeyInfo], current: &str, seps: (char, char)) -> Option<clap::ValueHint> {
+    let (key, _) = split_last(current, seps.0).1.split_once(seps.1)?;
+    keys.iter()
+        .find(|k| k.matches(key))
+        .map(|k| k.value_hint)
+        .filter(|&hint| hint != clap::ValueHint::Unknown)
+}
+
+/// The value hint for `current`, using the keys of `T`, see [`hint_for`]
+#[cfg(feature = "clap")]
+pub fn value_hint<T: SubOpt>(current: &str) -> Option<clap::ValueHint> {
+    hint_with(&T::keys(), current, (T::SEPARATOR, T::KV_SEPARATOR))
+}
+
+/// Split off the last segment
+fn split_last(current: &str, sep: char) -> (&str, &str) {
+    match current.rfind(sep) {
+        Some(pos) => current.split_at(pos + sep.len_utf8()),
+        None => ("", current),
+    }
+}
+
+/// Complete the keys in `current` against `keys`, see [`complete`]
+pub fn complete_keys(keys: &[KeyInfo], current: &str) -> Vec<String> {
+    keys_for(keys, current, (crate::DEFAULT_SEPARATOR, '='))
+}
+
+fn keys_for(keys: &[KeyInfo], current: &str, (sep, kv_sep): (char, char)) -> Vec<String> {
+    let (done, last) = split_last(current, sep);
+    if last.contains(kv_sep) {
+        return Vec::new();
+    }
+    let given = done
+        .split(sep)
+        .map(|seg| seg.split_once(kv_sep).map_or(seg, |(k, _)| k))
+        .collect::<Vec<_>>();
+    keys.iter()
+        .filter(|key| !key.hidden)
+        .filter(|key| key.name.starts_with(last))
+        .filter(|key| !given.iter().any(|k| key.matches(k)))
+        .map(|key| match key.value_name {
+            Some(_) => format!("{}{}{}", done, key.name, kv_sep),
+            None => format!("{}{}", done, key.name),
+        })
+        .collect()
+}
+
+/// Generate a static fish completion line for the keys of `T`, for the option `--long` of `bin`.
+///
+/// Static completions can only complete the first key of the value. Append the output to the
+/// completions generated by clap_complete.
+pub fn fish<T: SubOpt>(bin: &str, long: &str) -> String {
+    render_fish(bin, long, &T::keys())
+}
+
+/// Generate a fish completion line for `keys`, see [`fish`]
+///
+/// ```
+/// use clap_subopt_parser::{completion, KeyInfo};
+///
+/// let keys = [KeyInfo::new("source").value_name("INDEX").help("Source buffer")];
+/// assert_eq!(
+///     completion::render_fish("myapp", "buf", &keys),
+///     "complete -c myapp -l buf -r -f -a \"{source=\\t'Source buffer'}\"\n"
+/// );
+/// ```
+pub fn render_fish(bin: &str, long: &str, keys: &[KeyInfo]) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('\'', "\\'");
+    let candidates = keys
+        .iter()
+        .filter(|key| !key.hidden)
+        .map(|key| {
+            let eq = if key.value_name.is_some() { "=" } else { "" };
+            format!(
+                "{}{}\\t'{}'",
+                escape(key.name),
+                eq,
+                escape(key.help.unwrap_or_default())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "complete -c {} -l {} -r -f -a \"{{{}}}\"\n",
+        bin, long, candidates
+    )
+}
+
+/// Generate a zsh completion function for the keys of `T`, for the option `--long` of `bin`.
+///
+/// The function is named `_<bin>_<long>`, and completes all segments of the value using
+/// `_values`. Values of keys with a [value hint](KeyInfo::value_hint) are completed with the
+/// matching zsh function, like `_files`. Reference it as the action of the option in an `_arguments` spec, like
+/// `'--buf=[Buffer]:buf:_myapp_buf'`. Segments are split at the [separators](SubOpt::SEPARATOR)
+/// of `T`.
+///
+/// ```
+/// use clap_subopt_parser::{completion, Delimited, KvMap};
+///
+/// let zsh = completion::zsh::<Delimited<KvMap, ';', '='>>("myapp", "env");
+/// assert!(zsh.contains("_values -s ';' -S '=' 'sub-options'"));
+/// ```
+pub fn zsh<T: SubOpt>(bin: &str, long: &str) -> String {
+    render_zsh(bin, long, &T::keys(), (T::SEPARATOR, T::KV_SEPARATOR))
+}
+
+/// Generate a zsh completion function for `keys`, see [`zsh`]
+///
+/// `seps` are the separators between sub-options and between keys and values, like
+/// `(T::SEPARATOR, T::KV_SEPARATOR)`.
+///
+/// ```
+/// # #[cfg(feature = "clap")] {
+/// use clap_subopt_parser::{completion, KeyInfo};
+///
+/// let keys = [
+///     KeyInfo::new("source").value_name("INDEX").help("Source buffer"),
+///     KeyInfo::new("ro").help("Read-only"),
+///     KeyInfo::new("file").value_name("PATH").value_hint(clap::ValueHint::FilePath),
+/// ];
+/// assert_eq!(
+///     completion::render_zsh("myapp", "buf", &keys, (':', '=')),
+///     "_myapp_buf() {
+///     _values -s ':' -S '=' 'sub-options' \\
+///         'source[Source buffer]:INDEX: ' \\
+///         'ro[Read-only]' \\
+///         'file:PATH:_files'
+/// }
+/// "
+/// );
+/// # }
+/// ```
+pub fn render_zsh(bin: &str, long: &str, keys: &[KeyInfo], seps: (char, char)) -> String {
+    let quote = |c: char| c.to_string().replace('\'', "'\\''");
+    let escape = |s: &str| {
+        s.replace('\'', "'\\''")
+            .replace('[', "\\[")
+            .replace(']', "\\]")
+            .replace(':', "\\:")
+    };
+    let ident = |s: &str| s.replace(|c: char| !c.is_ascii_alphanumeric(), "_");
+    let mut out = format!(
+        "_{}_{}() {{\n    _values -s '{}' -S '{}' 'sub-options'",
+        ident(bin),
+        ident(long),
+        quote(seps.0),
+        quote(seps.1)
+    );
+    for key in keys.iter().filter(|k| !k.hidden) {
+        out += &format!(" \\\n        '{}", escape(key.name));
+        if let Some(help) = key.help {
+            out += &format!("[{}]", escape(help));
+        }
+        if let Some(value_name) = key.value_name {
+            out += &format!(":{}:{}", escape(value_name), zsh_action(key));
+        }
+        out += "'";
+    }
+    out += "\n}\n";
+    out
+}
+
+/// The zsh completion action for the value of `key`, matching clap_complete
+#[cfg(feature = "clap")]
+fn zsh_action(key: &KeyInfo) -> &'static str {
+    use clap::ValueHint;
+    match key.value_hint {
+        ValueHint::AnyPath | ValueHint::FilePath => "_files",
+        ValueHint::DirPath => "_files -/",
+        ValueHint::ExecutablePath => "_absolute_command_paths",
+        ValueHint::CommandName => "_command_names -e",
+        ValueHint::CommandString => "_cmdstring",
+        ValueHint::CommandWithArguments => "_cmdambivalent",
+        ValueHint::Username => "_users",
+        ValueHint::Hostname => "_hosts",
+        ValueHint::Url => "_urls",
+        ValueHint::EmailAddress => "_email_addresses",
+        _ => " ",
+    }
+}
+
+/// The zsh completion action for the value of `key`, without value hints
+#[cfg(not(feature = "clap"))]
+fn zsh_action(_key: &KeyInfo) -> &'static str {
+    " "
+}
+
+/// Generate a nushell completer for the keys of `T`, for the option `--long` of `bin`.
+///
+/// The completer is named `nu-complete <bin> <long>`, and completes the keys of the segment
+/// being typed. Reference it in the `extern` signature of the command, like
+/// `--buf: string@"nu-complete myapp buf"`. Segments are split at the
+/// [separators](SubOpt::SEPARATOR) of `T`.
+///
+/// ```
+/// use clap_subopt_parser::{completion, Delimited, KvMap};
+///
+/// let nu = completion::nushell::<Delimited<KvMap, ',', '='>>("myapp", "env");
+/// assert!(nu.contains(r#"str replace --regex "[^,]*$" """#));
+/// ```
+pub fn nushell<T: SubOpt>(bin: &str, long: &str) -> String {
+    render_nushell(bin, long, &T::keys(), (T::SEPARATOR, T::KV_SEPARATOR))
+}
+
+/// Generate a nushell completer for `keys`, see [`nushell`]
+///
+/// `seps` are the separators between sub-options and between keys and values, like
+/// `(T::SEPARATOR, T::KV_SEPARATOR)`.
+///
+/// ```
+/// use clap_subopt_parser::{completion, KeyInfo};
+///
+/// let keys = [
+///     KeyInfo::new("source").value_name("INDEX").help("Source buffer"),
+///     KeyInfo::new("ro"),
+/// ];
+/// assert_eq!(
+///     completion::render_nushell("myapp", "buf", &keys, (':', '=')),
+///     r#"def "nu-complete myapp buf" [context: string] {
+///     let done = ($context | split row " " | last | str replace --regex "[^:]*$" "")
+///     [
+///         { value: "source=", description: "Source buffer" }
+///         { value: "ro" }
+///     ] | each {|it| $it | update value $"($done)($it.value)" }
+/// }
+/// "#
+/// );
+/// ```
+pub fn render_nushell(bin: &str, long: &str, keys: &[KeyInfo], seps: (char, char)) -> String {
+    let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let class = match seps.0 {
+        c @ ('\\' | ']' | '^' | '-' | '[') => format!("\\{}", c),
+        c => c.to_string(),
+    };
+    let mut out = format!(
+        "def \"nu-complete {} {}\" [context: string] {{\n    \
+         let done = ($context | split row \" \" | last | str replace --regex \"[^{}]*$\" \"\")\n    [\n",
+        escape(bin),
+        escape(long),
+        escape(&class)
+    );
+    for key in keys.iter().filter(|k| !k.hidden) {
+        let eq = match key.value_name {
+            Some(_) => seps.1.to_string(),
+            None => String::new(),
+        };
+        out += &format!("        {{ value: \"{}{}\"", escape(key.name), escape(&eq));
+        if let Some(help) = key.help {
+            out += &format!(", description: \"{}\"", escape(help));
+        }
+        out += " }\n";
+    }
+    out += "    ] | each {|it| $it | update value $\"($done)($it.value)\" }\n}\n";
+    out
+}
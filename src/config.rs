@@ -0,0 +1,186 @@
+use crate::{
+    fallback_error, update, KeyTable, Provenance, Source, SubOpt, SubOptError, SubOptParser,
+};
+use std::path::Path;
+
+/// A value from a configuration file
+enum ConfigValue {
+    Bool(bool),
+    Str(String),
+}
+
+impl<T: SubOpt> SubOptParser<T> {
+    /// Parse `s` on top of the values from the configuration file at `path`, recording where
+    /// the values came from.
+    ///
+    /// The file is a TOML table with the `toml` feature, or a JSON object with the `json`
+    /// feature, depending on its extension. Its entries are applied like sub-options before
+    /// the argument, so the argument overrides them: strings and numbers are values, `true` and
+    /// `false` set and skip keys without a [value](crate::KeyInfo::value_name), arrays are
+    /// joined with `,`, and tables are put in square brackets, like `[host=localhost,port=80]`.
+    ///
+    /// The keys set in the file are recorded as [`Source::File`], and are not taken from
+    /// [environment fallbacks](crate::KeyInfo::env) or prompted for. `None` applies only the
+    /// file. Errors in the values of the file are [`SubOptError::InFile`] errors naming it.
+    ///
+    /// ```
+    /// # #[cfg(feature = "toml")] {
+    /// use clap_subopt_parser::{KvMap, Source, SubOptParser};
+    ///
+    /// let path = std::env::temp_dir().join("clap-subopt-parser-buf.toml");
+    /// std::fs::write(&path, "source = 2\noffset = 4096\nsync = true\n").unwrap();
+    ///
+    /// let parser = SubOptParser::<KvMap>::default();
+    /// let (buf, provenance) = parser.parse_with_config(&path, Some("offset=0")).unwrap();
+    /// assert_eq!((buf.pairs["source"].as_str(), buf.pairs["offset"].as_str()), ("2", "0"));
+    /// assert_eq!(buf.values, ["sync"]);
+    /// assert_eq!(provenance.source("source"), Some(&Source::File(path.clone())));
+    /// assert!(provenance.is_explicit("offset"));
+    /// # }
+    /// ```
+    ///
+    /// ```
+    /// # #[cfg(feature = "toml")] {
+    /// use clap_subopt_parser::{subopt, SubOptError, SubOptParser};
+    ///
+    /// subopt! {
+    ///     struct Buf {
+    ///         offset: u64,
+    ///     }
+    /// }
+    ///
+    /// let path = std::env::temp_dir().join("clap-subopt-parser-bad.toml");
+    /// std::fs::write(&path, "offset = \"x\"\n").unwrap();
+    ///
+    /// let parser = SubOptParser::<Buf>::default();
+    /// let err = parser.parse_with_config(&path, None).map(drop).unwrap_err();
+    /// assert!(matches!(&err, SubOptError::InFile { path: p, .. } if *p == path));
+    /// assert_eq!((err.key(), err.value()), (Some("offset"), Some("x")));
+    /// assert!(err.to_string().starts_with(&format!("In config file '{}'", path.display())));
+    /// # }
+    /// ```
+    pub fn parse_with_config(
+        &self,
+        path: impl AsRef<Path>,
+        s: Option<&str>,
+    ) -> Result<(T, Provenance), SubOptError> {
+        let path = path.as_ref();
+        let keys = &KeyTable::cached::<T>().keys;
+        let mut val = self.init.create();
+        let mut provenance = Provenance::default();
+        for (k, v) in read_config(path)? {
+            let key = keys.iter().find(|key| key.matches(&k));
+            let flag = key.map_or(true, |key| key.value_name.is_none());
+            let value = match v {
+                ConfigValue::Bool(true) if flag => None,
+                ConfigValue::Bool(false) if flag => continue,
+                ConfigValue::Bool(b) => Some(b.to_string()),
+                ConfigValue::Str(v) => Some(v),
+            };
+            let source = Source::File(path.to_owned());
+            update(&mut val, &k, value.as_deref()).map_err(|e| {
+                let secret = key.is_some_and(|key| key.secret);
+                fallback_error(e, (&k, secret), value.as_deref(), T::KV_SEPARATOR, &source)
+            })?;
+            match key {
+                Some(key) => provenance.record(key.name, source),
+                None => provenance.record(k, source),
+            }
+        }
+        let parser = self.clone().allow_empty(self.allow_empty || s.is_none());
+        parser
+            .parse_from(val, provenance, s.unwrap_or_default(), update)
+            .map_err(|e| self.with_key_messages(e))
+    }
+}
+
+/// Read the entries of the configuration file at `path`
+fn read_config(path: &Path) -> Result<Vec<(String, ConfigValue)>, SubOptError> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        SubOptError::Custom(format!(
+            "Cannot read config file '{}': {}",
+            path.display(),
+            e
+        ))
+    })?;
+    let invalid = |e: &dyn std::fmt::Display| {
+        SubOptError::Custom(format!("Invalid config file '{}': {}", path.display(), e))
+    };
+    match path.extension().and_then(|ext| ext.to_str()) {
+        #[cfg(feature = "toml")]
+        Some("toml") => {
+            let table: toml::Table = text.parse().map_err(|e| invalid(&e))?;
+            Ok(table.into_iter().map(|(k, v)| (k, toml_value(v))).collect())
+        }
+        #[cfg(feature = "json")]
+        Some("json") => {
+            let object: serde_json::Map<String, serde_json::Value> =
+                serde_json::from_str(&text).map_err(|e| invalid(&e))?;
+            Ok(object
+                .into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, json_value(v)))
+                .collect())
+        }
+        _ => Err(invalid(&"unsupported format")),
+    }
+}
+
+#[cfg(feature = "toml")]
+fn toml_value(v: toml::Value) -> ConfigValue {
+    match v {
+        toml::Value::Boolean(b) => ConfigValue::Bool(b),
+        toml::Value::String(s) => ConfigValue::Str(s),
+        toml::Value::Array(items) => ConfigValue::Str(
+            items
+                .into_iter()
+                .map(|item| toml_value(item).into_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        toml::Value::Table(table) => ConfigValue::Str(format!(
+            "[{}]",
+            table
+                .into_iter()
+                .map(|(k, v)| format!("{}={}", k, toml_value(v).into_string()))
+                .collect::<Vec<_>>()
+                .join(",")
+        )),
+        toml::Value::Integer(i) => ConfigValue::Str(i.to_string()),
+        toml::Value::Float(f) => ConfigValue::Str(f.to_string()),
+        toml::Value::Datetime(d) => ConfigValue::Str(d.to_string()),
+    }
+}
+
+#[cfg(feature = "json")]
+fn json_value(v: serde_json::Value) -> ConfigValue {
+    match v {
+        serde_json::Value::Bool(b) => ConfigValue::Bool(b),
+        serde_json::Value::String(s) => ConfigValue::Str(s),
+        serde_json::Value::Array(items) => ConfigValue::Str(
+            items
+                .into_iter()
+                .map(|item| json_value(item).into_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        serde_json::Value::Object(object) => ConfigValue::Str(format!(
+            "[{}]",
+            object
+                .into_iter()
+                .map(|(k, v)| format!("{}={}", k, json_value(v).into_string()))
+                .collect::<Vec<_>>()
+                .join(",")
+        )),
+        v => ConfigValue::Str(v.to_string()),
+    }
+}
+
+impl ConfigValue {
+    fn into_string(self) -> String {
+        match self {
+            ConfigValue::Bool(b) => b.to_string(),
+            ConfigValue::Str(s) => s,
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{KeyInfo, SubOpt, SubOptError, SubOptParser};
+use std::sync::Arc;
+
+/// A variant of [`SubOpt`] whose methods receive a context, for values that depend on runtime
+/// state, like the names of the registered devices.
+///
+/// Parse it with [`SubOptParser::with_context`], which yields [`WithContext`] values.
+///
+/// ```
+/// use clap_subopt_parser::{SubOptError, SubOptParser, SubOptWithContext, WithContext};
+/// use std::sync::Arc;
+///
+/// struct AppConfig {
+///     devices: Vec<String>,
+/// }
+///
+/// #[derive(Debug, Default)]
+/// struct Mount {
+///     device: String,
+/// }
+///
+/// impl SubOptWithContext for Mount {
+///     type Context = AppConfig;
+///
+///     fn update_from_value(&mut self, _: &AppConfig, k: &str) -> Result<(), SubOptError> {
+///         Err(SubOptError::UnknownKey(k.into()))
+///     }
+///     fn update_from_kvpair(
+///         &mut self,
+///         config: &AppConfig,
+///         k: &str,
+///         v: &str,
+///     ) -> Result<(), SubOptError> {
+///         match k {
+///             "device" if config.devices.iter().any(|d| d == v) => self.device = v.into(),
+///             "device" => return Err(SubOptError::invalid_value(k, "no such device")),
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let config = Arc::new(AppConfig { devices: vec!["sda".into()] });
+/// let parser = SubOptParser::<WithContext<Mount>>::with_context(config);
+/// assert_eq!(parser.parse("device=sda").unwrap().value.device, "sda");
+/// assert!(parser.parse("device=sdb").is_err());
+/// ```
+///
+/// The parser holds the context until it and its clones are dropped.
+///
+/// ```
+/// # use clap_subopt_parser::{SubOptError, SubOptParser, SubOptWithContext, WithContext};
+/// # use std::sync::Arc;
+/// # #[derive(Default)] struct Mount;
+/// # impl SubOptWithContext for Mount {
+/// #     type Context = Vec<String>;
+/// #     fn update_from_value(&mut self, _: &Vec<String>, _: &str) -> Result<(), SubOptError> {
+/// #         Ok(())
+/// #     }
+/// #     fn update_from_kvpair(&mut self, _: &Vec<String>, _: &str, _: &str) -> Result<(), SubOptError> {
+/// #         Ok(())
+/// #     }
+/// # }
+/// let devices = Arc::new(vec!["sda".to_owned()]);
+/// let parser = SubOptParser::<WithContext<Mount>>::with_context(devices.clone());
+/// let mount = parser.parse("device=sda").unwrap();
+/// drop((parser, mount));
+/// assert_eq!(Arc::strong_count(&devices), 1);
+/// ```
+pub trait SubOptWithContext: Send + Sync + 'static {
+    /// The context
+    type Context: Send + Sync + 'static;
+    /// Like [`SubOpt::update_from_value`], with the context
+    fn update_from_value(&mut self, context: &Self::Context, k: &str) -> Result<(), SubOptError>;
+    /// Like [`SubOpt::update_from_kvpair`], with the context
+    fn update_from_kvpair(
+        &mut self,
+        context: &Self::Context,
+        k: &str,
+        v: &str,
+    ) -> Result<(), SubOptError>;
+    /// Like [`SubOpt::keys`]
+    fn keys() -> Vec<KeyInfo> {
+        Vec::new()
+    }
+}
+
+/// A [`SubOptWithContext`] value with its context, which implements [`SubOpt`]
+pub struct WithContext<T: SubOptWithContext> {
+    /// The parsed value
+    pub value: T,
+    context: Arc<T::Context>,
+}
+
+impl<T: SubOptWithContext> WithContext<T> {
+    /// Wrap `value`, to be parsed with `context`
+    pub fn new(value: T, context: Arc<T::Context>) -> Self {
+        Self { value, context }
+    }
+    /// The context
+    pub fn context(&self) -> &T::Context {
+        &self.context
+    }
+    /// The parsed value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: SubOptWithContext + Clone> Clone for WithContext<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value.clone(),
+            context: self.context.clone(),
+        }
+    }
+}
+
+impl<T: SubOptWithContext + std::fmt::Debug> std::fmt::Debug for WithContext<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("WithContext")
+            .field("value", &self.value)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: SubOptWithContext> SubOpt for WithContext<T> {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        self.value.update_from_value(&self.context, k)
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        self.value.update_from_kvpair(&self.context, k, v)
+    }
+    fn keys() -> Vec<KeyInfo> {
+        T::keys()
+    }
+}
+
+impl<T: SubOptWithContext + Default> SubOptParser<WithContext<T>> {
+    /// Create a parser for `T`, passing `context` to its methods.
+    ///
+    /// Parsing starts from `T::default()`.
+    pub fn with_context(context: Arc<T::Context>) -> Self {
+        Self::with_factory(move || WithContext::new(T::default(), context.clone()))
+    }
+}
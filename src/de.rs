@@ -0,0 +1,451 @@
+use crate::{parse_kv, tokenize, values, SubOptError};
+#[cfg(feature = "clap")]
+use clap::builder::TypedValueParser;
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+use std::marker::PhantomData;
+use std::ops::Range;
+
+/// A parser for any [`Deserialize`](serde::Deserialize) type, without implementing
+/// [`SubOpt`](crate::SubOpt).
+///
+/// The sub-options are deserialized as a map from keys to values. Values are parsed according
+/// to the type of the field, and bare values are `true`, for `bool` fields. Nested structs and
+/// maps are given in square brackets, like `net=[host=localhost:port=80]`, and sequences are
+/// separated by `,`. Strings can be bracketed too, to contain separators, like
+/// `addr=[127.0.0.1:80]`.
+///
+/// Like with serde in general, unknown keys are ignored unless the type has
+/// `#[serde(deny_unknown_fields)]`.
+///
+/// ```
+/// use clap_subopt_parser::SerdeSubOptParser;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Clone, Deserialize)]
+/// #[serde(deny_unknown_fields)]
+/// struct Buf {
+///     source: usize,
+///     #[serde(default)]
+///     offset: u64,
+///     #[serde(default)]
+///     ro: bool,
+///     #[serde(default)]
+///     tags: Vec<String>,
+///     #[serde(default)]
+///     addr: String,
+/// }
+///
+/// let parser = SerdeSubOptParser::<Buf>::new();
+/// let buf = parser.parse("source=1:ro:tags=a,b").unwrap();
+/// assert_eq!((buf.source, buf.offset, buf.ro), (1, 0, true));
+/// assert_eq!(buf.tags, ["a", "b"]);
+/// let buf = parser.parse("source=1:addr=[127.0.0.1:80]").unwrap();
+/// assert_eq!(buf.addr, "127.0.0.1:80");
+/// assert_eq!(
+///     parser.parse("offset=4").unwrap_err().to_string(),
+///     "Missing required key 'source'"
+/// );
+/// assert!(parser.parse("source=1:size=4").is_err());
+/// ```
+///
+/// With the `toml` feature, arguments starting with `{` are parsed as TOML inline tables
+/// instead, like `{source = 0, offset = 1000, net = {port = 80}}`. With the `json` feature,
+/// they are parsed as JSON objects, like `{"source": 0, "offset": 1000}`, which is convenient
+/// for generated command lines. With the `yaml` feature, they are parsed as YAML flow
+/// mappings, like `{source: 0, offset: 1000}`. With several of these features, the format is
+/// told apart by the separator after the first key, and JSON keys being quoted.
+///
+/// ```
+/// use clap_subopt_parser::SerdeSubOptParser;
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Net {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Buf {
+///     source: usize,
+///     net: Net,
+/// }
+///
+/// let parser = SerdeSubOptParser::<Buf>::new();
+/// # #[cfg(feature = "toml")] {
+/// let buf = parser.parse(r#"{source = 0, net = {host = "localhost", port = 80}}"#).unwrap();
+/// assert_eq!((buf.source, buf.net.host.as_str(), buf.net.port), (0, "localhost", 80));
+/// assert!(parser.parse("{source = 0}").is_err());
+/// # }
+/// # #[cfg(feature = "json")] {
+/// let buf = parser.parse(r#"{"source": 1, "net": {"host": "::1", "port": 443}}"#).unwrap();
+/// assert_eq!((buf.source, buf.net.host.as_str(), buf.net.port), (1, "::1", 443));
+/// # }
+/// # #[cfg(feature = "yaml")] {
+/// let buf = parser.parse("{source: 2, net: {host: example.com, port: 8080}}").unwrap();
+/// assert_eq!((buf.source, buf.net.host.as_str(), buf.net.port), (2, "example.com", 8080));
+/// # }
+/// ```
+pub struct SerdeSubOptParser<T> {
+    _opt: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for SerdeSubOptParser<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SerdeSubOptParser<T> {}
+
+impl<T> Default for SerdeSubOptParser<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> std::fmt::Debug for SerdeSubOptParser<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SerdeSubOptParser")
+            .field("type", &std::any::type_name::<T>())
+            .finish()
+    }
+}
+
+impl<T> SerdeSubOptParser<T> {
+    /// Create a parser
+    pub const fn new() -> Self {
+        Self { _opt: PhantomData }
+    }
+}
+
+impl<T: DeserializeOwned> SerdeSubOptParser<T> {
+    /// Parse `s`, without clap
+    pub fn parse(&self, s: &str) -> Result<T, SubOptError> {
+        let trimmed = s.trim();
+        if trimmed.is_empty() {
+            return Err(SubOptError::EmptyArgument);
+        }
+        #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+        if trimmed.starts_with('{') {
+            return parse_inline(trimmed);
+        }
+        T::deserialize(MapDeserializer { s, nested: false })
+    }
+}
+
+/// Parse the inline object `s`, which starts with `{`, in the format it is written in, out of
+/// the enabled ones.
+///
+/// JSON objects have a quoted first key followed by `:`, and TOML inline tables separate the
+/// first key from its value with `=`. Anything else is YAML.
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+fn parse_inline<T: DeserializeOwned>(s: &str) -> Result<T, SubOptError> {
+    #[cfg(feature = "json")]
+    if !cfg!(any(feature = "toml", feature = "yaml"))
+        || s[1..].trim_start().starts_with('"') && first_kv_sep(s) == Some(':')
+    {
+        return serde_json::from_str(s)
+            .map_err(|e| SubOptError::Custom(format!("Invalid JSON object: {}", e)));
+    }
+    #[cfg(feature = "toml")]
+    if !cfg!(feature = "yaml") || first_kv_sep(s) == Some('=') {
+        return T::deserialize(toml::de::ValueDeserializer::new(s))
+            .map_err(|e| SubOptError::Custom(format!("Invalid TOML table: {}", e.message())));
+    }
+    #[cfg(feature = "yaml")]
+    return serde_yaml::from_str(s)
+        .map_err(|e| SubOptError::Custom(format!("Invalid YAML mapping: {}", e)));
+    #[cfg(not(feature = "yaml"))]
+    unreachable!("JSON or TOML is the fallback without YAML")
+}
+
+/// The first `=` or `:` in `s`
+#[cfg(any(feature = "json", feature = "toml"))]
+fn first_kv_sep(s: &str) -> Option<char> {
+    s.chars().find(|&c| c == '=' || c == ':')
+}
+
+#[cfg(feature = "clap")]
+impl<T: DeserializeOwned + Clone + Send + Sync + 'static> TypedValueParser
+    for SerdeSubOptParser<T>
+{
+    type Value = T;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        self.parse(crate::to_str(value))
+            .map_err(|e| e.format(cmd, arg))
+    }
+}
+
+impl de::Error for SubOptError {
+    fn custom<M: std::fmt::Display>(msg: M) -> Self {
+        SubOptError::Custom(msg.to_string())
+    }
+    fn unknown_field(field: &str, _expected: &'static [&'static str]) -> Self {
+        SubOptError::UnknownKey(field.into())
+    }
+    fn missing_field(field: &'static str) -> Self {
+        SubOptError::MissingKey(field.into())
+    }
+}
+
+/// Deserializes the sub-options of an argument, or of a bracketed value, as a map
+struct MapDeserializer<'a> {
+    s: &'a str,
+    /// Whether this is a bracketed value, whose errors are put in context by the outer map
+    nested: bool,
+}
+
+impl<'de> de::Deserializer<'de> for MapDeserializer<'de> {
+    type Error = SubOptError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        visitor.visit_map(MapAccess {
+            arg: (!self.nested).then_some(self.s),
+            segments: tokenize(self.s, crate::DEFAULT_SEPARATOR, '='),
+            value: None,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum
+        identifier ignored_any
+    }
+}
+
+/// The sub-options of a map
+struct MapAccess<'a, I> {
+    /// The argument, for putting errors in context, unless this is a bracketed value
+    arg: Option<&'a str>,
+    segments: I,
+    value: Option<(Range<usize>, &'a str, Option<&'a str>)>,
+}
+
+impl<'a, I> MapAccess<'a, I> {
+    fn in_segment(&self, e: SubOptError, span: Range<usize>) -> SubOptError {
+        match self.arg {
+            Some(arg) => e.in_segment(arg, span, '='),
+            None => e,
+        }
+    }
+}
+
+impl<'de, I> de::MapAccess<'de> for MapAccess<'de, I>
+where
+    I: Iterator<Item = (Range<usize>, &'de str, Option<&'de str>)>,
+{
+    type Error = SubOptError;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, SubOptError> {
+        let Some((span, k, v)) = self.segments.next() else {
+            return Ok(None);
+        };
+        self.value = Some((span.clone(), k, v));
+        seed.deserialize(k.into_deserializer())
+            .map(Some)
+            .map_err(|e| self.in_segment(e, span))
+    }
+
+    fn next_value_seed<V: de::DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, SubOptError> {
+        let (span, key, value) = self.value.take().expect("a key before its value");
+        seed.deserialize(ValueDeserializer { key, value })
+            .map_err(|e| self.in_segment(e, span))
+    }
+}
+
+/// Deserializes the value of a key, parsing it according to the type that is asked for
+struct ValueDeserializer<'a> {
+    key: &'a str,
+    value: Option<&'a str>,
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn value(&self) -> Result<&'a str, SubOptError> {
+        self.value
+            .ok_or_else(|| SubOptError::MissingValueForKey(self.key.into()))
+    }
+    fn parse<T>(&self) -> Result<T, SubOptError>
+    where
+        T: std::str::FromStr,
+        T::Err: std::fmt::Display,
+    {
+        parse_kv(self.key, self.value()?)
+    }
+    /// The value, without the square brackets around it, if any
+    fn unbracketed(&self) -> Result<&'a str, SubOptError> {
+        let value = self.value()?;
+        Ok(value
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .unwrap_or(value))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = SubOptError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        match self.value {
+            Some(value) => visitor.visit_borrowed_str(value),
+            None => visitor.visit_bool(true),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        match self.value {
+            Some(value) => visitor.visit_bool(values::boolean(self.key, value)?),
+            None => visitor.visit_bool(true),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        visitor.visit_borrowed_str(self.unbracketed()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        visitor.visit_borrowed_bytes(self.value()?.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SubOptError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, SubOptError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        let key = self.key;
+        let items = self
+            .unbracketed()?
+            .split(',')
+            .filter(|item| !item.is_empty())
+            .map(|item| ValueDeserializer {
+                key,
+                value: Some(item),
+            });
+        let mut items = de::value::SeqDeserializer::new(items);
+        let value = visitor.visit_seq(&mut items)?;
+        items.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SubOptError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, SubOptError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        let s = self.unbracketed()?;
+        MapDeserializer { s, nested: true }.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SubOptError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, SubOptError> {
+        visitor.visit_enum(self.value()?.into_deserializer())
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, SubOptError> {
+        visitor.visit_unit()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, SubOptError> for ValueDeserializer<'de> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
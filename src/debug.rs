@@ -0,0 +1,48 @@
+use crate::SubOptError;
+use std::sync::OnceLock;
+
+/// Whether `CLAP_SUBOPT_DEBUG` is set to something other than `0` or nothing
+pub(crate) fn enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var_os("CLAP_SUBOPT_DEBUG").is_some_and(|v| !v.is_empty() && v != "0")
+    })
+}
+
+/// Print the start of parsing an argument of type `T`
+pub(crate) fn start<T>(len: usize, sep: char, kv_sep: char) {
+    eprintln!(
+        "clap-subopt-parser: parsing {} bytes as {}, separated by {:?}, keys by {:?}",
+        len,
+        std::any::type_name::<T>(),
+        sep,
+        kv_sep
+    );
+}
+
+/// Print how a sub-option was dispatched, with the value of secret keys hidden
+pub(crate) fn segment(
+    index: usize,
+    key: &str,
+    value: Option<&str>,
+    secret: bool,
+    result: &Result<(), SubOptError>,
+) {
+    let value = match value {
+        Some(_) if secret => "=***".to_owned(),
+        Some(value) => format!("={:?}", value),
+        None => String::new(),
+    };
+    let method = match value.is_empty() {
+        true => "update_from_value",
+        false => "update_from_kvpair",
+    };
+    let outcome = match result {
+        Ok(()) => "ok".to_owned(),
+        Err(e) => format!("error: {}", e),
+    };
+    eprintln!(
+        "clap-subopt-parser:   #{} {:?}{} -> {}: {}",
+        index, key, value, method, outcome
+    );
+}
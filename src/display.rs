@@ -0,0 +1,106 @@
+use crate::{split_protected, SubOpt};
+
+/// Formatting [`SubOpt`] values as arguments that parse back to an equal value, so they can be
+/// logged, persisted, and given to the program again.
+///
+/// Implementations list the [sub-options](SubOptDisplay::sub_options) of the value, and
+/// [`to_arg_string`](SubOptDisplay::to_arg_string) joins them with the separators of the
+/// type. Values containing the separator are enclosed in square brackets, like
+/// `addr=[127.0.0.1:80]`, so such keys must be parsed with functions that remove the brackets,
+/// like the ones in [`values`](crate::values).
+///
+/// ```
+/// use clap_subopt_parser::{parse_kv, parse_str, KeyInfo, SubOpt, SubOptDisplay, SubOptError};
+///
+/// #[derive(Debug, Default, PartialEq)]
+/// struct Login {
+///     user: String,
+///     password: String,
+///     sync: bool,
+/// }
+///
+/// impl SubOpt for Login {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "sync" => self.sync = true,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "user" => self.user = parse_kv(k, v)?,
+///             "password" => self.password = parse_kv(k, v)?,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+///     fn keys() -> Vec<KeyInfo> {
+///         vec![
+///             KeyInfo::new("user").value_name("NAME"),
+///             KeyInfo::new("password").value_name("PASSWORD").secret(true),
+///             KeyInfo::new("sync"),
+///         ]
+///     }
+/// }
+///
+/// impl SubOptDisplay for Login {
+///     fn sub_options(&self) -> Vec<(&'static str, Option<String>)> {
+///         let mut opts = vec![
+///             ("user", Some(self.user.clone())),
+///             ("password", Some(self.password.clone())),
+///         ];
+///         if self.sync {
+///             opts.push(("sync", None));
+///         }
+///         opts
+///     }
+/// }
+///
+/// let login = Login { user: "admin".into(), password: "hunter2".into(), sync: true };
+/// assert_eq!(login.to_arg_string(), "user=admin:password=hunter2:sync");
+/// assert_eq!(parse_str::<Login>(&login.to_arg_string()).unwrap(), login);
+/// assert_eq!(login.to_redacted_arg_string(), "user=admin:password=***:sync");
+/// ```
+pub trait SubOptDisplay: SubOpt {
+    /// The sub-options that make up the value, as keys and values, with `None` for bare values
+    fn sub_options(&self) -> Vec<(&'static str, Option<String>)>;
+    /// Format the value as an argument
+    fn to_arg_string(&self) -> String {
+        join::<Self>(self.sub_options(), |_| false)
+    }
+    /// Format the value as an argument, with the values of [secret](crate::KeyInfo::secret) keys
+    /// replaced by `***`.
+    ///
+    /// This is meant for logging, the result doesn't parse back to the same value.
+    fn to_redacted_arg_string(&self) -> String {
+        let keys = Self::keys();
+        join::<Self>(self.sub_options(), |k| {
+            keys.iter().any(|key| key.secret && key.matches(k))
+        })
+    }
+}
+
+fn join<T: SubOpt + ?Sized>(
+    opts: Vec<(&'static str, Option<String>)>,
+    secret: impl Fn(&str) -> bool,
+) -> String {
+    let mut out = String::new();
+    for (k, v) in opts {
+        if !out.is_empty() {
+            out.push(T::SEPARATOR);
+        }
+        out += k;
+        if let Some(v) = v {
+            out.push(T::KV_SEPARATOR);
+            if secret(k) {
+                out += "***";
+            } else if split_protected(&v, T::SEPARATOR).nth(1).is_some() {
+                out += &format!("[{}]", v);
+            } else {
+                out += &v;
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,370 @@
+//! Documentation generators for [`KeyInfo`] metadata, and example command lines.
+
+use crate::{keys::grouped, KeyInfo, SubOpt, SubOptDisplay};
+use std::borrow::Cow;
+
+/// Render the keys of `T` as a man page subsection for the argument `arg`.
+///
+/// The output is roff, in the style of clap_mangen's option list, and can be appended to
+/// its output. Keys with a [category](KeyInfo::category) are listed under an italic heading.
+pub fn man<T: SubOpt>(arg: &str) -> String {
+    render_man(arg, &T::keys())
+}
+
+/// Render `keys` as a man page subsection, see [`man`]
+///
+/// ```
+/// use clap_subopt_parser::{docs, KeyInfo};
+///
+/// let keys = [KeyInfo::new("offset").value_name("BYTES").help("Offset").default("0")];
+/// assert_eq!(
+///     docs::render_man("--buf", &keys),
+///     ".SS \"Sub-options for \\-\\-buf\"\n.TP\n\\fBoffset\\fR=\\fIBYTES\\fR\nOffset [default: 0]\n"
+/// );
+/// ```
+pub fn render_man(arg: &str, keys: &[KeyInfo]) -> String {
+    let mut out = format!(".SS \"Sub-options for {}\"\n", roff_escape(arg));
+    for (category, keys) in grouped(keys) {
+        if let Some(category) = category {
+            out += &format!(".PP\n\\fI{}\\fR\n", roff_escape(category));
+        }
+        for key in keys {
+            out += &man_key(key);
+        }
+    }
+    out
+}
+
+/// A `.TP` paragraph for `key`
+fn man_key(key: &KeyInfo) -> String {
+    let mut out = ".TP\n".to_owned();
+    out += &format!("\\fB{}\\fR", roff_escape(key.name));
+    if let Some(value_name) = key.value_name {
+        out += &format!("=\\fI{}\\fR", roff_escape(value_name));
+    }
+    out += "\n";
+    let text = format!("{}{}", key.help.unwrap_or(""), key.annotations());
+    let text = text.trim_start();
+    if !text.is_empty() {
+        if text.starts_with(['.', '\'']) {
+            out += "\\&";
+        }
+        out += &roff_escape(text);
+        out += "\n";
+    }
+    out
+}
+
+fn roff_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('-', "\\-")
+}
+
+/// Render the keys of `T` as a Markdown section for the argument `arg`
+pub fn markdown<T: SubOpt>(arg: &str) -> String {
+    render_markdown(arg, &T::keys())
+}
+
+/// Render `keys` as a Markdown section, see [`markdown`]
+///
+/// Keys with a [category](KeyInfo::category) get their own table, under a heading.
+///
+/// ```
+/// use clap_subopt_parser::{docs, KeyInfo};
+///
+/// let keys = [KeyInfo::new("offset").aliases(&["off"]).value_name("BYTES").default("0")];
+/// assert_eq!(
+///     docs::render_markdown("--buf", &keys),
+///     "### Sub-options for `--buf`
+///
+/// | Key | Value | Required | Default | Description |
+/// |-----|-------|----------|---------|-------------|
+/// | `offset`, `off` | `BYTES` | no | `0` |  |
+/// "
+/// );
+/// ```
+pub fn render_markdown(arg: &str, keys: &[KeyInfo]) -> String {
+    let code = |s: &str| format!("`{}`", s);
+    let mut out = format!("### Sub-options for {}\n", code(arg));
+    for (category, keys) in grouped(keys) {
+        if let Some(category) = category {
+            out += &format!("\n#### {}\n", markdown_escape(category));
+        }
+        out += "\n| Key | Value | Required | Default | Description |\n";
+        out += "|-----|-------|----------|---------|-------------|\n";
+        for key in keys {
+            let names = std::iter::once(&key.name)
+                .chain(key.aliases)
+                .map(|name| code(name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let cells = [
+                names,
+                key.value_name.map(code).unwrap_or_default(),
+                if key.required { "yes" } else { "no" }.to_owned(),
+                key.default.map(code).unwrap_or_default(),
+                match key.since {
+                    Some(since) => format!("{} (since {})", key.help.unwrap_or_default(), since)
+                        .trim_start()
+                        .to_owned(),
+                    None => key.help.unwrap_or_default().to_owned(),
+                },
+            ];
+            out += "|";
+            for cell in cells {
+                out += &format!(" {} |", markdown_escape(&cell));
+            }
+            out += "\n";
+        }
+    }
+    out
+}
+
+fn markdown_escape(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+/// A shell to quote example command lines for, see [`example_command`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Shell {
+    /// POSIX shells, like bash and zsh
+    Sh,
+    /// PowerShell
+    PowerShell,
+    /// The Windows command prompt
+    Cmd,
+}
+
+/// Render a command line running `bin` with the argument `arg` set to each of `values`, quoted
+/// for `shell`, for examples in documentation and error hints generated from real values.
+///
+/// The values are formatted with [`SubOptDisplay::to_arg_string`], so they parse back to
+/// the same values. This includes the values of [secret](KeyInfo::secret) keys, so only use
+/// values without real secrets.
+///
+/// ```
+/// use clap_subopt_parser::docs::{example_command, Shell};
+/// use clap_subopt_parser::{SubOpt, SubOptDisplay, SubOptError};
+///
+/// struct Cache {
+///     name: String,
+///     path: String,
+/// }
+/// # impl SubOpt for Cache {
+/// #     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> { Ok(()) }
+/// #     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> { Ok(()) }
+/// # }
+///
+/// impl SubOptDisplay for Cache {
+///     fn sub_options(&self) -> Vec<(&'static str, Option<String>)> {
+///         vec![("name", Some(self.name.clone())), ("path", Some(self.path.clone()))]
+///     }
+/// }
+///
+/// let caches = [
+///     Cache { name: "my cache".into(), path: "C:\\Cache".into() },
+///     Cache { name: "tmp".into(), path: "/tmp".into() },
+/// ];
+/// assert_eq!(
+///     example_command("myapp", "--cache", &caches, Shell::Sh),
+///     "myapp --cache 'name=my cache:path=[C:\\Cache]' --cache name=tmp:path=/tmp"
+/// );
+/// assert_eq!(
+///     example_command("myapp", "--cache", &caches[..1], Shell::Cmd),
+///     "myapp --cache \"name=my cache:path=[C:\\Cache]\""
+/// );
+/// ```
+pub fn example_command<T: SubOptDisplay>(
+    bin: &str,
+    arg: &str,
+    values: &[T],
+    shell: Shell,
+) -> String {
+    let mut out = quote(bin, shell).into_owned();
+    for value in values {
+        out.push(' ');
+        out += &quote(arg, shell);
+        out.push(' ');
+        out += &quote(&value.to_arg_string(), shell);
+    }
+    out
+}
+
+/// Quote `word` as a single argument for `shell`, if needed.
+///
+/// The command prompt can't escape `%` inside quotes, so `%NAME%` is still expanded if the
+/// variable `NAME` is set.
+///
+/// ```
+/// use clap_subopt_parser::docs::{quote, Shell};
+///
+/// assert_eq!(quote("offset=4:sync", Shell::Sh), "offset=4:sync");
+/// assert_eq!(quote("label=it's", Shell::Sh), "'label=it'\\''s'");
+/// assert_eq!(quote("label=it's", Shell::PowerShell), "'label=it''s'");
+/// assert_eq!(quote("label=\"a b\"", Shell::Cmd), "\"label=\\\"a b\\\"\"");
+/// ```
+pub fn quote(word: &str, shell: Shell) -> Cow<'_, str> {
+    let safe = |c: char| match shell {
+        Shell::Sh => c.is_ascii_alphanumeric() || "_-+=:,./@%".contains(c),
+        Shell::PowerShell => c.is_ascii_alphanumeric() || "_-+=:./\\".contains(c),
+        Shell::Cmd => !c.is_whitespace() && !"\"&|<>^()%!".contains(c),
+    };
+    if !word.is_empty() && word.chars().all(safe) {
+        return word.into();
+    }
+    match shell {
+        Shell::Sh => format!("'{}'", word.replace('\'', "'\\''")).into(),
+        Shell::PowerShell => format!("'{}'", word.replace('\'', "''")).into(),
+        Shell::Cmd => {
+            // Backslashes are only special before quotes, see CommandLineToArgvW
+            let mut out = String::from('"');
+            let mut backslashes = 0;
+            for c in word.chars() {
+                match c {
+                    '\\' => backslashes += 1,
+                    '"' => {
+                        out += &"\\".repeat(backslashes * 2 + 1);
+                        backslashes = 0;
+                    }
+                    _ => {
+                        out += &"\\".repeat(backslashes);
+                        backslashes = 0;
+                    }
+                }
+                if c != '\\' {
+                    out.push(c);
+                }
+            }
+            out += &"\\".repeat(backslashes * 2);
+            out.push('"');
+            out.into()
+        }
+    }
+}
+
+/// Generate a JSON Schema describing the keys of `T`, as an object with a property per key
+#[cfg(feature = "json")]
+pub fn json_schema<T: SubOpt>() -> serde_json::Value {
+    render_json_schema(&T::keys())
+}
+
+/// Generate a JSON Schema describing `keys`, see [`json_schema`]
+#[cfg(feature = "json")]
+pub fn render_json_schema(keys: &[KeyInfo]) -> serde_json::Value {
+    let mut properties = serde_json::Map::new();
+    for key in keys {
+        let mut prop = serde_json::Map::new();
+        prop.insert("type".into(), "string".into());
+        if let Some(crate::ValueKind::Enum { variants }) = key.kind {
+            prop.insert("enum".into(), variants.into());
+        }
+        if let Some(help) = key.help {
+            prop.insert("description".into(), help.into());
+        }
+        if let Some(default) = key.default {
+            prop.insert("default".into(), default.into());
+        }
+        properties.insert(key.name.into(), prop.into());
+    }
+    let required = keys
+        .iter()
+        .filter(|key| key.required)
+        .map(|key| key.name)
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// Generate a [schemars](schemars) root schema describing the keys of `T`, as an object with a
+/// property per key.
+///
+/// Unlike [`json_schema`], the properties are typed by their [kind](KeyInfo::kind), and keys
+/// without a value are booleans, so the schema also describes configuration files.
+///
+/// ```
+/// use clap_subopt_parser::{docs, KeyInfo, SubOpt, SubOptError, ValueKind};
+///
+/// #[derive(Default)]
+/// struct Buf;
+///
+/// impl SubOpt for Buf {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> { Ok(()) }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> { Ok(()) }
+///     fn keys() -> Vec<KeyInfo> {
+///         vec![
+///             KeyInfo::new("offset").value_name("BYTES").kind(ValueKind::Int { min: Some(0), max: None }),
+///             KeyInfo::new("sync").help("Write synchronously"),
+///         ]
+///     }
+/// }
+///
+/// let schema = docs::root_schema::<Buf>();
+/// let properties = &schema.schema.object.as_ref().unwrap().properties;
+/// assert!(properties.contains_key("offset"));
+/// assert!(properties.contains_key("sync"));
+/// ```
+#[cfg(feature = "schemars")]
+pub fn root_schema<T: SubOpt>() -> schemars::schema::RootSchema {
+    schemars::schema::RootSchema {
+        meta_schema: Some("http://json-schema.org/draft-07/schema#".into()),
+        schema: schema_object(&T::keys()),
+        definitions: Default::default(),
+    }
+}
+
+/// Generate a [schemars](schemars) schema describing `keys`, see [`root_schema`].
+///
+/// This is for implementing `schemars::JsonSchema` for [`SubOpt`] types, so they are included
+/// in the schemas of the configurations containing them.
+#[cfg(feature = "schemars")]
+pub fn schema_object(keys: &[KeyInfo]) -> schemars::schema::SchemaObject {
+    use schemars::schema::{
+        InstanceType, Metadata, NumberValidation, ObjectValidation, SchemaObject,
+    };
+
+    let mut object = ObjectValidation {
+        additional_properties: Some(Box::new(false.into())),
+        ..Default::default()
+    };
+    for key in keys {
+        let mut prop = SchemaObject::default();
+        match key.kind {
+            Some(crate::ValueKind::Int { min, max }) => {
+                prop.instance_type = Some(InstanceType::Integer.into());
+                prop.number = Some(Box::new(NumberValidation {
+                    minimum: min.map(|min| min as f64),
+                    maximum: max.map(|max| max as f64),
+                    ..Default::default()
+                }));
+            }
+            Some(crate::ValueKind::Bool) => prop.instance_type = Some(InstanceType::Boolean.into()),
+            None if key.value_name.is_none() => {
+                prop.instance_type = Some(InstanceType::Boolean.into())
+            }
+            Some(crate::ValueKind::Enum { variants }) => {
+                prop.instance_type = Some(InstanceType::String.into());
+                prop.enum_values = Some(variants.iter().map(|&v| v.into()).collect());
+            }
+            _ => prop.instance_type = Some(InstanceType::String.into()),
+        }
+        prop.metadata = Some(Box::new(Metadata {
+            description: key.help.map(Into::into),
+            default: key.default.map(Into::into),
+            ..Default::default()
+        }));
+        object.properties.insert(key.name.into(), prop.into());
+        if key.required {
+            object.required.insert(key.name.into());
+        }
+    }
+    SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        object: Some(Box::new(object)),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{SubOpt, SubOptError, SubOptParser, SubOptRegistry};
+use std::path::Path;
+
+/// The variables of a dotenv-style file, whose values are sub-options, like
+/// `MYAPP_BUF=source=0:offset=4`.
+///
+/// This is for deployments that configure everything through env files. Lines are
+/// `NAME=value`, optionally prefixed with `export`, and values can be enclosed in single or
+/// double quotes. Empty lines and lines starting with `#` are ignored, and later lines win for
+/// repeated names.
+///
+/// Parse errors are [`SubOptError::InEnv`] errors naming the variable, like with
+/// [`from_env`](crate::from_env).
+///
+/// ```
+/// use clap_subopt_parser::{DotEnv, KvMap};
+///
+/// let env = DotEnv::from_text(
+///     "# Buffers\n\
+///      MYAPP_BUF=source=0:offset=4\n\
+///      export MYAPP_NET=\"host=localhost:port=80\"\n",
+/// );
+/// let buf = env.parse::<KvMap>("MYAPP_BUF").unwrap().unwrap();
+/// assert_eq!(buf.pairs["offset"], "4");
+/// let net = env.parse::<KvMap>("MYAPP_NET").unwrap().unwrap();
+/// assert_eq!(net.pairs["host"], "localhost");
+/// assert!(env.parse::<KvMap>("MYAPP_CACHE").unwrap().is_none());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DotEnv {
+    vars: Vec<(String, String)>,
+}
+
+impl DotEnv {
+    /// Read the file at `path`
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        std::fs::read_to_string(path).map(|text| Self::from_text(&text))
+    }
+    /// Read the variables from the contents of a file
+    pub fn from_text(text: &str) -> Self {
+        let mut vars = Vec::new();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            let value = ['"', '\'']
+                .iter()
+                .find_map(|&q| value.strip_prefix(q)?.strip_suffix(q))
+                .unwrap_or(value);
+            vars.push((name.trim_end().to_owned(), value.to_owned()));
+        }
+        Self { vars }
+    }
+    /// The value of the variable `name`, if set
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+    /// Parse the variable `name` as `T`, with the default parser settings, or return `None` if
+    /// it is not set
+    pub fn parse<T: SubOpt + Default>(&self, name: &str) -> Result<Option<T>, SubOptError> {
+        self.parse_with(name, &SubOptParser::default())
+    }
+    /// Parse the variable `name` with `parser`, or return `None` if it is not set
+    pub fn parse_with<T: SubOpt>(
+        &self,
+        name: &str,
+        parser: &SubOptParser<T>,
+    ) -> Result<Option<T>, SubOptError> {
+        let Some(value) = self.get(name) else {
+            return Ok(None);
+        };
+        parser
+            .parse(value)
+            .map(Some)
+            .map_err(|error| SubOptError::InEnv {
+                var: name.to_owned(),
+                error: Box::new(error),
+            })
+    }
+    /// Parse the variable `name` with the types of `registry`, so `MYAPP_OUTPUT=tcp:port=80`
+    /// is parsed as the type registered as `tcp`, or return `None` if it is not set
+    ///
+    /// ```
+    /// use clap_subopt_parser::{DotEnv, KvMap, SubOptRegistry};
+    ///
+    /// let registry = SubOptRegistry::<KvMap>::new().register::<KvMap>("tcp", Box::new);
+    /// let env = DotEnv::from_text("MYAPP_OUTPUT=tcp:port=80\nMYAPP_INPUT=udp:port=53\n");
+    /// let output = env.parse_registered("MYAPP_OUTPUT", &registry).unwrap().unwrap();
+    /// assert_eq!(output.pairs["port"], "80");
+    /// assert!(env.parse_registered("MYAPP_INPUT", &registry).is_err());
+    /// ```
+    pub fn parse_registered<D: ?Sized + 'static>(
+        &self,
+        name: &str,
+        registry: &SubOptRegistry<D>,
+    ) -> Result<Option<Box<D>>, SubOptError> {
+        let Some(value) = self.get(name) else {
+            return Ok(None);
+        };
+        registry
+            .parse(value)
+            .map(Some)
+            .map_err(|error| SubOptError::InEnv {
+                var: name.to_owned(),
+                error: Box::new(error),
+            })
+    }
+    /// The variables, in the order of the file
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+}
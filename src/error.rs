@@ -0,0 +1,605 @@
+use crate::{English, Messages};
+use std::ops::Range;
+
+/// An error that can happen when parsing a sub-option.
+#[derive(Debug)]
+pub enum SubOptError {
+    /// Unknown key
+    UnknownKey(String),
+    /// Missing value for key
+    MissingValueForKey(String),
+    /// A [required](crate::KeyInfo::required) key was not given
+    MissingKey(String),
+    /// Custom error, for example parse errors. Convert these errors into a string.
+    Custom(String),
+    /// Invalid value for a key.
+    ///
+    /// Create it with [`SubOptError::invalid_value`].
+    InvalidValue {
+        /// The key
+        key: String,
+        /// What is wrong with the value, for example the message of a parse error
+        reason: String,
+        /// Description of the expected value, like `an unsigned integer`
+        expected: Option<String>,
+        /// An example of a valid sub-option, like `offset=4096`
+        example: Option<String>,
+    },
+    /// The argument is empty, so there are no sub-options
+    EmptyArgument,
+    /// The number of sub-options is outside the bounds set with
+    /// [`SubOptParser::min_segments`](crate::SubOptParser::min_segments) and
+    /// [`SubOptParser::max_segments`](crate::SubOptParser::max_segments)
+    SegmentCount {
+        /// The minimum number of sub-options
+        min: usize,
+        /// The maximum number of sub-options, if any
+        max: Option<usize>,
+        /// The number of sub-options given
+        count: usize,
+    },
+    /// A limit of [`parse_untrusted`](crate::parse_untrusted) was exceeded
+    LimitExceeded {
+        /// The name of the exceeded field of [`Limits`](crate::Limits), like `max_len`
+        limit: &'static str,
+        /// The value of the limit
+        max: usize,
+    },
+    /// An error located in a segment of the argument.
+    ///
+    /// The parser wraps the errors returned by [`SubOpt`](crate::SubOpt) methods in this,
+    /// so the offending sub-option can be pointed out.
+    InSegment {
+        /// The whole argument value
+        arg: String,
+        /// Byte range of the offending segment in `arg`
+        span: Range<usize>,
+        /// The separator between keys and values in `arg`, see
+        /// [`SubOpt::KV_SEPARATOR`](crate::SubOpt::KV_SEPARATOR)
+        kv_sep: char,
+        /// The error returned for the segment
+        error: Box<SubOptError>,
+    },
+    /// An error in the value of an environment variable, see [`from_env`](crate::from_env) and
+    /// [`DotEnv`](crate::DotEnv)
+    InEnv {
+        /// The name of the variable
+        var: String,
+        /// The error in its value
+        error: Box<SubOptError>,
+    },
+    /// An error in a value from a configuration file, see
+    /// [`SubOptParser::parse_with_config`](crate::SubOptParser::parse_with_config)
+    InFile {
+        /// The path of the file
+        path: std::path::PathBuf,
+        /// The error in the value
+        error: Box<SubOptError>,
+    },
+}
+
+/// Parse the value `value` of `key` with [`FromStr`](std::str::FromStr).
+///
+/// Failures are [`SubOptError::InvalidValue`] errors naming the key and the expected type.
+///
+/// ```
+/// use clap_subopt_parser::parse_kv;
+///
+/// assert_eq!(parse_kv::<u64>("offset", "4096").unwrap(), 4096);
+/// assert_eq!(
+///     parse_kv::<u64>("offset", "x").unwrap_err().to_string(),
+///     "Invalid value for key 'offset': invalid digit found in string (expected u64)"
+/// );
+/// ```
+pub fn parse_kv<T>(key: &str, value: &str) -> Result<T, SubOptError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| SubOptError::invalid_value(key, e).expected(short_type_name::<T>()))
+}
+
+/// The name of `T` without module paths, like `Vec<String>`
+pub(crate) fn short_type_name<T>() -> String {
+    let name = std::any::type_name::<T>();
+    let mut out = String::new();
+    for token in name.split_inclusive(['<', '>', ',', ' ', '&', '[', ']', ';', '(', ')']) {
+        out += token.rsplit("::").next().unwrap_or(token);
+    }
+    out
+}
+
+impl SubOptError {
+    /// Create an [`SubOptError::InvalidValue`] error for `key`.
+    ///
+    /// ```
+    /// use clap_subopt_parser::SubOptError;
+    ///
+    /// let err = "x".parse::<u64>().map_err(|e| {
+    ///     SubOptError::invalid_value("offset", e)
+    ///         .expected("an unsigned integer")
+    ///         .example("offset=4096")
+    /// });
+    /// assert_eq!(
+    ///     err.unwrap_err().to_string(),
+    ///     "Invalid value for key 'offset': invalid digit found in string \
+    ///      (expected an unsigned integer, e.g. offset=4096)"
+    /// );
+    /// ```
+    pub fn invalid_value(key: impl Into<String>, reason: impl std::fmt::Display) -> Self {
+        SubOptError::InvalidValue {
+            key: key.into(),
+            reason: reason.to_string(),
+            expected: None,
+            example: None,
+        }
+    }
+    /// Describe the expected value of an [`SubOptError::InvalidValue`] error.
+    ///
+    /// Has no effect on other errors.
+    #[must_use]
+    pub fn expected(mut self, description: impl Into<String>) -> Self {
+        if let SubOptError::InvalidValue { expected, .. } = self.root_mut() {
+            *expected = Some(description.into());
+        }
+        self
+    }
+    /// Give an example of a valid sub-option for an [`SubOptError::InvalidValue`] error.
+    ///
+    /// Has no effect on other errors.
+    #[must_use]
+    pub fn example(mut self, sub_option: impl Into<String>) -> Self {
+        if let SubOptError::InvalidValue { example, .. } = self.root_mut() {
+            *example = Some(sub_option.into());
+        }
+        self
+    }
+    fn root_mut(&mut self) -> &mut SubOptError {
+        match self {
+            SubOptError::InSegment { error, .. }
+            | SubOptError::InEnv { error, .. }
+            | SubOptError::InFile { error, .. } => error.root_mut(),
+            _ => self,
+        }
+    }
+    /// Replace the message with `template`, see [`SubOptParser::key_messages`]
+    ///
+    /// [`SubOptParser::key_messages`]: crate::SubOptParser::key_messages
+    pub(crate) fn with_template(mut self, template: &str) -> Self {
+        let msg = template
+            .replace("{key}", self.key().unwrap_or_default())
+            .replace("{value}", self.value().unwrap_or_default())
+            .replace("{error}", &self.root().to_string());
+        *self.root_mut() = SubOptError::Custom(msg);
+        self
+    }
+    /// Show the values of the keys matching `secret` as `***` in the argument, which is separated
+    /// by `sep` and `kv_sep`
+    pub(crate) fn redact(self, sep: char, kv_sep: char, secret: impl Fn(&str) -> bool) -> Self {
+        let has_secret = |arg: &str| {
+            crate::split_protected(arg, sep)
+                .any(|opt| opt.split_once(kv_sep).is_some_and(|(k, _)| secret(k)))
+        };
+        let (arg, span, error) = match self {
+            SubOptError::InSegment {
+                arg, span, error, ..
+            } if has_secret(&arg) => (arg, span, error),
+            _ => return self,
+        };
+        let mut redacted = String::new();
+        let mut new_span = span.clone();
+        let mut start = 0;
+        for (i, opt) in crate::split_protected(&arg, sep).enumerate() {
+            if i > 0 {
+                redacted.push(sep);
+            }
+            let new_start = redacted.len();
+            match opt.split_once(kv_sep) {
+                Some((k, _)) if secret(k) => redacted += &format!("{}{}***", k, kv_sep),
+                _ => redacted += opt,
+            }
+            if start == span.start {
+                new_span = new_start..redacted.len();
+            }
+            start += opt.len() + sep.len_utf8();
+        }
+        SubOptError::InSegment {
+            arg: redacted,
+            span: new_span,
+            kv_sep,
+            error,
+        }
+    }
+    pub(crate) fn in_segment(self, arg: &str, span: Range<usize>, kv_sep: char) -> Self {
+        SubOptError::InSegment {
+            arg: arg.to_owned(),
+            span,
+            kv_sep,
+            error: Box::new(self),
+        }
+    }
+    /// The key the error is about, if known.
+    ///
+    /// For bare values, this is the value itself, like with [`SubOpt::update_from_value`].
+    ///
+    /// [`SubOpt::update_from_value`]: crate::SubOpt::update_from_value
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            SubOptError::UnknownKey(k)
+            | SubOptError::MissingValueForKey(k)
+            | SubOptError::MissingKey(k)
+            | SubOptError::InvalidValue { key: k, .. } => Some(k),
+            SubOptError::Custom(_)
+            | SubOptError::EmptyArgument
+            | SubOptError::SegmentCount { .. }
+            | SubOptError::LimitExceeded { .. } => None,
+            SubOptError::InSegment {
+                arg,
+                span,
+                kv_sep,
+                error,
+            } => error.key().or_else(|| {
+                let seg = &arg[span.clone()];
+                Some(seg.split_once(*kv_sep).map_or(seg, |(k, _)| k))
+            }),
+            SubOptError::InEnv { error, .. } | SubOptError::InFile { error, .. } => error.key(),
+        }
+    }
+    /// The value of the offending key-value pair, if known
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_str, subopt, Delimited};
+    ///
+    /// subopt! {
+    ///     struct Buf {
+    ///         offset: u64,
+    ///     }
+    /// }
+    ///
+    /// let err = parse_str::<Delimited<Buf, ',', ':'>>("offset:x").map(drop).unwrap_err();
+    /// assert_eq!((err.key(), err.value()), (Some("offset"), Some("x")));
+    /// ```
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            SubOptError::InSegment {
+                arg, span, kv_sep, ..
+            } => arg[span.clone()].split_once(*kv_sep).map(|(_, v)| v),
+            SubOptError::InEnv { error, .. } | SubOptError::InFile { error, .. } => error.value(),
+            _ => None,
+        }
+    }
+    /// Byte range of the offending segment in the argument, if known
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            SubOptError::InSegment { span, .. } => Some(span.clone()),
+            SubOptError::InEnv { error, .. } | SubOptError::InFile { error, .. } => error.span(),
+            _ => None,
+        }
+    }
+    /// Convert into a JSON object, for consumption by wrapper tools.
+    ///
+    /// The object has a `kind` and a `message`. The `key`, `value`, `span`, `arg`, `env`,
+    /// `file`, `expected` and `example` fields are present when known.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let root = self.root();
+        let kind = match root {
+            SubOptError::UnknownKey(_) => "unknown_key",
+            SubOptError::MissingValueForKey(_) => "missing_value",
+            SubOptError::MissingKey(_) => "missing_key",
+            SubOptError::Custom(_) => "custom",
+            SubOptError::InvalidValue { .. } => "invalid_value",
+            SubOptError::EmptyArgument => "empty_argument",
+            SubOptError::SegmentCount { .. } => "segment_count",
+            SubOptError::LimitExceeded { .. } => "limit_exceeded",
+            SubOptError::InSegment { .. }
+            | SubOptError::InEnv { .. }
+            | SubOptError::InFile { .. } => unreachable!(),
+        };
+        let mut obj = serde_json::Map::new();
+        obj.insert("kind".into(), kind.into());
+        obj.insert("message".into(), self.to_string().into());
+        if let Some(key) = self.key() {
+            obj.insert("key".into(), key.into());
+        }
+        if let Some(value) = self.value() {
+            obj.insert("value".into(), value.into());
+        }
+        let mut located = self;
+        match self {
+            SubOptError::InEnv { var, error } => {
+                obj.insert("env".into(), var.as_str().into());
+                located = error;
+            }
+            SubOptError::InFile { path, error } => {
+                obj.insert("file".into(), path.display().to_string().into());
+                located = error;
+            }
+            _ => {}
+        }
+        if let SubOptError::InSegment { arg, span, .. } = located {
+            obj.insert("arg".into(), arg.as_str().into());
+            obj.insert(
+                "span".into(),
+                serde_json::json!({"start": span.start, "end": span.end}),
+            );
+        }
+        if let SubOptError::InvalidValue {
+            expected, example, ..
+        } = root
+        {
+            if let Some(expected) = expected {
+                obj.insert("expected".into(), expected.as_str().into());
+            }
+            if let Some(example) = example {
+                obj.insert("example".into(), example.as_str().into());
+            }
+        }
+        obj.into()
+    }
+    /// The underlying error, without the location information of [`SubOptError::InSegment`]
+    pub fn root(&self) -> &SubOptError {
+        match self {
+            SubOptError::InSegment { error, .. }
+            | SubOptError::InEnv { error, .. }
+            | SubOptError::InFile { error, .. } => error.root(),
+            _ => self,
+        }
+    }
+    #[cfg(feature = "clap")]
+    pub(crate) fn kind(&self) -> clap::error::ErrorKind {
+        match self {
+            SubOptError::UnknownKey(_) => clap::error::ErrorKind::UnknownArgument,
+            // clap reports empty values as invalid values too
+            SubOptError::MissingValueForKey(_)
+            | SubOptError::MissingKey(_)
+            | SubOptError::EmptyArgument
+            | SubOptError::Custom(_)
+            | SubOptError::InvalidValue { .. }
+            | SubOptError::SegmentCount { .. }
+            | SubOptError::LimitExceeded { .. } => clap::error::ErrorKind::InvalidValue,
+            SubOptError::InSegment { error, .. }
+            | SubOptError::InEnv { error, .. }
+            | SubOptError::InFile { error, .. } => error.kind(),
+        }
+    }
+    /// Convert into a [`clap::Error`] for `arg`, formatted with the settings of `cmd`.
+    ///
+    /// Unlike the [`From`] conversion, this respects the color choice of the command,
+    /// and includes the usage and help hint, the same way clap's own errors do. Keys and values
+    /// from the argument are highlighted with the [styles](clap::Command::get_styles) of the
+    /// command, like the invalid values in clap's errors.
+    ///
+    /// ```
+    /// use clap::{Arg, Command};
+    /// use clap_subopt_parser::{parse_str, subopt};
+    ///
+    /// subopt! {
+    ///     struct Buf {
+    ///         offset: u64,
+    ///     }
+    /// }
+    ///
+    /// let cmd = Command::new("myapp");
+    /// let arg = Arg::new("buf").long("buf");
+    /// let err = parse_str::<Buf>("size=4").map(drop).unwrap_err().format(&cmd, Some(&arg));
+    /// let message = err.to_string();
+    /// assert!(message.contains("Invalid value \"size=4\" for '--buf': Unknown key: size"));
+    /// assert!(err.render().ansi().to_string().contains("\x1b[33msize\x1b[0m"));
+    /// ```
+    #[cfg(feature = "clap")]
+    pub fn format(self, cmd: &clap::Command, arg: Option<&clap::Arg>) -> clap::Error {
+        self.format_with(&English, cmd, arg)
+    }
+    /// Like [`SubOptError::format`], but with the given [`Messages`]
+    #[cfg(feature = "clap")]
+    pub fn format_with(
+        self,
+        messages: &dyn Messages,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+    ) -> clap::Error {
+        self.format_full(messages, cmd, arg, None)
+    }
+    /// Like [`SubOptError::format_with`], with an example value of the argument appended
+    #[cfg(feature = "clap")]
+    pub(crate) fn format_full(
+        self,
+        messages: &dyn Messages,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        example: Option<&str>,
+    ) -> clap::Error {
+        // The styles are applied with ANSI codes, which clap strips without colors
+        let styles = cmd.get_styles();
+        let (literal, invalid, valid) = (
+            styles.get_literal(),
+            styles.get_invalid(),
+            styles.get_valid(),
+        );
+        let literal = |s: &str| format!("{}{}{}", literal.render(), s, literal.render_reset());
+        let invalid = |s: &str| format!("{}{}{}", invalid.render(), s, invalid.render_reset());
+        let valid = |s: &str| format!("{}{}{}", valid.render(), s, valid.render_reset());
+        let flag = arg.and_then(crate::flag);
+        let arg = match arg {
+            // Displaying an argument needs the settings clap fills in when building the command
+            Some(arg) if arg.get_num_args().is_some() => arg.to_string(),
+            Some(arg) => flag.clone().unwrap_or_else(|| arg.get_id().to_string()),
+            None => "...".to_owned(),
+        };
+        let value = match &self {
+            SubOptError::InSegment { arg: value, .. } => {
+                Some(invalid(&truncate(value, MAX_ECHO_LEN)))
+            }
+            _ => None,
+        };
+        let mut msg = messages.invalid_arg(
+            value.as_deref(),
+            &literal(&arg),
+            &self.render(messages, &invalid, &valid),
+        );
+        if let Some(example) = example {
+            let example = messages.example(flag.as_deref(), &valid(example));
+            msg += &format!("\n\n{}", example);
+        }
+        clap::Error::raw(self.kind(), msg).format(&mut cmd.clone())
+    }
+    /// The error message, as provided by `messages`.
+    ///
+    /// Keys and segments echoed from the argument are truncated to 80 bytes, and other text to
+    /// 1 KiB, with an ellipsis, so pathological arguments don't produce huge messages.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{English, SubOptError};
+    ///
+    /// let err = SubOptError::UnknownKey("x".repeat(1 << 20));
+    /// assert_eq!(err.localized(&English), format!("Unknown key: {}…", "x".repeat(80)));
+    /// ```
+    pub fn localized(&self, messages: &dyn Messages) -> String {
+        self.render(messages, &str::to_owned, &str::to_owned)
+    }
+    /// The error message, with the text echoed from the argument passed through `invalid`, and
+    /// examples through `valid`, to highlight them
+    fn render(
+        &self,
+        messages: &dyn Messages,
+        invalid: &dyn Fn(&str) -> String,
+        valid: &dyn Fn(&str) -> String,
+    ) -> String {
+        let echo = |s| invalid(&truncate(s, MAX_ECHO_LEN));
+        match self {
+            SubOptError::UnknownKey(k) => messages.unknown_key(&echo(k)),
+            SubOptError::MissingValueForKey(k) => messages.missing_value(&echo(k)),
+            SubOptError::MissingKey(k) => messages.missing_key(&echo(k)),
+            SubOptError::Custom(s) => truncate(s, MAX_TEXT_LEN).into_owned(),
+            SubOptError::InvalidValue {
+                key,
+                reason,
+                expected,
+                example,
+            } => messages.invalid_value(
+                &echo(key),
+                &truncate(reason, MAX_TEXT_LEN),
+                expected.as_deref(),
+                example
+                    .as_deref()
+                    .map(|example| valid(&truncate(example, MAX_ECHO_LEN)))
+                    .as_deref(),
+            ),
+            SubOptError::EmptyArgument => messages.empty_argument(),
+            SubOptError::SegmentCount { min, max, count } => {
+                messages.segment_count(*min, *max, *count)
+            }
+            SubOptError::LimitExceeded { limit, max } => messages.limit_exceeded(limit, *max),
+            SubOptError::InSegment {
+                arg, span, error, ..
+            } => match &**error {
+                SubOptError::Custom(s) => messages
+                    .custom_in_segment(&echo(&arg[span.clone()]), &truncate(s, MAX_TEXT_LEN)),
+                _ => error.render(messages, invalid, valid),
+            },
+            SubOptError::InEnv { var, error } => {
+                messages.in_env(var, &error.render(messages, invalid, valid))
+            }
+            SubOptError::InFile { path, error } => {
+                messages.in_file(path, &error.render(messages, invalid, valid))
+            }
+        }
+    }
+}
+
+/// Display at most `max` of `items`, one per line, followed by a line like `…and 37 more` if
+/// some are left out.
+///
+/// This is for presenting many errors or warnings, like those from
+/// [`take_warnings`](crate::take_warnings), without flooding the terminal.
+///
+/// ```
+/// use clap_subopt_parser::{summarize, SubOptError};
+///
+/// let errors: Vec<_> = (0..40).map(|i| SubOptError::UnknownKey(format!("k{}", i))).collect();
+/// assert_eq!(
+///     summarize(&errors, 3),
+///     "Unknown key: k0\nUnknown key: k1\nUnknown key: k2\n…and 37 more"
+/// );
+/// ```
+pub fn summarize<T: std::fmt::Display>(items: &[T], max: usize) -> String {
+    let mut lines: Vec<String> = items.iter().take(max).map(ToString::to_string).collect();
+    if items.len() > max {
+        lines.push(format!("…and {} more", items.len() - max));
+    }
+    lines.join("\n")
+}
+
+/// Maximum length of keys and argument values echoed back in error messages
+pub(crate) const MAX_ECHO_LEN: usize = 80;
+
+/// Maximum length of other text in error messages, like parse error messages
+const MAX_TEXT_LEN: usize = 1024;
+
+/// Truncate `s` to at most `max` bytes, marking the truncation with an ellipsis
+pub(crate) fn truncate(s: &str, max: usize) -> std::borrow::Cow<'_, str> {
+    if s.len() <= max {
+        return s.into();
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &s[..end]).into()
+}
+
+impl std::fmt::Display for SubOptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.localized(&English))
+    }
+}
+
+impl std::error::Error for SubOptError {}
+
+#[cfg(feature = "clap")]
+impl From<SubOptError> for clap::Error {
+    fn from(sub: SubOptError) -> Self {
+        clap::Error::raw(sub.kind(), format!("{}\n", sub))
+    }
+}
+
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for SubOptError {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        let code = match self {
+            SubOptError::UnknownKey(_) => "clap_subopt_parser::unknown_key",
+            SubOptError::MissingValueForKey(_) => "clap_subopt_parser::missing_value",
+            SubOptError::MissingKey(_) => "clap_subopt_parser::missing_key",
+            SubOptError::Custom(_) => "clap_subopt_parser::custom",
+            SubOptError::InvalidValue { .. } => "clap_subopt_parser::invalid_value",
+            SubOptError::EmptyArgument => "clap_subopt_parser::empty_argument",
+            SubOptError::SegmentCount { .. } => "clap_subopt_parser::segment_count",
+            SubOptError::LimitExceeded { .. } => "clap_subopt_parser::limit_exceeded",
+            SubOptError::InSegment { error, .. }
+            | SubOptError::InEnv { error, .. }
+            | SubOptError::InFile { error, .. } => return error.code(),
+        };
+        Some(Box::new(code))
+    }
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        match self {
+            SubOptError::InSegment { arg, .. } => Some(arg),
+            SubOptError::InEnv { error, .. } | SubOptError::InFile { error, .. } => {
+                error.source_code()
+            }
+            _ => None,
+        }
+    }
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        match self {
+            SubOptError::InSegment { span, error, .. } => Some(Box::new(std::iter::once(
+                miette::LabeledSpan::new_with_span(Some(error.to_string()), span.clone()),
+            ))),
+            SubOptError::InEnv { error, .. } | SubOptError::InFile { error, .. } => error.labels(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{update, KeyInfo, SubOpt, SubOptError};
+use std::marker::PhantomData;
+
+/// Separate arguments for each key of a [`SubOpt`] type, like `--buf-source <INDEX>` and
+/// `--buf-offset <BYTES>`, reassembled into a value after matching.
+///
+/// This lets programs offer a verbose spelling next to the compact `--buf source=0:offset=4`.
+/// Keys with a [value name](KeyInfo::value_name) take a value, and the other keys are flags.
+/// The argument ids and long names are the prefix and the key name joined with `-`.
+///
+/// ```
+/// use clap::{Arg, Command};
+/// use clap_subopt_parser::{parse_kv, ExplodedArgs, KeyInfo, SubOpt, SubOptError, SubOptParser};
+///
+/// #[derive(Debug, Default, Clone)]
+/// struct Buf {
+///     offset: u64,
+///     sync: bool,
+/// }
+///
+/// impl SubOpt for Buf {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "sync" => self.sync = true,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "offset" => self.offset = parse_kv(k, v)?,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+///     fn keys() -> Vec<KeyInfo> {
+///         vec![KeyInfo::new("offset").value_name("BYTES"), KeyInfo::new("sync")]
+///     }
+/// }
+///
+/// let exploded = ExplodedArgs::<Buf>::new("buf");
+/// let parser = SubOptParser::<Buf>::default();
+/// let cmd = Command::new("myapp")
+///     .arg(Arg::new("buf").long("buf").value_parser(parser))
+///     .args(exploded.args());
+/// let m = cmd.get_matches_from(["myapp", "--buf", "offset=4", "--buf-sync"]);
+/// let compact = m.get_one::<Buf>("buf").cloned().unwrap_or_default();
+/// let buf = exploded.apply(&m, compact).unwrap();
+/// assert_eq!(buf.offset, 4);
+/// assert!(buf.sync);
+/// ```
+pub struct ExplodedArgs<T> {
+    keys: Vec<(String, KeyInfo)>,
+    _opt: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for ExplodedArgs<T> {
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            _opt: PhantomData,
+        }
+    }
+}
+
+impl<T: SubOpt> ExplodedArgs<T> {
+    /// Create arguments for the keys of `T`, with names starting with `prefix`
+    pub fn new(prefix: &str) -> Self {
+        let keys = T::keys()
+            .into_iter()
+            .map(|key| (format!("{}-{}", prefix, key.name), key))
+            .collect();
+        Self {
+            keys,
+            _opt: PhantomData,
+        }
+    }
+    /// The arguments, to add to the command with [`clap::Command::args`].
+    ///
+    /// They are hidden for [hidden](KeyInfo::hidden) keys.
+    pub fn args(&self) -> Vec<clap::Arg> {
+        self.keys
+            .iter()
+            .map(|(id, key)| {
+                let arg = clap::Arg::new(id).long(id).hide(key.hidden);
+                let arg = match key.help {
+                    Some(help) => arg.help(help),
+                    None => arg,
+                };
+                match key.value_name {
+                    Some(value_name) => arg
+                        .action(clap::ArgAction::Set)
+                        .value_name(value_name)
+                        .value_parser(clap::builder::ValueParser::string()),
+                    None => arg.action(clap::ArgAction::SetTrue),
+                }
+            })
+            .collect()
+    }
+    /// Update `val` with the arguments given in `m`, then [finish](SubOpt::finish) and
+    /// [validate](SubOpt::validate) it
+    pub fn apply(&self, m: &clap::ArgMatches, mut val: T) -> Result<T, SubOptError> {
+        for (id, key) in &self.keys {
+            match key.value_name {
+                Some(_) => {
+                    if let Some(v) = m.get_one::<String>(id) {
+                        update(&mut val, key.name, Some(v))?;
+                    }
+                }
+                None => {
+                    if m.get_one::<bool>(id).copied().unwrap_or(false) {
+                        update(&mut val, key.name, None)?;
+                    }
+                }
+            }
+        }
+        let val = val.finish()?;
+        val.validate()?;
+        Ok(val)
+    }
+}
+
+impl<T: SubOpt + Default> ExplodedArgs<T> {
+    /// Build a value from the arguments given in `m`, starting from `T::default()`
+    pub fn from_matches(&self, m: &clap::ArgMatches) -> Result<T, SubOptError> {
+        self.apply(m, T::default())
+    }
+}
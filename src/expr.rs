@@ -0,0 +1,131 @@
+//! A tiny integer expression evaluator, for [`values::expr`](crate::values::expr).
+//!
+//! Grammar:
+//!
+//! ```text
+//! expr   = term (('+' | '-') term)*
+//! term   = unary (('*' | '/' | '%') unary)*
+//! unary  = '-' unary | atom
+//! atom   = number | '(' expr ')'
+//! ```
+
+/// Evaluate `s`, returning an error message on failure
+pub(crate) fn eval(s: &str) -> Result<i128, String> {
+    let mut parser = Parser {
+        s,
+        pos: 0,
+        depth: 0,
+    };
+    let n = parser.expr()?;
+    parser.skip_ws();
+    match parser.peek() {
+        None => Ok(n),
+        Some(c) => Err(format!("unexpected '{}' at position {}", c, parser.pos)),
+    }
+}
+
+/// The maximum nesting of parentheses and unary minus, so deeply nested input can't overflow
+/// the stack
+const MAX_DEPTH: usize = 256;
+
+struct Parser<'a> {
+    s: &'a str,
+    pos: usize,
+    /// The current nesting
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.s[self.pos..].chars().next()
+    }
+    fn skip_ws(&mut self) {
+        while let Some(c) = self.peek().filter(|c| c.is_whitespace()) {
+            self.pos += c.len_utf8();
+        }
+    }
+    /// Consume `c` if it's next
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+    fn expr(&mut self) -> Result<i128, String> {
+        let mut n = self.term()?;
+        loop {
+            if self.eat('+') {
+                n = n.checked_add(self.term()?).ok_or("overflow")?;
+            } else if self.eat('-') {
+                n = n.checked_sub(self.term()?).ok_or("overflow")?;
+            } else {
+                return Ok(n);
+            }
+        }
+    }
+    fn term(&mut self) -> Result<i128, String> {
+        let mut n = self.unary()?;
+        loop {
+            if self.eat('*') {
+                n = n.checked_mul(self.unary()?).ok_or("overflow")?;
+            } else if self.eat('/') {
+                n = n.checked_div(self.unary()?).ok_or("division by zero")?;
+            } else if self.eat('%') {
+                n = n.checked_rem(self.unary()?).ok_or("division by zero")?;
+            } else {
+                return Ok(n);
+            }
+        }
+    }
+    /// Run `f` one level deeper, failing if that's too deep
+    fn nested(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<i128, String>,
+    ) -> Result<i128, String> {
+        if self.depth == MAX_DEPTH {
+            return Err(format!("nested too deeply at position {}", self.pos));
+        }
+        self.depth += 1;
+        let n = f(self);
+        self.depth -= 1;
+        n
+    }
+    fn unary(&mut self) -> Result<i128, String> {
+        if self.eat('-') {
+            return self
+                .nested(Self::unary)?
+                .checked_neg()
+                .ok_or_else(|| "overflow".into());
+        }
+        self.atom()
+    }
+    fn atom(&mut self) -> Result<i128, String> {
+        if self.eat('(') {
+            let n = self.nested(Self::expr)?;
+            if !self.eat(')') {
+                return Err(format!("missing ')' at position {}", self.pos));
+            }
+            return Ok(n);
+        }
+        self.skip_ws();
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        let number = &self.s[start..self.pos];
+        if number.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => format!("unexpected '{}' at position {}", c, start),
+                None => "unexpected end".into(),
+            });
+        }
+        crate::values::int::<i128>("", number)
+            .map_err(|_| format!("invalid number '{}' at position {}", number, start))
+    }
+}
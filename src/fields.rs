@@ -0,0 +1,75 @@
+use crate::{schema::parse_value, KeyInfo, KeyTable, SchemaValue, SubOpt, SubOptError};
+
+/// A simpler alternative to implementing [`SubOpt`], for types with a fixed set of fields.
+///
+/// The [fields](KeyedFields::fields) describe the keys, and their values are parsed according to
+/// their [kind](KeyInfo::kind) like in a [`KeySchema`](crate::KeySchema), so
+/// [`set`](KeyedFields::set) only has to store them. Fields without a
+/// [value name](KeyInfo::value_name) and fields of kind [`ValueKind::Bool`](crate::ValueKind::Bool)
+/// can be given as bare values, which sets them to `true`. The fields are also the
+/// [keys](SubOpt::keys) for help and completion.
+///
+/// ```
+/// use clap_subopt_parser::{parse_str, KeyInfo, KeyedFields, SchemaValue, SubOptError, ValueKind};
+///
+/// #[derive(Default)]
+/// struct Buf {
+///     offset: i64,
+///     ro: bool,
+/// }
+///
+/// impl KeyedFields for Buf {
+///     fn fields() -> Vec<KeyInfo> {
+///         vec![
+///             KeyInfo::new("offset").value_name("BYTES")
+///                 .kind(ValueKind::Int { min: Some(0), max: None }),
+///             KeyInfo::new("ro").kind(ValueKind::Bool),
+///         ]
+///     }
+///     fn set(&mut self, key: &str, value: SchemaValue) -> Result<(), SubOptError> {
+///         match (key, value) {
+///             ("offset", SchemaValue::Int(v)) => self.offset = v,
+///             ("ro", SchemaValue::Bool(v)) => self.ro = v,
+///             (key, _) => return Err(SubOptError::UnknownKey(key.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let buf = parse_str::<Buf>("offset=512:ro").unwrap();
+/// assert_eq!(buf.offset, 512);
+/// assert!(buf.ro);
+/// assert!(parse_str::<Buf>("offset=-1").is_err());
+/// ```
+pub trait KeyedFields: 'static {
+    /// The fields.
+    ///
+    /// They are looked up in a [`KeyTable::cached`] table when parsing, so this must return
+    /// the same fields on every call.
+    fn fields() -> Vec<KeyInfo>;
+    /// Set the field named `key` to the parsed `value`.
+    ///
+    /// `key` is the name of the field, even if an alias was given.
+    fn set(&mut self, key: &str, value: SchemaValue) -> Result<(), SubOptError>;
+}
+
+impl<T: KeyedFields> SubOpt for T {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        set(self, k, None)
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        set(self, k, Some(v))
+    }
+    fn keys() -> Vec<KeyInfo> {
+        T::fields()
+    }
+}
+
+fn set<T: KeyedFields>(val: &mut T, k: &str, v: Option<&str>) -> Result<(), SubOptError> {
+    let table = KeyTable::cached::<T>();
+    let field = table
+        .find(k)
+        .ok_or_else(|| SubOptError::UnknownKey(k.into()))?;
+    let value = parse_value(field, v)?;
+    val.set(field.name, value)
+}
@@ -0,0 +1,84 @@
+use crate::{help::ArgExt, SubOpt};
+
+/// A [`SubOpt`] type with the name of its flag, for use with [`Flatten`]
+pub trait SubOptFlag: SubOpt + Default + Clone + Send + Sync {
+    /// The long name of the flag, which is also the argument id, like `buf` for `--buf`
+    const LONG: &'static str;
+    /// The help text of the flag
+    const HELP: Option<&'static str> = None;
+}
+
+/// A flag with sub-options of type `T`, for embedding in derive-based CLIs with
+/// `#[clap(flatten)]`.
+///
+/// It adds the flag `--<LONG>`, with the [key table](crate::help::key_table) of `T` in its long
+/// help. If the flag is not given, the value is `T::default()`.
+///
+/// ```
+/// use clap::Parser;
+/// use clap_subopt_parser::{parse_kv, Flatten, SubOpt, SubOptError, SubOptFlag};
+///
+/// #[derive(Debug, Default, Clone)]
+/// struct Buf {
+///     offset: u64,
+/// }
+///
+/// impl SubOpt for Buf {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         Err(SubOptError::UnknownKey(k.into()))
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "offset" => self.offset = parse_kv(k, v)?,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// impl SubOptFlag for Buf {
+///     const LONG: &'static str = "buf";
+///     const HELP: Option<&'static str> = Some("The buffer");
+/// }
+///
+/// #[derive(clap::Parser)]
+/// struct Args {
+///     #[clap(flatten)]
+///     buf: Flatten<Buf>,
+/// }
+///
+/// let args = Args::parse_from(["myapp", "--buf", "offset=4"]);
+/// assert_eq!(args.buf.0.offset, 4);
+/// let args = Args::parse_from(["myapp"]);
+/// assert_eq!(args.buf.0.offset, 0);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Flatten<T>(pub T);
+
+impl<T: SubOptFlag> clap::FromArgMatches for Flatten<T> {
+    fn from_arg_matches(matches: &clap::ArgMatches) -> Result<Self, clap::Error> {
+        let mut val = Self(T::default());
+        val.update_from_arg_matches(matches)?;
+        Ok(val)
+    }
+    fn update_from_arg_matches(&mut self, matches: &clap::ArgMatches) -> Result<(), clap::Error> {
+        if let Some(val) = matches.get_one::<T>(T::LONG) {
+            self.0 = val.clone();
+        }
+        Ok(())
+    }
+}
+
+impl<T: SubOptFlag> clap::Args for Flatten<T> {
+    fn augment_args(cmd: clap::Command) -> clap::Command {
+        let arg = clap::Arg::new(T::LONG).long(T::LONG);
+        let arg = match T::HELP {
+            Some(help) => arg.help(help),
+            None => arg,
+        };
+        cmd.arg(arg.subopt::<T>())
+    }
+    fn augment_args_for_update(cmd: clap::Command) -> clap::Command {
+        Self::augment_args(cmd)
+    }
+}
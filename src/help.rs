@@ -0,0 +1,318 @@
+//! Help text generation from [`KeyInfo`] metadata.
+
+use crate::{keys::grouped, English, KeyInfo, Messages, SubOpt};
+#[cfg(feature = "clap")]
+use crate::{SubOptDisplay, SubOptParser};
+
+/// Render a table of the keys of `T`, for use in help text.
+///
+/// The [example](SubOpt::example) of `T` is appended, if any.
+pub fn key_table<T: SubOpt>() -> String {
+    format!("{}{}", render_key_table(&T::keys()), example::<T>(None))
+}
+
+/// Like [`key_table`], with the help text translated by `messages`
+pub fn localized_key_table<T: SubOpt>(messages: &dyn Messages) -> String {
+    format!(
+        "{}{}",
+        render_localized_key_table(&T::keys(), messages),
+        example::<T>(None)
+    )
+}
+
+/// Like [`render_key_table`], with the help text translated using [`Messages::key_help`].
+///
+/// ```
+/// use clap_subopt_parser::{help, KeyInfo, Messages};
+///
+/// struct German;
+///
+/// impl Messages for German {
+///     fn key_help(&self, key: &KeyInfo) -> Option<String> {
+///         match key.name {
+///             "source" => Some("Quellpuffer".into()),
+///             _ => key.help.map(Into::into),
+///         }
+///     }
+/// }
+///
+/// let keys = [KeyInfo::new("source").value_name("INDEX").help("Source buffer")];
+/// assert_eq!(
+///     help::render_localized_key_table(&keys, &German),
+///     "Sub-options:\n    source=<INDEX>    Quellpuffer"
+/// );
+/// ```
+pub fn render_localized_key_table(keys: &[KeyInfo], messages: &dyn Messages) -> String {
+    format!("Sub-options:{}", rows(keys, messages))
+}
+
+/// Render a table of `keys`, see [`key_table`]
+///
+/// Keys with a [category](KeyInfo::category) are listed under a heading.
+///
+/// ```
+/// use clap_subopt_parser::{help, KeyInfo};
+///
+/// let keys = [
+///     KeyInfo::new("source").value_name("INDEX").help("Source buffer").required(true),
+///     KeyInfo::new("offset").value_name("BYTES").help("Offset").default("0"),
+///     KeyInfo::new("debug").help("Internal").hide(true),
+///     KeyInfo::new("sync").help("Synchronous I/O").category("performance"),
+/// ];
+/// assert_eq!(
+///     help::render_key_table(&keys),
+///     "Sub-options:
+///     source=<INDEX>    Source buffer [required]
+///     offset=<BYTES>    Offset [default: 0]
+///
+///   performance:
+///     sync              Synchronous I/O"
+/// );
+/// ```
+pub fn render_key_table(keys: &[KeyInfo]) -> String {
+    render_localized_key_table(keys, &English)
+}
+
+/// The rows of a key table, each starting with a newline, with help text from `messages`
+fn rows(keys: &[KeyInfo], messages: &dyn Messages) -> String {
+    let spec = |key: &KeyInfo| match key.value_name {
+        Some(value_name) => format!("{}=<{}>", key.name, value_name),
+        None => key.name.to_owned(),
+    };
+    let groups = grouped(keys);
+    let width = groups
+        .iter()
+        .flat_map(|(_, keys)| keys.iter().map(|k| spec(k).len()))
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for (category, keys) in groups {
+        if let Some(category) = category {
+            out += &format!("\n\n  {}:", category);
+        }
+        for key in keys {
+            let line = format!(
+                "\n    {:width$}    {}{}",
+                spec(key),
+                messages.key_help(key).unwrap_or_default(),
+                key.annotations()
+            );
+            out += line.trim_end();
+        }
+    }
+    out
+}
+
+/// The example of `T` for `flag`, starting with a blank line, or an empty string
+fn example<T: SubOpt>(flag: Option<&str>) -> String {
+    match (T::example(), flag) {
+        (Some(example), Some(flag)) => format!("\n\nExample: {} {}", flag, example),
+        (Some(example), None) => format!("\n\nExample: {}", example),
+        (None, _) => String::new(),
+    }
+}
+
+/// Render the defaults of the keys of `T` as an argument value, like `source=0:offset=0`.
+///
+/// Only keys with a [default](KeyInfo::default) are included. Returns `None` if there are none.
+pub fn default_value<T: SubOpt>() -> Option<String> {
+    render_defaults(&T::keys(), T::SEPARATOR, T::KV_SEPARATOR)
+}
+
+/// Render the defaults of `keys`, see [`default_value`]
+///
+/// ```
+/// use clap_subopt_parser::{help, KeyInfo};
+///
+/// let keys = [
+///     KeyInfo::new("source"),
+///     KeyInfo::new("offset").default("0"),
+///     KeyInfo::new("len").default("4096"),
+/// ];
+/// assert_eq!(help::render_default_value(&keys).unwrap(), "offset=0:len=4096");
+/// ```
+pub fn render_default_value(keys: &[KeyInfo]) -> Option<String> {
+    render_defaults(keys, crate::DEFAULT_SEPARATOR, '=')
+}
+
+fn render_defaults(keys: &[KeyInfo], sep: char, kv_sep: char) -> Option<String> {
+    let defaults = keys
+        .iter()
+        .filter(|key| !key.hidden)
+        .filter_map(|key| Some(format!("{}{}{}", key.name, kv_sep, key.default?)))
+        .collect::<Vec<_>>();
+    (!defaults.is_empty()).then(|| defaults.join(&sep.to_string()))
+}
+
+/// Extension methods for [`clap::Arg`]
+#[cfg(feature = "clap")]
+pub trait ArgExt {
+    /// Make the argument take sub-options of type `T`.
+    ///
+    /// This sets a [`SubOptParser`] with the default settings, the value name
+    /// `KEY=VALUE:...`, and the [`key_table`] as in [`ArgExt::subopt_help`] if `T` has
+    /// [keys](SubOpt::keys).
+    ///
+    /// ```
+    /// use clap::{Arg, Command};
+    /// use clap_subopt_parser::{help::ArgExt, SubOpt, SubOptError};
+    /// # #[derive(Debug, Default, Clone)]
+    /// # struct Buf;
+    /// # impl SubOpt for Buf {
+    /// #     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    /// #         match k {
+    /// #             "sync" => Ok(()),
+    /// #             _ => Err(SubOptError::UnknownKey(k.into())),
+    /// #         }
+    /// #     }
+    /// #     fn update_from_kvpair(&mut self, k: &str, _: &str) -> Result<(), SubOptError> {
+    /// #         Err(SubOptError::UnknownKey(k.into()))
+    /// #     }
+    /// # }
+    ///
+    /// let arg = Arg::new("buf").long("buf").subopt::<Buf>();
+    /// assert_eq!(arg.get_value_names().unwrap(), ["KEY=VALUE:..."]);
+    /// assert_eq!(arg.get_long_help(), None);
+    /// let m = Command::new("myapp").arg(arg).get_matches_from(["myapp", "--buf", "sync"]);
+    /// assert!(m.get_one::<Buf>("buf").is_some());
+    /// ```
+    #[must_use]
+    fn subopt<T: SubOpt + Default + Clone + Send + Sync>(self) -> Self;
+    /// Append the [`key_table`] of `T` to the long help of the argument.
+    ///
+    /// Call this once per argument, when building the command.
+    #[must_use]
+    fn subopt_help<T: SubOpt>(self) -> Self;
+    /// Append the [`default_value`] of `T` to the help of the argument, like
+    /// `[default: offset=0]`.
+    ///
+    /// Unlike [`clap::Arg::default_value`], this only affects the help text.
+    #[must_use]
+    fn show_subopt_default<T: SubOpt>(self) -> Self;
+    /// Set the [default value](clap::Arg::default_value) of the argument to `T::default()`,
+    /// formatted with [`SubOptDisplay::to_arg_string`].
+    ///
+    /// This way, the default shown in help is exactly what is parsed when the argument is not
+    /// given. If `T::default()` has no sub-options, the default value is empty, which needs
+    /// [`SubOptParser::allow_empty`](crate::SubOptParser::allow_empty).
+    ///
+    /// ```
+    /// use clap::{Arg, Command};
+    /// use clap_subopt_parser::{help::ArgExt, parse_kv, SubOpt, SubOptDisplay, SubOptError, SubOptParser};
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// struct Buf {
+    ///     size: u64,
+    /// }
+    ///
+    /// impl Default for Buf {
+    ///     fn default() -> Self {
+    ///         Self { size: 4096 }
+    ///     }
+    /// }
+    ///
+    /// impl SubOpt for Buf {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "size" => self.size = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// impl SubOptDisplay for Buf {
+    ///     fn sub_options(&self) -> Vec<(&'static str, Option<String>)> {
+    ///         vec![("size", Some(self.size.to_string()))]
+    ///     }
+    /// }
+    ///
+    /// let arg = Arg::new("buf")
+    ///     .long("buf")
+    ///     .value_parser(SubOptParser::<Buf>::default())
+    ///     .subopt_default::<Buf>();
+    /// assert_eq!(arg.get_default_values(), ["size=4096"]);
+    /// let m = Command::new("myapp").arg(arg).get_matches_from(["myapp"]);
+    /// assert_eq!(m.get_one::<Buf>("buf").unwrap().size, 4096);
+    /// ```
+    #[must_use]
+    fn subopt_default<T: SubOptDisplay + Default>(self) -> Self;
+}
+
+#[cfg(feature = "clap")]
+impl ArgExt for clap::Arg {
+    fn subopt<T: SubOpt + Default + Clone + Send + Sync>(self) -> Self {
+        let value_name = format!("KEY{}VALUE{}...", T::KV_SEPARATOR, T::SEPARATOR);
+        let arg = self
+            .action(clap::ArgAction::Set)
+            .value_name(value_name)
+            .value_parser(SubOptParser::<T>::default());
+        if T::keys().is_empty() {
+            return arg;
+        }
+        arg.subopt_help::<T>()
+    }
+    fn subopt_help<T: SubOpt>(self) -> Self {
+        let table = format!(
+            "{}{}",
+            render_key_table(&T::keys()),
+            example::<T>(crate::flag(&self).as_deref())
+        );
+        let help = match self.get_long_help().or_else(|| self.get_help()) {
+            Some(help) => format!("{}\n\n{}", help, table),
+            None => table,
+        };
+        self.long_help(help)
+    }
+    fn show_subopt_default<T: SubOpt>(self) -> Self {
+        let default = match default_value::<T>() {
+            Some(default) => format!("[default: {}]", default),
+            None => return self,
+        };
+        let help = match self.get_help() {
+            Some(help) => format!("{} {}", help, default),
+            None => default,
+        };
+        self.help(help)
+    }
+    fn subopt_default<T: SubOptDisplay + Default>(self) -> Self {
+        self.default_value(T::default().to_arg_string())
+    }
+}
+
+/// Extension methods for [`clap::Command`]
+#[cfg(feature = "clap")]
+pub trait CommandExt {
+    /// Append a section documenting the keys of `T` for the argument `arg` to the after help.
+    ///
+    /// `arg` is only used in the section title, like `--buf`. Call this once for each argument
+    /// that uses sub-options.
+    #[must_use]
+    fn with_subopt_docs<T: SubOpt>(self, arg: &str) -> Self;
+}
+
+#[cfg(feature = "clap")]
+impl CommandExt for clap::Command {
+    fn with_subopt_docs<T: SubOpt>(self, arg: &str) -> Self {
+        let section = format!(
+            "Sub-options for {}:{}{}",
+            arg,
+            rows(&T::keys(), &English),
+            example::<T>(Some(arg))
+        );
+        let append = |help: Option<&clap::builder::StyledStr>| match help {
+            Some(help) => format!("{}\n\n{}", help, section),
+            None => section.clone(),
+        };
+        let long = self.get_after_long_help().map(|help| append(Some(help)));
+        let short = append(self.get_after_help());
+        let cmd = self.after_help(short);
+        match long {
+            Some(long) => cmd.after_long_help(long),
+            None => cmd,
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! Finding sub-option arguments in a built [`clap::Command`].
+//!
+//! clap erases the type of value parsers, so the [`SubOpt`] types to look for have to be
+//! [registered](Registry::register). Arguments are matched by the type of their values,
+//! which is `T` for a [`SubOptParser<T>`](crate::SubOptParser).
+
+use crate::{KeyInfo, SubOpt};
+
+/// A set of [`SubOpt`] types to look for in a command
+///
+/// ```
+/// use clap::{Arg, Command};
+/// use clap_subopt_parser::{introspect::Registry, KeyInfo, SubOpt, SubOptError, SubOptParser};
+///
+/// #[derive(Default, Clone)]
+/// struct Buf;
+///
+/// impl SubOpt for Buf {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> { Ok(()) }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> { Ok(()) }
+///     fn keys() -> Vec<KeyInfo> {
+///         vec![KeyInfo::new("offset").value_name("BYTES")]
+///     }
+/// }
+///
+/// let cmd = Command::new("myapp").subcommand(
+///     Command::new("load")
+///         .arg(Arg::new("buf").long("buf").value_parser(SubOptParser::<Buf>::default()))
+///         .arg(Arg::new("name").long("name")),
+/// );
+/// let args = Registry::new().register::<Buf>().args(&cmd);
+/// assert_eq!(args.len(), 1);
+/// assert_eq!(args[0].path, ["myapp", "load"]);
+/// assert_eq!(args[0].arg.get_id(), "buf");
+/// assert_eq!(args[0].keys[0].name, "offset");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Registry {
+    types: Vec<RegisteredType>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RegisteredType {
+    matches: fn(&clap::builder::ValueParser) -> bool,
+    keys: fn() -> Vec<KeyInfo>,
+}
+
+/// An argument using a registered [`SubOpt`] type, see [`Registry::args`]
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct SubOptArg<'cmd> {
+    /// The names of the command and subcommands leading to the argument, starting with the root
+    pub path: Vec<&'cmd str>,
+    /// The argument
+    pub arg: &'cmd clap::Arg,
+    /// The keys of the [`SubOpt`] type
+    pub keys: Vec<KeyInfo>,
+}
+
+impl Registry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Look for arguments with values of type `T`
+    #[must_use]
+    pub fn register<T: SubOpt + Default>(mut self) -> Self {
+        self.types.push(RegisteredType {
+            matches: |parser| parser.type_id() == std::any::TypeId::of::<T>(),
+            keys: T::keys,
+        });
+        self
+    }
+    /// Find the arguments of `cmd` and all its subcommands that use a registered type.
+    ///
+    /// Arguments are listed depth first, in the order they were added.
+    pub fn args<'cmd>(&self, cmd: &'cmd clap::Command) -> Vec<SubOptArg<'cmd>> {
+        let mut out = Vec::new();
+        self.walk(cmd, &mut Vec::new(), &mut out);
+        out
+    }
+    /// Render the man page of `cmd` with `man`, with a `SUB-OPTIONS` section after the options,
+    /// describing the keys of the arguments that use a registered type.
+    ///
+    /// `man` must be created from `cmd`. The arguments of subcommands are included, labeled
+    /// with the subcommand names. Without such arguments, this is the same as
+    /// [`clap_mangen::Man::render`].
+    ///
+    /// ```
+    /// # use clap_subopt_parser::{introspect::Registry, KeyInfo, SubOpt, SubOptError, SubOptParser};
+    /// # #[derive(Default, Clone)]
+    /// # struct Buf;
+    /// # impl SubOpt for Buf {
+    /// #     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> { Ok(()) }
+    /// #     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> { Ok(()) }
+    /// #     fn keys() -> Vec<KeyInfo> {
+    /// #         vec![KeyInfo::new("offset").value_name("BYTES")]
+    /// #     }
+    /// # }
+    /// use clap::{Arg, Command};
+    ///
+    /// let cmd = Command::new("myapp")
+    ///     .arg(Arg::new("buf").long("buf").value_parser(SubOptParser::<Buf>::default()));
+    /// let mut out = Vec::new();
+    /// Registry::new()
+    ///     .register::<Buf>()
+    ///     .render_man(&clap_mangen::Man::new(cmd.clone()), &cmd, &mut out)
+    ///     .unwrap();
+    /// let out = String::from_utf8(out).unwrap();
+    /// assert!(out.contains(".SH \"SUB-OPTIONS\"\n.SS \"Sub-options for \\-\\-buf\"\n"));
+    /// ```
+    #[cfg(feature = "mangen")]
+    pub fn render_man(
+        &self,
+        man: &clap_mangen::Man,
+        cmd: &clap::Command,
+        w: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        let mut page = Vec::new();
+        man.render(&mut page)?;
+        let page = String::from_utf8(page).expect("roff is UTF-8");
+        let mut section = String::new();
+        for found in self
+            .args(cmd)
+            .into_iter()
+            .filter(|found| !found.keys.is_empty())
+        {
+            let mut label = found.path[1..].join(" ");
+            let flag = crate::flag(found.arg)
+                .unwrap_or_else(|| found.arg.get_id().as_str().to_uppercase());
+            if !label.is_empty() {
+                label.push(' ');
+            }
+            label += &flag;
+            section += &crate::docs::render_man(&label, &found.keys);
+        }
+        if section.is_empty() {
+            return w.write_all(page.as_bytes());
+        }
+        // After the OPTIONS section, which is followed by the next one or the end
+        let at = page
+            .find(".SH OPTIONS\n")
+            .and_then(|options| page[options + 1..].find("\n.SH ").map(|i| options + i + 2))
+            .unwrap_or(page.len());
+        w.write_all(&page.as_bytes()[..at])?;
+        w.write_all(b".SH \"SUB-OPTIONS\"\n")?;
+        w.write_all(section.as_bytes())?;
+        w.write_all(&page.as_bytes()[at..])
+    }
+    fn walk<'cmd>(
+        &self,
+        cmd: &'cmd clap::Command,
+        path: &mut Vec<&'cmd str>,
+        out: &mut Vec<SubOptArg<'cmd>>,
+    ) {
+        path.push(cmd.get_name());
+        for arg in cmd.get_arguments() {
+            let parser = arg.get_value_parser();
+            if let Some(ty) = self.types.iter().find(|ty| (ty.matches)(parser)) {
+                out.push(SubOptArg {
+                    path: path.clone(),
+                    arg,
+                    keys: (ty.keys)(),
+                });
+            }
+        }
+        for sub in cmd.get_subcommands() {
+            self.walk(sub, path, out);
+        }
+        path.pop();
+    }
+}
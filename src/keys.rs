@@ -0,0 +1,530 @@
+use std::any::TypeId;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+/// Metadata about a key of a [`SubOpt`](crate::SubOpt) type.
+///
+/// Returned by [`SubOpt::keys`](crate::SubOpt::keys), and used for help, completion,
+/// suggestions and documentation generation.
+///
+/// ```
+/// use clap_subopt_parser::KeyInfo;
+///
+/// let keys = [
+///     KeyInfo::new("source")
+///         .help("Index of the source buffer")
+///         .value_name("INDEX")
+///         .required(true),
+///     KeyInfo::new("offset")
+///         .aliases(&["off"])
+///         .help("Offset in bytes")
+///         .value_name("BYTES")
+///         .default("0"),
+/// ];
+/// assert!(keys[1].matches("off"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct KeyInfo {
+    /// The name of the key
+    pub name: &'static str,
+    /// Alternative names of the key
+    pub aliases: &'static [&'static str],
+    /// Help text
+    pub help: Option<&'static str>,
+    /// Name of the value, like `BYTES`
+    pub value_name: Option<&'static str>,
+    /// Rust type of the value, like `usize`
+    pub type_name: Option<&'static str>,
+    /// The kind of value, for rendering input widgets
+    pub kind: Option<ValueKind>,
+    /// Whether the key must be given
+    pub required: bool,
+    /// The default value, if the key is not given
+    pub default: Option<&'static str>,
+    /// Environment variable used for the value if the key is not given, like `MYAPP_BUF_OFFSET`.
+    ///
+    /// For keys without a [value name](KeyInfo::value_name), the key is given as a bare value
+    /// if the variable is set and not empty. Errors in the value are [`SubOptError::InEnv`]
+    /// errors naming the variable.
+    ///
+    /// [`SubOptError::InEnv`]: crate::SubOptError::InEnv
+    pub env: Option<&'static str>,
+    /// Version of the program that added the key, like `1.3`
+    pub since: Option<&'static str>,
+    /// Category for grouping keys in help and documentation, like `network`
+    pub category: Option<&'static str>,
+    /// The kind of value, for shell completion
+    #[cfg(feature = "clap")]
+    pub value_hint: clap::ValueHint,
+    /// Whether the key is left out of help, completion and suggestions.
+    ///
+    /// Hidden keys still parse, which is useful for internal or experimental keys.
+    pub hidden: bool,
+    /// Whether the value is secret, like a password.
+    ///
+    /// Secret values are shown as `***` in the argument echoed by errors. Use
+    /// [`Redacted`](crate::Redacted) for the field, so it isn't shown by the [`SubOpt`](crate::SubOpt)
+    /// type either.
+    pub secret: bool,
+}
+
+impl KeyInfo {
+    /// Create metadata for the key `name`
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            aliases: &[],
+            help: None,
+            value_name: None,
+            type_name: None,
+            kind: None,
+            required: false,
+            default: None,
+            env: None,
+            since: None,
+            category: None,
+            #[cfg(feature = "clap")]
+            value_hint: clap::ValueHint::Unknown,
+            hidden: false,
+            secret: false,
+        }
+    }
+    /// Set the alternative names
+    #[must_use]
+    pub const fn aliases(mut self, aliases: &'static [&'static str]) -> Self {
+        self.aliases = aliases;
+        self
+    }
+    /// Set the help text
+    #[must_use]
+    pub const fn help(mut self, help: &'static str) -> Self {
+        self.help = Some(help);
+        self
+    }
+    /// Set the name of the value
+    #[must_use]
+    pub const fn value_name(mut self, value_name: &'static str) -> Self {
+        self.value_name = Some(value_name);
+        self
+    }
+    /// Set the Rust type of the value
+    #[must_use]
+    pub const fn type_name(mut self, type_name: &'static str) -> Self {
+        self.type_name = Some(type_name);
+        self
+    }
+    /// Set the kind of value
+    #[must_use]
+    pub const fn kind(mut self, kind: ValueKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+    /// Set whether the key must be given
+    #[must_use]
+    pub const fn required(mut self, yes: bool) -> Self {
+        self.required = yes;
+        self
+    }
+    /// Set the default value
+    #[must_use]
+    pub const fn default(mut self, default: &'static str) -> Self {
+        self.default = Some(default);
+        self
+    }
+    /// Set the environment variable fallback
+    ///
+    /// ```
+    /// use clap_subopt_parser::{testing, KeyInfo, SubOpt, SubOptError};
+    ///
+    /// #[derive(Default)]
+    /// struct Buf {
+    ///     source: String,
+    ///     offset: String,
+    /// }
+    ///
+    /// impl SubOpt for Buf {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "source" => self.source = v.into(),
+    ///             "offset" => self.offset = v.into(),
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///     fn keys() -> Vec<KeyInfo> {
+    ///         vec![KeyInfo::new("offset").value_name("BYTES").env("MYAPP_BUF_OFFSET")]
+    ///     }
+    /// }
+    ///
+    /// std::env::set_var("MYAPP_BUF_OFFSET", "512");
+    /// assert_eq!(testing::parse_ok::<Buf>("source=1").offset, "512");
+    /// assert_eq!(testing::parse_ok::<Buf>("source=1:offset=0").offset, "0");
+    /// ```
+    ///
+    /// Errors in the value of the variable name it, and hide the value of
+    /// [secret](KeyInfo::secret) keys.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_kv, parse_str, KeyInfo, SubOpt, SubOptError};
+    ///
+    /// #[derive(Default)]
+    /// struct Db {
+    ///     host: String,
+    ///     pin: u32,
+    /// }
+    ///
+    /// impl SubOpt for Db {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "host" => self.host = v.into(),
+    ///             "pin" => self.pin = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///     fn keys() -> Vec<KeyInfo> {
+    ///         vec![KeyInfo::new("pin").value_name("PIN").env("MYAPP_DB_PIN").secret(true)]
+    ///     }
+    /// }
+    ///
+    /// std::env::set_var("MYAPP_DB_PIN", "12ab");
+    /// let err = parse_str::<Db>("host=db").map(drop).unwrap_err();
+    /// assert!(matches!(&err, SubOptError::InEnv { var, .. } if var == "MYAPP_DB_PIN"));
+    /// assert_eq!(err.value(), Some("***"));
+    /// ```
+    #[must_use]
+    pub const fn env(mut self, env: &'static str) -> Self {
+        self.env = Some(env);
+        self
+    }
+    /// Set the version that added the key
+    #[must_use]
+    pub const fn since(mut self, version: &'static str) -> Self {
+        self.since = Some(version);
+        self
+    }
+    /// Set the category
+    #[must_use]
+    pub const fn category(mut self, category: &'static str) -> Self {
+        self.category = Some(category);
+        self
+    }
+    /// Set the kind of value, like [`clap::ValueHint::FilePath`]
+    #[must_use]
+    #[cfg(feature = "clap")]
+    pub const fn value_hint(mut self, value_hint: clap::ValueHint) -> Self {
+        self.value_hint = value_hint;
+        self
+    }
+    /// Set whether the key is hidden
+    #[must_use]
+    pub const fn hide(mut self, yes: bool) -> Self {
+        self.hidden = yes;
+        self
+    }
+    /// Set whether the value is secret
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_kv, testing, KeyInfo, Redacted, SubOpt, SubOptError};
+    ///
+    /// #[derive(Default)]
+    /// struct Login {
+    ///     user: String,
+    ///     password: Redacted<String>,
+    /// }
+    ///
+    /// impl SubOpt for Login {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "user" => self.user = v.into(),
+    ///             "password" => self.password = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///     fn keys() -> Vec<KeyInfo> {
+    ///         vec![KeyInfo::new("user"), KeyInfo::new("password").secret(true)]
+    ///     }
+    /// }
+    ///
+    /// let err = testing::assert_parse_err::<Login>("password=hunter2:usr=me", |_| true);
+    /// assert!(!format!("{:?}", err).contains("hunter2"));
+    /// let err = testing::assert_parse_err::<Login>("usr=me:password=hunter2", |_| true);
+    /// assert!(!format!("{:?}", err).contains("hunter2"));
+    /// ```
+    #[must_use]
+    pub const fn secret(mut self, yes: bool) -> Self {
+        self.secret = yes;
+        self
+    }
+    /// Annotations for help text, like ` [required] [default: 0]`
+    pub(crate) fn annotations(&self) -> String {
+        let mut out = String::new();
+        if self.required {
+            out += " [required]";
+        }
+        if let Some(default) = self.default {
+            out += &format!(" [default: {}]", default);
+        }
+        if let Some(env) = self.env {
+            out += &format!(" [env: {}]", env);
+        }
+        if !self.aliases.is_empty() {
+            out += &format!(" [aliases: {}]", self.aliases.join(", "));
+        }
+        if let Some(since) = self.since {
+            out += &format!(" [since {}]", since);
+        }
+        out
+    }
+    /// Whether `key` is the name or one of the aliases
+    pub fn matches(&self, key: &str) -> bool {
+        self.name == key || self.aliases.contains(&key)
+    }
+}
+
+/// The kind of value a key takes, see [`KeyInfo::kind`].
+///
+/// This is meant for GUI wrappers, to render an appropriate widget instead of a text box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValueKind {
+    /// An integer, within the inclusive bounds
+    Int {
+        /// The minimum value
+        min: Option<i64>,
+        /// The maximum value
+        max: Option<i64>,
+    },
+    /// A boolean
+    Bool,
+    /// One of a fixed set of values
+    Enum {
+        /// The possible values
+        variants: &'static [&'static str],
+    },
+    /// A filesystem path
+    Path,
+    /// Free text
+    String,
+}
+
+impl ValueKind {
+    #[cfg(feature = "json")]
+    fn to_json(self) -> serde_json::Value {
+        match self {
+            ValueKind::Int { min, max } => {
+                serde_json::json!({ "type": "int", "min": min, "max": max })
+            }
+            ValueKind::Bool => serde_json::json!({ "type": "bool" }),
+            ValueKind::Enum { variants } => {
+                serde_json::json!({ "type": "enum", "variants": variants })
+            }
+            ValueKind::Path => serde_json::json!({ "type": "path" }),
+            ValueKind::String => serde_json::json!({ "type": "string" }),
+        }
+    }
+}
+
+/// All key metadata of a [`SubOpt`](crate::SubOpt) type, as plain data.
+///
+/// This is meant for feeding custom documentation pipelines.
+///
+/// ```
+/// use clap_subopt_parser::{KeyInfo, KeyTable, SubOpt, SubOptError};
+///
+/// #[derive(Default)]
+/// struct Buf;
+///
+/// impl SubOpt for Buf {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> { Ok(()) }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> { Ok(()) }
+///     fn keys() -> Vec<KeyInfo> {
+///         vec![KeyInfo::new("offset").type_name("usize").default("0")]
+///     }
+/// }
+///
+/// let table = KeyTable::of::<Buf>();
+/// assert!(table.type_name.ends_with("Buf"));
+/// assert_eq!(table.keys[0].type_name, Some("usize"));
+/// assert_eq!(table.position("offset"), Some(0));
+/// assert_eq!(table.find("offset").unwrap().type_name, Some("usize"));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct KeyTable {
+    /// Rust type name of the [`SubOpt`](crate::SubOpt) type, see [`std::any::type_name`]
+    pub type_name: &'static str,
+    /// The keys, including hidden ones
+    pub keys: Vec<KeyInfo>,
+    /// Hash table of the names and aliases, with their positions in `keys`
+    index: Vec<Option<(&'static str, usize)>>,
+}
+
+impl std::fmt::Debug for KeyTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("KeyTable")
+            .field("type_name", &self.type_name)
+            .field("keys", &self.keys)
+            .finish()
+    }
+}
+
+impl KeyTable {
+    /// The key table of `T`
+    pub fn of<T: crate::SubOpt>() -> Self {
+        let keys = T::keys();
+        let names = keys
+            .iter()
+            .enumerate()
+            .flat_map(|(i, key)| {
+                std::iter::once(key.name)
+                    .chain(key.aliases.iter().copied())
+                    .map(move |name| (i, name))
+            })
+            .collect::<Vec<_>>();
+        let size = (names.len() * 2).next_power_of_two();
+        let mut index = vec![None; size];
+        for (i, name) in names {
+            let mut slot = hash(name.as_bytes()) as usize % size;
+            while index[slot].is_some() {
+                slot = (slot + 1) % size;
+            }
+            index[slot] = Some((name, i));
+        }
+        Self {
+            type_name: std::any::type_name::<T>(),
+            keys,
+            index,
+        }
+    }
+    /// The key named `key`, matching aliases too
+    pub fn find(&self, key: &str) -> Option<&KeyInfo> {
+        let size = self.index.len();
+        let mut slot = hash(key.as_bytes()) as usize % size;
+        while let Some((name, i)) = self.index[slot] {
+            if name == key {
+                match self.keys.get(i) {
+                    Some(info) if info.matches(key) => return Some(info),
+                    _ => break,
+                }
+            }
+            slot = (slot + 1) % size;
+        }
+        // Not indexed, or the keys were changed after building the index
+        self.keys.iter().find(|info| info.matches(key))
+    }
+    /// The key table of `T`, built once per type and shared by all parsers.
+    ///
+    /// [`SubOptParser`](crate::SubOptParser) looks up keys in this table, so repeated parses,
+    /// like those of the values of a `Vec<T>` argument, don't call
+    /// [`SubOpt::keys`](crate::SubOpt::keys) again.
+    ///
+    /// ```
+    /// # use clap_subopt_parser::{KeyInfo, KeyTable, SubOpt, SubOptError};
+    /// # #[derive(Default)]
+    /// # struct Buf;
+    /// # impl SubOpt for Buf {
+    /// #     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> { Ok(()) }
+    /// #     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> { Ok(()) }
+    /// #     fn keys() -> Vec<KeyInfo> { vec![KeyInfo::new("offset")] }
+    /// # }
+    /// use std::sync::Arc;
+    ///
+    /// let table = KeyTable::cached::<Buf>();
+    /// assert!(Arc::ptr_eq(&table, &KeyTable::cached::<Buf>()));
+    /// assert_eq!(*table, KeyTable::of::<Buf>());
+    /// ```
+    pub fn cached<T: crate::SubOpt>() -> Arc<Self> {
+        static CACHE: OnceLock<Mutex<HashMap<TypeId, Arc<KeyTable>>>> = OnceLock::new();
+        thread_local! {
+            // Copies of the shared entries, so repeated parses don't take the lock
+            static LOCAL: RefCell<HashMap<TypeId, Arc<KeyTable>>> = Default::default();
+        }
+        let id = TypeId::of::<T>();
+        if let Some(table) = LOCAL.with(|local| local.borrow().get(&id).cloned()) {
+            return table;
+        }
+        let cache = CACHE.get_or_init(Default::default);
+        let lock = || cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let cached = lock().get(&id).cloned();
+        let table = match cached {
+            Some(table) => table,
+            None => {
+                // Built without holding the lock, as `keys` may look up other tables
+                let table = Arc::new(Self::of::<T>());
+                lock().entry(id).or_insert(table).clone()
+            }
+        };
+        LOCAL.with(|local| local.borrow_mut().insert(id, table.clone()));
+        table
+    }
+    /// The position of `key` in declaration order, matching aliases too.
+    ///
+    /// Useful for sorting things by key in the same order as the generated help.
+    pub fn position(&self, key: &str) -> Option<usize> {
+        self.keys.iter().position(|k| k.matches(key))
+    }
+    /// Convert into JSON, with one object per key
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> serde_json::Value {
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| {
+                serde_json::json!({
+                    "name": key.name,
+                    "aliases": key.aliases,
+                    "help": key.help,
+                    "value_name": key.value_name,
+                    "type_name": key.type_name,
+                    "kind": key.kind.map(ValueKind::to_json),
+                    "required": key.required,
+                    "default": key.default,
+                    "env": key.env,
+                    "since": key.since,
+                    "category": key.category,
+                    "hidden": key.hidden,
+                    "secret": key.secret,
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({ "type_name": self.type_name, "keys": keys })
+    }
+}
+
+/// Group the keys that aren't hidden by category.
+///
+/// Keys without a category come first, then the categories in order of first appearance.
+pub(crate) fn grouped(keys: &[KeyInfo]) -> Vec<(Option<&'static str>, Vec<&KeyInfo>)> {
+    let mut groups: Vec<(Option<&'static str>, Vec<&KeyInfo>)> = vec![(None, Vec::new())];
+    for key in keys.iter().filter(|k| !k.hidden) {
+        match groups
+            .iter_mut()
+            .find(|(category, _)| *category == key.category)
+        {
+            Some((_, group)) => group.push(key),
+            None => groups.push((key.category, vec![key])),
+        }
+    }
+    groups.retain(|(_, group)| !group.is_empty());
+    groups
+}
+
+/// FNV-1a hash of `bytes`, for the index of [`KeyTable`]
+fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -5,8 +5,9 @@
 //! # Derive usage
 //!
 //! ```
+//! # #[cfg(feature = "clap")] {
 //! use clap::Parser;
-//! use clap_subopt_parser::{SubOpt, SubOptError, SubOptParser};
+//! use clap_subopt_parser::{parse_kv, SubOpt, SubOptError, SubOptParser};
 //!
 //! #[derive(clap::Parser, Debug)]
 //! struct Args {
@@ -32,16 +33,8 @@
 //!     }
 //!     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
 //!         match k {
-//!             "source" => {
-//!                 self.source = v
-//!                     .parse::<usize>()
-//!                     .map_err(|e| SubOptError::Custom(e.to_string()))?
-//!             }
-//!             "offset" => {
-//!                 self.offset = v
-//!                     .parse::<usize>()
-//!                     .map_err(|e| SubOptError::Custom(e.to_string()))?
-//!             }
+//!             "source" => self.source = parse_kv(k, v)?,
+//!             "offset" => self.offset = parse_kv(k, v)?,
 //!             k => return Err(SubOptError::UnknownKey(k.into())),
 //!         }
 //!         Ok(())
@@ -50,56 +43,2233 @@
 //!
 //!
 //! eprintln!("{:#?}", Args::parse());
+//! # }
 //! ```
 //!
+//! # Syntax
+//!
+//! Sub-options are separated by `:` ([`DEFAULT_SEPARATOR`]), and keys are separated from values
+//! by the first `=`.
+//! Separators inside square brackets don't split, so values like `addr=[127.0.0.1:80]` can
+//! contain colons. Types can use other separators, see [`SubOpt::SEPARATOR`], [`Delimited`] and
+//! [`QueryString`].
+//!
+//! # Debugging
+//!
+//! If the environment variable `CLAP_SUBOPT_DEBUG` is set to `1`, the parser prints how it
+//! splits arguments to stderr: the separators, and each sub-option with the method it was passed
+//! to and the outcome. The values of [secret](KeyInfo::secret) keys are hidden.
+//!
+//! # Features
+//!
+//! - `clap` (default): The clap integration, like the [`TypedValueParser`] implementations,
+//!   [`help::ArgExt`] and [`SubOptError::format`]. Without it, the parser, the [`SubOpt`] trait
+//!   and [`SubOptError`] can be used without depending on clap. The integration is for clap 4.
+//! - `json`: Add [`SubOptError::to_json`], an option to report errors as JSON,
+//!   JSON Schema generation in [`docs`], and [`KeyTable::to_json`]. With `serde`, accept JSON
+//!   objects in [`SerdeSubOptParser`], and JSON files in [`SubOptParser::parse_with_config`].
+//! - `prompt`: Add [`SubOptParser::prompt`], to ask for missing required keys interactively.
+//! - `rayon`: Add [`SubOptParser::par_parse_many`], for parsing large batches in parallel.
+//! - `serde`: Add [`SerdeSubOptParser`], for parsing any `Deserialize` type, and
+//!   [`SerializeSubOpt`], for serializing [`SubOptDisplay`] values.
+//! - `toml`: Accept TOML inline tables in [`SerdeSubOptParser`], and TOML files in
+//!   [`SubOptParser::parse_with_config`].
+//! - `yaml`: Accept YAML flow mappings in [`SerdeSubOptParser`].
+//! - `figment`: Add [`SubOptProvider`], for merging sub-options into figment configurations.
+//! - `tracing`: Emit a `tracing` span for each parsed argument, and an event for each
+//!   sub-option, with its key, the length of its value and whether it was accepted. Values are
+//!   not logged.
+//! - `chrono`: Add [`values::timestamp`] and [`values::date`].
+//! - `expr`: Add [`values::expr`].
+//! - `fuzzing`: Add [`testing::arbitrary_arg`], [`testing::ValidArg`] and
+//!   [`testing::arg_strategy`], for generating valid arguments from key metadata with
+//!   `arbitrary` and `proptest`.
+//! - `glob`: Add [`values::glob`].
+//! - `humantime`: Add [`values::duration`].
+//! - `num-bigint`: Support `BigInt` and `BigUint` in [`values::int`], for values that don't
+//!   fit in a `u128`.
+//! - `regex`: Add [`values::regex`].
+//! - `schemars`: Add [`docs::root_schema`] and [`docs::schema_object`].
+//! - `semver`: Add [`values::version`] and [`values::version_req`].
+//! - `url`: Add [`values::url`] and [`SubOptParser::url_syntax`].
+//! - `uuid`: Add [`values::uuid`].
+//! - `mime`: Add [`values::mime`].
+//! - `mangen`: Add [`introspect::Registry::render_man`], for clap_mangen man pages with the
+//!   sub-options of the arguments.
+//! - `miette`: Implement `miette::Diagnostic` for [`SubOptError`], with a label pointing
+//!   at the offending sub-option.
+//!
 
 #![warn(missing_docs)]
 
+mod builder;
+pub mod completion;
+#[cfg(any(feature = "json", feature = "toml"))]
+mod config;
+mod context;
+#[cfg(feature = "serde")]
+mod de;
+mod debug;
+mod display;
+pub mod docs;
+mod dotenv;
+mod error;
+#[cfg(feature = "clap")]
+mod exploded;
+#[cfg(feature = "expr")]
+mod expr;
+mod fields;
+#[cfg(feature = "clap")]
+mod flatten;
+pub mod help;
+#[cfg(feature = "clap")]
+pub mod introspect;
+mod keys;
+mod limits;
+mod macros;
+mod messages;
+mod position;
+pub mod presets;
+#[cfg(feature = "prompt")]
+mod prompt;
+mod provenance;
+#[cfg(feature = "figment")]
+mod provider;
+mod registry;
+mod schema;
+#[cfg(feature = "serde")]
+mod ser;
+pub mod testing;
+mod types;
+mod validate;
+pub mod values;
+mod warning;
+
+pub use builder::SubOptBuilder;
+pub use context::{SubOptWithContext, WithContext};
+#[cfg(feature = "serde")]
+pub use de::SerdeSubOptParser;
+pub use display::SubOptDisplay;
+pub use dotenv::DotEnv;
+pub use error::{parse_kv, summarize, SubOptError};
+#[cfg(feature = "clap")]
+pub use exploded::ExplodedArgs;
+pub use fields::KeyedFields;
+#[cfg(feature = "clap")]
+pub use flatten::{Flatten, SubOptFlag};
+pub use keys::{KeyInfo, KeyTable, ValueKind};
+pub use limits::{fuzz_parse, parse_untrusted, Limits};
+#[doc(hidden)]
+pub use macros::__is_bool;
+pub use messages::{English, Messages};
+pub use position::SegmentPosition;
+#[cfg(feature = "prompt")]
+pub use prompt::{Prompt, Terminal};
+pub use provenance::{Provenance, Source};
+#[cfg(feature = "figment")]
+pub use provider::SubOptProvider;
+pub use registry::SubOptRegistry;
+#[cfg(feature = "clap")]
+pub use schema::SchemaSubOptParser;
+pub use schema::{KeySchema, SchemaValue};
+#[cfg(feature = "serde")]
+pub use ser::SerializeSubOpt;
+pub use types::{
+    Chained, Delimited, Either, EmptyStringError, KvMap, Lenient, NativeDelimited, Nested,
+    NonEmptyString, Positional, QueryString, Redacted, RedactedError, TrimmedString, TypedKvMap,
+};
+pub use validate::{validate_str, Report};
+pub use warning::{take_warnings, warn, SubOptWarning, MAX_WARNINGS};
+
+#[cfg(feature = "clap")]
 use clap::builder::TypedValueParser;
 use std::marker::PhantomData;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+/// Where [`SubOptParser::parse_until`] takes the values of the keys not given from
+#[cfg_attr(not(feature = "clap"), allow(dead_code))]
+enum Fallbacks<'a> {
+    /// The [environment](KeyInfo::env) and [prompts](SubOptParser::prompt)
+    Live,
+    /// The environment and prompts, recording the values for a [`Deferred`] parse
+    Record(&'a mut Vec<FallbackValue>),
+    /// The values recorded by an earlier parse of the argument, without debug output
+    Replay(&'a [FallbackValue]),
+}
+
+/// A key not given, the value it was given instead, and where the value came from
+type FallbackValue = (&'static str, String, Source);
+
+/// Creates the initial values of a [`SubOptParser`]
+enum Init<T> {
+    /// A function, like `T::default`
+    Fn(fn() -> T),
+    /// A [factory](SubOptParser::with_factory) or [template](SubOptParser::with_template)
+    Factory(Arc<dyn Fn() -> T + Send + Sync>),
+}
+
+impl<T> Clone for Init<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Init::Fn(f) => Init::Fn(*f),
+            Init::Factory(f) => Init::Factory(f.clone()),
+        }
+    }
+}
+
+impl<T> Init<T> {
+    fn create(&self) -> T {
+        match self {
+            Init::Fn(f) => f(),
+            Init::Factory(f) => f(),
+        }
+    }
+}
 
-/// The [`TypedValueParser`] implementation
-#[derive(Default)]
+/// The [`TypedValueParser`] implementation.
+///
+/// Parsers compare equal if their settings are. Messages, prompts, templates and factories are
+/// compared by address. Parsers without a [factory](SubOptParser::with_factory) or
+/// [template](SubOptParser::with_template) are cheap to clone. The parser is not `Copy`,
+/// because factories and templates are reference counted.
+///
+/// ```
+/// # #[derive(Default, Clone)] struct Buf;
+/// # impl clap_subopt_parser::SubOpt for Buf {
+/// #     fn update_from_value(&mut self, _: &str) -> Result<(), SubOptError> { Ok(()) }
+/// #     fn update_from_kvpair(&mut self, _: &str, _: &str) -> Result<(), SubOptError> { Ok(()) }
+/// # }
+/// use clap_subopt_parser::{SubOptError, SubOptParser};
+///
+/// #[derive(Debug, Clone, PartialEq, Eq)]
+/// struct Parsers {
+///     buf: SubOptParser<Buf>,
+/// }
+///
+/// let parsers = Parsers { buf: SubOptParser::default() };
+/// assert_eq!(parsers.buf, SubOptParser::<Buf>::new());
+/// assert_ne!(parsers.buf, parsers.buf.clone().allow_empty(true));
+/// ```
 pub struct SubOptParser<T> {
-    _opt: PhantomData<T>,
+    messages: &'static dyn Messages,
+    key_messages: &'static [(&'static str, &'static str)],
+    json_errors: bool,
+    url_syntax: bool,
+    csv_header: Option<&'static [&'static str]>,
+    allow_empty: bool,
+    help_key: Option<&'static str>,
+    keys_as_possible_values: bool,
+    default_keyword: Option<&'static str>,
+    min_segments: usize,
+    max_segments: Option<usize>,
+    #[cfg(feature = "prompt")]
+    prompt: Option<&'static dyn Prompt>,
+    init: Init<T>,
+    _opt: PhantomData<fn() -> T>,
+}
+
+impl<T: Default> SubOptParser<T> {
+    /// Create a parser that starts parsing from `T::default()`, like [`Default::default`].
+    ///
+    /// This and the setters are `const`, so configured parsers can be put in statics and
+    /// constants.
+    ///
+    /// ```
+    /// # #[derive(Default)] struct Buf;
+    /// # impl clap_subopt_parser::SubOpt for Buf {
+    /// #     fn update_from_value(&mut self, _: &str) -> Result<(), SubOptError> { Ok(()) }
+    /// #     fn update_from_kvpair(&mut self, _: &str, _: &str) -> Result<(), SubOptError> { Ok(()) }
+    /// # }
+    /// use clap_subopt_parser::{SubOptError, SubOptParser};
+    ///
+    /// static BUF_PARSER: SubOptParser<Buf> = SubOptParser::new().allow_empty(true);
+    ///
+    /// assert!(BUF_PARSER.parse("").is_ok());
+    /// ```
+    pub const fn new() -> Self {
+        Self::with_init(Init::Fn(T::default))
+    }
+}
+
+impl<T: Default> Default for SubOptParser<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> Clone for SubOptParser<T> {
     fn clone(&self) -> Self {
-        Self { _opt: PhantomData }
+        Self {
+            messages: self.messages,
+            key_messages: self.key_messages,
+            json_errors: self.json_errors,
+            url_syntax: self.url_syntax,
+            csv_header: self.csv_header,
+            allow_empty: self.allow_empty,
+            help_key: self.help_key,
+            keys_as_possible_values: self.keys_as_possible_values,
+            default_keyword: self.default_keyword,
+            min_segments: self.min_segments,
+            max_segments: self.max_segments,
+            #[cfg(feature = "prompt")]
+            prompt: self.prompt,
+            init: self.init.clone(),
+            _opt: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for SubOptParser<T> {
+    fn eq(&self, other: &Self) -> bool {
+        #[cfg(feature = "prompt")]
+        match (self.prompt, other.prompt) {
+            (Some(a), Some(b))
+                if std::ptr::eq(a as *const _ as *const (), b as *const _ as *const ()) => {}
+            (None, None) => {}
+            _ => return false,
+        }
+        std::ptr::eq(
+            self.messages as *const _ as *const (),
+            other.messages as *const _ as *const (),
+        ) && self.key_messages == other.key_messages
+            && self.json_errors == other.json_errors
+            && self.url_syntax == other.url_syntax
+            && self.csv_header == other.csv_header
+            && self.allow_empty == other.allow_empty
+            && self.help_key == other.help_key
+            && self.keys_as_possible_values == other.keys_as_possible_values
+            && self.default_keyword == other.default_keyword
+            && self.min_segments == other.min_segments
+            && self.max_segments == other.max_segments
+            && match (&self.init, &other.init) {
+                // Only `new` creates `Init::Fn`, always with `T::default`
+                (Init::Fn(_), Init::Fn(_)) => true,
+                (Init::Factory(a), Init::Factory(b)) => Arc::ptr_eq(a, b),
+                _ => false,
+            }
+    }
+}
+
+impl<T> Eq for SubOptParser<T> {}
+
+impl<T> std::fmt::Debug for SubOptParser<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SubOptParser")
+            .field("type", &std::any::type_name::<T>())
+            .field("key_messages", &self.key_messages)
+            .field("json_errors", &self.json_errors)
+            .field("url_syntax", &self.url_syntax)
+            .field("csv_header", &self.csv_header)
+            .field("allow_empty", &self.allow_empty)
+            .field("help_key", &self.help_key)
+            .field("keys_as_possible_values", &self.keys_as_possible_values)
+            .field("default_keyword", &self.default_keyword)
+            .field("min_segments", &self.min_segments)
+            .field("max_segments", &self.max_segments)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> SubOptParser<T> {
+    /// Create a parser that starts parsing from values created by `factory`, instead of
+    /// `T::default()`.
+    ///
+    /// This allows [`SubOpt`] types that don't implement [`Default`].
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_kv, SubOpt, SubOptError, SubOptParser};
+    ///
+    /// #[derive(Debug, Clone)]
+    /// struct Device {
+    ///     path: String,
+    ///     queue_depth: u32,
+    /// }
+    ///
+    /// impl SubOpt for Device {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         self.path = k.into();
+    ///         Ok(())
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "queue_depth" => self.queue_depth = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let parser = SubOptParser::with_factory(|| Device { path: "/dev/null".into(), queue_depth: 32 });
+    /// let dev = parser.parse("/dev/sda").unwrap();
+    /// assert_eq!((dev.path.as_str(), dev.queue_depth), ("/dev/sda", 32));
+    /// ```
+    ///
+    /// The factory is shared between clones of the parser.
+    pub fn with_factory<F>(factory: F) -> Self
+    where
+        F: Fn() -> T + Send + Sync + 'static,
+    {
+        Self::with_init(Init::Factory(Arc::new(factory)))
+    }
+    const fn with_init(init: Init<T>) -> Self {
+        Self {
+            messages: &English,
+            key_messages: &[],
+            json_errors: false,
+            url_syntax: false,
+            csv_header: None,
+            allow_empty: false,
+            help_key: None,
+            keys_as_possible_values: false,
+            default_keyword: Some("default"),
+            min_segments: 0,
+            max_segments: None,
+            #[cfg(feature = "prompt")]
+            prompt: None,
+            init,
+            _opt: PhantomData,
+        }
+    }
+    /// Require at least `n` sub-options, which is 0 by default.
+    ///
+    /// Otherwise, parsing fails with [`SubOptError::SegmentCount`].
+    ///
+    /// ```
+    /// use clap_subopt_parser::{SubOptError, SubOptParser};
+    /// # #[derive(Debug, Default)] struct Buf;
+    /// # impl clap_subopt_parser::SubOpt for Buf {
+    /// #     fn update_from_value(&mut self, _: &str) -> Result<(), SubOptError> { Ok(()) }
+    /// #     fn update_from_kvpair(&mut self, _: &str, _: &str) -> Result<(), SubOptError> { Ok(()) }
+    /// # }
+    ///
+    /// let parser = SubOptParser::<Buf>::default().min_segments(3).max_segments(Some(3));
+    /// assert!(parser.parse("a:b:c").is_ok());
+    /// assert_eq!(
+    ///     parser.parse("a:b").unwrap_err().to_string(),
+    ///     "Expected exactly 3 sub-options, got 2"
+    /// );
+    /// ```
+    #[must_use]
+    pub const fn min_segments(mut self, n: usize) -> Self {
+        self.min_segments = n;
+        self
+    }
+    /// Allow at most `max` sub-options, or any number for `None`, which is the default.
+    ///
+    /// Otherwise, parsing fails with [`SubOptError::SegmentCount`].
+    #[must_use]
+    pub const fn max_segments(mut self, max: Option<usize>) -> Self {
+        self.max_segments = max;
+        self
+    }
+    /// Use `messages` for the error messages, for localization
+    #[must_use]
+    pub const fn messages(mut self, messages: &'static dyn Messages) -> Self {
+        self.messages = messages;
+        self
+    }
+    /// Accept an empty or whitespace-only argument, yielding the initial value.
+    ///
+    /// This is useful for arguments that can be given both bare and configured, like
+    /// `--cache=` and `--cache=size=4096`. Otherwise, such arguments are an
+    /// [`SubOptError::EmptyArgument`] error.
+    #[must_use]
+    pub const fn allow_empty(mut self, yes: bool) -> Self {
+        self.allow_empty = yes;
+        self
+    }
+    /// Start parsing from a clone of `template` instead of `T::default()`.
+    ///
+    /// This allows layering, for example with `template` loaded from a configuration file,
+    /// and the sub-options given on the command line overriding it.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_kv, SubOpt, SubOptError, SubOptParser};
+    ///
+    /// #[derive(Debug, Default, Clone)]
+    /// struct Buf {
+    ///     source: u32,
+    ///     offset: u64,
+    /// }
+    ///
+    /// impl SubOpt for Buf {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "source" => self.source = parse_kv(k, v)?,
+    ///             "offset" => self.offset = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let config = Buf { source: 2, offset: 4096 };
+    /// let parser = SubOptParser::default().with_template(config);
+    /// let buf = parser.parse("offset=0").unwrap();
+    /// assert_eq!((buf.source, buf.offset), (2, 0));
+    /// ```
+    ///
+    /// The template is shared between clones of the parser.
+    #[must_use]
+    pub fn with_template(mut self, template: T) -> Self
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        self.init = Init::Factory(Arc::new(move || template.clone()));
+        self
+    }
+    /// Set the value that shows the [key table](help::key_table), like `--buf help`.
+    ///
+    /// The table is returned as a `DisplayHelp` clap error, which `get_matches` prints before
+    /// exiting successfully. This is disabled (`None`) by default, and only has an effect if
+    /// [`SubOpt::keys`] is implemented.
+    ///
+    /// ```
+    /// # #[cfg(feature = "clap")] {
+    /// use clap::{error::ErrorKind, Arg, Command};
+    /// use clap_subopt_parser::{subopt, SubOptParser};
+    ///
+    /// subopt! {
+    ///     #[derive(Clone)]
+    ///     struct Buf {
+    ///         offset: u64,
+    ///     }
+    /// }
+    ///
+    /// let cmd = Command::new("myapp").arg(
+    ///     Arg::new("buf")
+    ///         .long("buf")
+    ///         .value_parser(SubOptParser::<Buf>::default().help_key(Some("help"))),
+    /// );
+    /// let err = cmd.try_get_matches_from(["myapp", "--buf", "help"]).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::DisplayHelp);
+    /// assert!(err.to_string().contains("offset"));
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn help_key(mut self, key: Option<&'static str>) -> Self {
+        self.help_key = key;
+        self
+    }
+    /// Report the [keys](SubOpt::keys) of `T` to clap as possible values, for help and
+    /// completion.
+    ///
+    /// clap then lists the keys with their [translated](Messages::key_help) help under the
+    /// argument, and completion scripts generated by clap_complete offer them. Arguments are
+    /// still validated by this parser, so `key=value` combinations are accepted, but clap's
+    /// help presents the keys as the only values, so this is disabled by default.
+    ///
+    /// ```
+    /// # #[cfg(feature = "clap")] {
+    /// use clap::{Arg, Command};
+    /// use clap_subopt_parser::{parse_kv, KeyInfo, SubOpt, SubOptError, SubOptParser};
+    ///
+    /// #[derive(Default, Clone)]
+    /// struct Buf {
+    ///     offset: u64,
+    ///     sync: bool,
+    /// }
+    ///
+    /// impl SubOpt for Buf {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "sync" => self.sync = true,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "offset" => self.offset = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///     fn keys() -> Vec<KeyInfo> {
+    ///         vec![KeyInfo::new("offset").help("Offset in bytes"), KeyInfo::new("sync")]
+    ///     }
+    /// }
+    ///
+    /// let arg = Arg::new("buf")
+    ///     .long("buf")
+    ///     .value_parser(SubOptParser::<Buf>::default().keys_as_possible_values(true));
+    /// let values = arg.get_possible_values();
+    /// let names: Vec<_> = values.iter().map(|v| v.get_name()).collect();
+    /// assert_eq!(names, ["offset", "sync"]);
+    /// assert_eq!(values[0].get_help().unwrap().to_string(), "Offset in bytes");
+    /// let m = Command::new("myapp")
+    ///     .arg(arg)
+    ///     .get_matches_from(["myapp", "--buf", "offset=4:sync"]);
+    /// assert_eq!(m.get_one::<Buf>("buf").unwrap().offset, 4);
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn keys_as_possible_values(mut self, yes: bool) -> Self {
+        self.keys_as_possible_values = yes;
+        self
+    }
+    /// Set the value that yields the initial value, like `--buf default`.
+    ///
+    /// This is `default` by default, and lets scripts ask for the baseline behavior explicitly.
+    /// No [environment fallbacks](KeyInfo::env) are applied, and no required keys are prompted
+    /// for.
+    /// `None` disables it.
+    ///
+    /// ```
+    /// # #[derive(Debug, Default, Clone, PartialEq)] struct Buf;
+    /// # impl clap_subopt_parser::SubOpt for Buf {
+    /// #     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    /// #         Err(SubOptError::UnknownKey(k.into()))
+    /// #     }
+    /// #     fn update_from_kvpair(&mut self, k: &str, _: &str) -> Result<(), SubOptError> {
+    /// #         Err(SubOptError::UnknownKey(k.into()))
+    /// #     }
+    /// # }
+    /// use clap_subopt_parser::{SubOptError, SubOptParser};
+    ///
+    /// let parser = SubOptParser::<Buf>::default();
+    /// assert_eq!(parser.parse("default").unwrap(), Buf);
+    /// assert!(parser.default_keyword(None).parse("default").is_err());
+    /// ```
+    #[must_use]
+    pub const fn default_keyword(mut self, keyword: Option<&'static str>) -> Self {
+        self.default_keyword = keyword;
+        self
+    }
+    /// Replace the error messages for specific keys.
+    ///
+    /// `key_messages` maps keys to message templates, in which `{key}`, `{value}` and `{error}`
+    /// are replaced with the key, the value, and the original error message.
+    ///
+    /// ```
+    /// # #[derive(Default)] struct Buf;
+    /// # impl clap_subopt_parser::SubOpt for Buf {
+    /// #     fn update_from_value(&mut self, _: &str) -> Result<(), SubOptError> { Ok(()) }
+    /// #     fn update_from_kvpair(&mut self, k: &str, _: &str) -> Result<(), SubOptError> {
+    /// #         Err(SubOptError::invalid_value(k, "unaligned"))
+    /// #     }
+    /// # }
+    /// use clap_subopt_parser::{SubOptError, SubOptParser};
+    ///
+    /// let parser = SubOptParser::<Buf>::default()
+    ///     .key_messages(&[("offset", "offset must be a multiple of 512 bytes, not {value}")]);
+    /// let err = parser.parse("offset=100").map(drop).unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "'offset=100': offset must be a multiple of 512 bytes, not 100"
+    /// );
+    /// ```
+    ///
+    /// Unknown key errors are not affected.
+    #[must_use]
+    pub const fn key_messages(
+        mut self,
+        key_messages: &'static [(&'static str, &'static str)],
+    ) -> Self {
+        self.key_messages = key_messages;
+        self
+    }
+    /// Ask for the values of [required](KeyInfo::required) keys that weren't given, using
+    /// `prompt`, like [`Terminal`].
+    ///
+    /// Keys with an [environment variable fallback](KeyInfo::env) that is set are not asked for.
+    #[cfg(feature = "prompt")]
+    #[must_use]
+    pub const fn prompt(mut self, prompt: &'static dyn Prompt) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+    /// Report errors as JSON objects, see [`SubOptError::to_json`].
+    ///
+    /// This is meant for wrapper tools that consume the errors programmatically.
+    /// Note that clap still prefixes the message with `error: `.
+    #[cfg(feature = "json")]
+    #[must_use]
+    pub const fn json_errors(mut self, yes: bool) -> Self {
+        self.json_errors = yes;
+        self
+    }
+    /// Parse URLs instead of sub-options, like `postgres://user@db:5432/app?pool=8`.
+    ///
+    /// The components are passed to the [`SubOpt`] implementation as the keys `scheme`, `user`,
+    /// `password`, `host`, `port`, `path` and `fragment`, when present, and the query
+    /// parameters as their own keys, with parameters without a value passed as bare values.
+    /// The `port` is only passed if given explicitly, and the `path` includes the leading `/`.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{KvMap, SubOptParser};
+    ///
+    /// let parser = SubOptParser::<KvMap>::default().url_syntax(true);
+    /// let db = parser.parse("postgres://admin@db:5432/app?pool=8&name=a%20b&ro").unwrap();
+    /// assert_eq!(db.pairs["scheme"], "postgres");
+    /// assert_eq!(db.pairs["user"], "admin");
+    /// assert_eq!(db.pairs["host"], "db");
+    /// assert_eq!(db.pairs["port"], "5432");
+    /// assert_eq!(db.pairs["path"], "/app");
+    /// assert_eq!(db.pairs["pool"], "8");
+    /// assert_eq!(db.pairs["name"], "a b");
+    /// assert_eq!(db.values, ["ro"]);
+    /// assert!(parser.parse("localhost").is_err());
+    /// ```
+    #[cfg(feature = "url")]
+    #[must_use]
+    pub const fn url_syntax(mut self, yes: bool) -> Self {
+        self.url_syntax = yes;
+        self
+    }
+    /// Parse CSV rows instead of sub-options, with the fields given to the keys in `header`
+    /// positionally, like `10,20,red` for the header `x,y,color`.
+    ///
+    /// This is for arguments pasted from spreadsheets. Fields can be quoted like in CSV files,
+    /// empty fields are skipped, and more fields than keys are a [`SubOptError::SegmentCount`]
+    /// error. `None` parses sub-options, which is the default.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{KvMap, SubOptParser};
+    ///
+    /// let parser = SubOptParser::<KvMap>::default().csv_header(Some(&["x", "y", "color", "label"]));
+    /// let point = parser.parse(r#"10,,red,"a ""b"", c""#).unwrap();
+    /// assert_eq!(point.pairs["x"], "10");
+    /// assert!(!point.pairs.contains_key("y"));
+    /// assert_eq!(point.pairs["label"], r#"a "b", c"#);
+    /// assert!(parser.parse("1,2,red,a,b").is_err());
+    /// ```
+    #[must_use]
+    pub const fn csv_header(mut self, header: Option<&'static [&'static str]>) -> Self {
+        self.csv_header = header;
+        self
+    }
+    /// Parse into `Option<T>`, with `none`, `off` and `disabled` yielding `None`.
+    ///
+    /// This is for features that can be either configured or disabled, like `--cache size=64`
+    /// and `--cache off`. The values can be changed with
+    /// [`OptionalSubOptParser::none_values`].
+    ///
+    /// ```
+    /// use clap::{Arg, Command};
+    /// use clap_subopt_parser::{parse_kv, SubOpt, SubOptError, SubOptParser};
+    ///
+    /// #[derive(Debug, Default, Clone)]
+    /// struct Cache {
+    ///     size: u32,
+    /// }
+    ///
+    /// impl SubOpt for Cache {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "size" => self.size = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let cmd = Command::new("myapp").arg(
+    ///     Arg::new("cache")
+    ///         .long("cache")
+    ///         .value_parser(SubOptParser::<Cache>::default().optional()),
+    /// );
+    /// let m = cmd.clone().get_matches_from(["myapp", "--cache", "off"]);
+    /// assert!(m.get_one::<Option<Cache>>("cache").unwrap().is_none());
+    /// let m = cmd.get_matches_from(["myapp", "--cache", "size=64"]);
+    /// assert_eq!(m.get_one::<Option<Cache>>("cache").unwrap().as_ref().unwrap().size, 64);
+    /// ```
+    #[cfg(feature = "clap")]
+    pub fn optional(self) -> OptionalSubOptParser<T> {
+        OptionalSubOptParser {
+            parser: self,
+            none_values: &["none", "off", "disabled"],
+        }
+    }
+    /// Convert the parsed values with `f`, like [`TypedValueParser::map`]
+    #[cfg(feature = "clap")]
+    pub fn map<U, F>(self, f: F) -> MapSubOptParser<T, F>
+    where
+        F: Fn(T) -> U,
+    {
+        MapSubOptParser { parser: self, f }
+    }
+    /// Convert the parsed values with `f`, which can fail.
+    ///
+    /// Errors are reported like parse errors, so they can be [`SubOptError::Custom`] or
+    /// [`SubOptError::InvalidValue`] errors explaining what's wrong.
+    ///
+    /// ```
+    /// use clap::{Arg, Command};
+    /// use clap_subopt_parser::{parse_kv, SubOpt, SubOptError, SubOptParser};
+    ///
+    /// #[derive(Debug, Default, Clone)]
+    /// struct Range {
+    ///     start: u64,
+    ///     end: u64,
+    /// }
+    ///
+    /// impl SubOpt for Range {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "start" => self.start = parse_kv(k, v)?,
+    ///             "end" => self.end = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let parser = SubOptParser::<Range>::default().try_map(|r| {
+    ///     if r.start <= r.end {
+    ///         Ok(r.start..r.end)
+    ///     } else {
+    ///         Err(SubOptError::Custom("end is before start".into()))
+    ///     }
+    /// });
+    /// let cmd = Command::new("myapp").arg(
+    ///     Arg::new("range").long("range").value_parser(parser),
+    /// );
+    /// let m = cmd.clone().get_matches_from(["myapp", "--range", "start=1:end=4"]);
+    /// assert_eq!(m.get_one::<std::ops::Range<u64>>("range").unwrap(), &(1..4));
+    /// assert!(cmd.try_get_matches_from(["myapp", "--range", "start=4:end=1"]).is_err());
+    /// ```
+    #[cfg(feature = "clap")]
+    pub fn try_map<U, F>(self, f: F) -> TryMapSubOptParser<T, F>
+    where
+        F: Fn(T) -> Result<U, SubOptError>,
+    {
+        TryMapSubOptParser { parser: self, f }
+    }
+    /// Also yield the [`Provenance`] of the values, as `(T, Provenance)`.
+    ///
+    /// ```
+    /// use clap::{Arg, Command};
+    /// use clap_subopt_parser::{Provenance, SubOpt, SubOptError, SubOptParser};
+    /// # #[derive(Debug, Default, Clone)]
+    /// # struct Buf;
+    /// # impl SubOpt for Buf {
+    /// #     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    /// #         match k {
+    /// #             "sync" => Ok(()),
+    /// #             _ => Err(SubOptError::UnknownKey(k.into())),
+    /// #         }
+    /// #     }
+    /// #     fn update_from_kvpair(&mut self, k: &str, _: &str) -> Result<(), SubOptError> {
+    /// #         Err(SubOptError::UnknownKey(k.into()))
+    /// #     }
+    /// # }
+    ///
+    /// let parser = SubOptParser::<Buf>::default().with_provenance();
+    /// let m = Command::new("myapp")
+    ///     .arg(Arg::new("buf").long("buf").value_parser(parser))
+    ///     .get_matches_from(["myapp", "--buf", "sync"]);
+    /// let (_, provenance) = m.get_one::<(Buf, Provenance)>("buf").unwrap();
+    /// assert!(provenance.is_explicit("sync"));
+    /// ```
+    #[cfg(feature = "clap")]
+    pub fn with_provenance(self) -> ProvenanceSubOptParser<T> {
+        ProvenanceSubOptParser { parser: self }
+    }
+    /// Pass the index of each occurrence of the argument to [`SubOpt::set_occurrence`].
+    ///
+    /// The parser counts the values it parses, and clones share the count, so it continues
+    /// across commands using the same parser. Use [`OccurrenceSubOptParser::reset`] to start
+    /// over, for example before parsing another command line.
+    #[cfg(feature = "clap")]
+    pub fn with_occurrences(self) -> OccurrenceSubOptParser<T> {
+        OccurrenceSubOptParser {
+            parser: self,
+            next: Default::default(),
+        }
+    }
+    /// Yield the validated argument as a [`Deferred`] value, which is parsed again on access.
+    ///
+    /// clap requires values to be `Send + Sync`, which [`SubOpt`] types don't have to be.
+    /// This checks the argument when clap parses it, and only stores the argument, so types
+    /// containing [`Rc`](std::rc::Rc) or [`Cell`](std::cell::Cell) can be used with clap too.
+    ///
+    /// ```
+    /// use clap::{Arg, Command};
+    /// use clap_subopt_parser::{parse_kv, take_warnings, Deferred, SubOpt, SubOptError, SubOptParser};
+    /// use std::rc::Rc;
+    ///
+    /// #[derive(Default)]
+    /// struct Buf {
+    ///     name: Rc<String>,
+    /// }
+    ///
+    /// impl SubOpt for Buf {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "name" => self.name = Rc::new(v.to_owned()),
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let cmd = Command::new("myapp").arg(
+    ///     Arg::new("buf")
+    ///         .long("buf")
+    ///         .value_parser(SubOptParser::<Buf>::default().deferred()),
+    /// );
+    /// let m = cmd.clone().get_matches_from(["myapp", "--buf", ":name=scratch"]);
+    /// let buf = m.get_one::<Deferred<Buf>>("buf").unwrap().parse().unwrap();
+    /// assert_eq!(*buf.name, "scratch");
+    /// assert!(cmd.try_get_matches_from(["myapp", "--buf", "size=4"]).is_err());
+    ///
+    /// // Warnings are only emitted when clap parses the argument
+    /// assert_eq!(take_warnings().len(), 1);
+    /// ```
+    #[cfg(feature = "clap")]
+    pub fn deferred(self) -> DeferredSubOptParser<T> {
+        DeferredSubOptParser { parser: self }
+    }
+    /// Install a handler for unknown keys.
+    ///
+    /// The handler is called with the key and the value (`None` for bare values) whenever
+    /// the [`SubOpt`] implementation returns [`SubOptError::UnknownKey`], and decides what to do
+    /// with the sub-option. This is useful for forwarding unrecognized keys elsewhere.
+    #[cfg(feature = "clap")]
+    pub fn on_unknown_key<F>(self, f: F) -> OnUnknownKey<T, F>
+    where
+        F: Fn(&str, Option<&str>) -> UnknownKeyAction,
+    {
+        OnUnknownKey { parser: self, f }
+    }
+    /// Split `s` into segments at `sep`, and the segments into keys and values at `kv_sep`, and
+    /// feed them to `update`.
+    ///
+    /// If `update` breaks, the rest of the segments are skipped, and the offset of the segment
+    /// it broke at is returned for sub-options.
+    fn parse_into<V>(
+        &self,
+        s: &str,
+        (sep, kv_sep): (char, char),
+        val: &mut V,
+        mut update: impl FnMut(
+            &mut V,
+            &str,
+            Option<&str>,
+            SegmentPosition,
+        ) -> Result<ControlFlow<()>, SubOptError>,
+    ) -> Result<Option<usize>, SubOptError> {
+        if s.trim().is_empty() {
+            return if self.allow_empty {
+                Ok(None)
+            } else {
+                Err(SubOptError::EmptyArgument)
+            };
+        }
+        #[cfg(feature = "url")]
+        if self.url_syntax {
+            let segments = url_segments(s.trim())?;
+            let count = segments.len();
+            for (index, (k, v)) in segments.iter().enumerate() {
+                if update(val, k, v.as_deref(), SegmentPosition { index, count })?.is_break() {
+                    break;
+                }
+            }
+            return Ok(None);
+        }
+        if let Some(header) = self.csv_header {
+            let fields = csv_fields(s)?;
+            if fields.len() > header.len() {
+                return Err(SubOptError::SegmentCount {
+                    min: 0,
+                    max: Some(header.len()),
+                    count: fields.len(),
+                });
+            }
+            let count = fields.iter().filter(|v| !v.is_empty()).count();
+            let given = header.iter().zip(&fields).filter(|(_, v)| !v.is_empty());
+            for (index, (k, v)) in given.enumerate() {
+                if update(val, k, Some(v), SegmentPosition { index, count })?.is_break() {
+                    break;
+                }
+            }
+            return Ok(None);
+        }
+        let segments = tokenize(s, sep, kv_sep);
+        let count = Segments {
+            emit_warnings: false,
+            ..segments.clone()
+        }
+        .count();
+        for (index, (span, k, v)) in segments.enumerate() {
+            let flow = update(val, k, v, SegmentPosition { index, count })
+                .map_err(|e| e.in_segment(s, span.clone(), kv_sep))?;
+            if flow.is_break() {
+                return Ok(Some(span.start));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Split the URL `s` into components and query parameters, see [`SubOptParser::url_syntax`]
+#[cfg(feature = "url")]
+fn url_segments(s: &str) -> Result<Vec<(String, Option<String>)>, SubOptError> {
+    let url = url::Url::parse(s).map_err(|e| SubOptError::Custom(format!("Invalid URL: {}", e)))?;
+    let mut segments = vec![("scheme".to_owned(), Some(url.scheme().to_owned()))];
+    let mut push = |k: &str, v: Option<&str>| segments.push((k.to_owned(), v.map(str::to_owned)));
+    if !url.username().is_empty() {
+        push("user", Some(url.username()));
+    }
+    if let Some(password) = url.password() {
+        push("password", Some(password));
+    }
+    if let Some(host) = url.host_str() {
+        push("host", Some(host));
+    }
+    if let Some(port) = url.port() {
+        push("port", Some(&port.to_string()));
+    }
+    if !url.path().is_empty() && url.path() != "/" {
+        push("path", Some(url.path()));
+    }
+    for param in url.query().unwrap_or_default().split('&') {
+        let decode = |s| types::percent_decode(s).map_err(|e| SubOptError::invalid_value(param, e));
+        match param.split_once('=') {
+            Some((k, v)) => push(&decode(k)?, Some(&decode(v)?)),
+            None if !param.is_empty() => push(&decode(param)?, None),
+            None => {}
+        }
+    }
+    if let Some(fragment) = url.fragment() {
+        push("fragment", Some(fragment));
+    }
+    Ok(segments)
+}
+
+/// Split the CSV row `s` into fields, see [`SubOptParser::csv_header`]
+fn csv_fields(s: &str) -> Result<Vec<String>, SubOptError> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("a field");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted || field.is_empty() => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(SubOptError::Custom("Unterminated quote in CSV row".into()));
+    }
+    Ok(fields)
+}
+
+/// Split `s` at the occurrences of `sep` that are not inside square brackets, like
+/// [`tokenize`], but keeping empty sub-options and without warnings
+fn split_protected(s: &str, sep: char) -> impl Iterator<Item = &str> {
+    let segments = Segments {
+        s,
+        start: Some(0),
+        sep,
+        kv_sep: sep,
+        emit_warnings: false,
+        keep_empty: true,
+    };
+    segments.map(move |(span, _, _)| &s[span])
+}
+
+/// The separator between sub-options of types that don't choose their own, and of parsers
+/// without a type, like [`KeySchema`] and [`SubOptBuilder`].
+///
+/// This is `:` on every platform. As `:` collides with drive letters on Windows, use
+/// [`NativeDelimited`] to separate the sub-options of a type like the entries of `PATH`, or
+/// [`SubOptBuilder::separator`] for a parser built at runtime.
+pub const DEFAULT_SEPARATOR: char = ':';
+
+/// The separator between the entries of `PATH`, `;` on Windows and `:` elsewhere
+pub const PATH_SEPARATOR: char = if cfg!(windows) { ';' } else { ':' };
+
+/// Split `s` into sub-options at `sep`, and the sub-options at the first `kv_sep` into key and
+/// value, the same way the parser does.
+///
+/// Separators inside square brackets don't split. Yields the byte range of each sub-option, the
+/// key, and the value, if any. Empty sub-options are skipped with a
+/// [`SubOptWarning::EmptySegment`] warning, unless `sep` is whitespace, so runs of spaces
+/// separate like one. A `[` that is never closed protects nothing, and emits a
+/// [`SubOptWarning::UnclosedBracket`] warning. Long values are scanned with memchr, so multi-kilobyte arguments split
+/// quickly.
+///
+/// ```
+/// use clap_subopt_parser::{take_warnings, tokenize, SubOptWarning};
+///
+/// let tokens: Vec<_> = tokenize("addr=[127.0.0.1:80]:sync", ':', '=').collect();
+/// assert_eq!(tokens[0], (0..19, "addr", Some("[127.0.0.1:80]")));
+/// assert_eq!(tokens[1], (20..24, "sync", None));
+///
+/// let long = format!("data=[{}]:sync", "a:".repeat(4096));
+/// assert_eq!(tokenize(&long, ':', '=').map(|(_, k, _)| k).collect::<Vec<_>>(), ["data", "sync"]);
+///
+/// let tokens: Vec<_> = tokenize("a=[x:b=1", ':', '=').collect();
+/// assert_eq!(tokens, [(0..4, "a", Some("[x")), (5..8, "b", Some("1"))]);
+/// assert_eq!(take_warnings(), [SubOptWarning::UnclosedBracket { span: 0..8 }]);
+/// ```
+pub fn tokenize(s: &str, sep: char, kv_sep: char) -> Segments<'_> {
+    Segments {
+        s,
+        start: Some(0),
+        sep,
+        kv_sep,
+        emit_warnings: true,
+        keep_empty: false,
+    }
+}
+
+/// The length from which segments are scanned with memchr
+const MEMCHR_THRESHOLD: usize = 64;
+
+/// Find the end of the segment continuing in `bytes` at bracket depth `depth`, and the position
+/// of its first `kv_sep`, with memchr. Returns `None` if a bracket is left unclosed.
+///
+/// This is kept out of line, so the scanning of short segments stays small.
+#[inline(never)]
+fn scan_long(
+    bytes: &[u8],
+    sep: u8,
+    kv_sep: u8,
+    mut depth: usize,
+) -> Option<(usize, Option<usize>)> {
+    let mut pos = 0;
+    let end = loop {
+        // Inside brackets, only brackets matter
+        let found = match depth {
+            0 => memchr::memchr3(sep, b'[', b']', &bytes[pos..]),
+            _ => memchr::memchr2(b'[', b']', &bytes[pos..]),
+        };
+        let Some(i) = found.map(|i| pos + i) else {
+            if depth > 0 {
+                return None;
+            }
+            break bytes.len();
+        };
+        match bytes[i] {
+            b'[' => depth += 1,
+            b']' => depth = depth.saturating_sub(1),
+            _ => break i,
+        }
+        pos = i + 1;
+    };
+    Some((end, memchr::memchr(kv_sep, &bytes[..end])))
+}
+
+/// The iterator returned by [`tokenize`]
+#[derive(Debug, Clone)]
+pub struct Segments<'a> {
+    s: &'a str,
+    /// The start of the next segment, or `None` after the last one
+    start: Option<usize>,
+    sep: char,
+    kv_sep: char,
+    /// Whether empty segments and unclosed brackets emit warnings
+    emit_warnings: bool,
+    /// Whether empty segments are yielded instead of skipped
+    keep_empty: bool,
+}
+
+impl Segments<'_> {
+    /// Find the end of the segment starting at `start`, and the position of its first `kv_sep`.
+    ///
+    /// Returns `None` if a bracket in the rest of the argument is left unclosed.
+    fn scan_segment(&self, start: usize) -> Option<(usize, Option<usize>)> {
+        let rest = &self.s[start..];
+        let mut depth = 0usize;
+        if self.sep.is_ascii() && self.kv_sep.is_ascii() {
+            // Bytes of multi-byte characters never match ASCII separators, so the bytes can be
+            // scanned directly. The start is scanned byte by byte, and the rest of long segments
+            // with memchr, jumping between separators and brackets.
+            let bytes = rest.as_bytes();
+            let (sep, kv_sep) = (self.sep as u8, self.kv_sep as u8);
+            let mut kv = None;
+            let head = bytes.len().min(MEMCHR_THRESHOLD);
+            for (i, &b) in bytes[..head].iter().enumerate() {
+                match b {
+                    b'[' => depth += 1,
+                    b']' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                if b == sep && depth == 0 {
+                    return Some((start + i, kv.map(|kv| start + kv)));
+                }
+                if b == kv_sep && kv.is_none() {
+                    kv = Some(i);
+                }
+            }
+            let (end, tail_kv) = scan_long(&bytes[head..], sep, kv_sep, depth)?;
+            let kv = kv.or(tail_kv.map(|i| head + i));
+            return Some((start + head + end, kv.map(|kv| start + kv)));
+        }
+        let mut kv = None;
+        for (i, c) in rest.char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            if c == self.sep && depth == 0 {
+                return Some((start + i, kv));
+            }
+            if c == self.kv_sep && kv.is_none() {
+                kv = Some(start + i);
+            }
+        }
+        (depth == 0).then_some((self.s.len(), kv))
+    }
+    /// Like [`Segments::scan_segment`], ignoring brackets
+    fn scan_unprotected(&self, start: usize) -> (usize, Option<usize>) {
+        let end = self.s[start..]
+            .find(self.sep)
+            .map_or(self.s.len(), |i| start + i);
+        let kv = self.s[start..end].find(self.kv_sep).map(|i| start + i);
+        (end, kv)
+    }
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = (std::ops::Range<usize>, &'a str, Option<&'a str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.start?;
+            let (end, kv) = match self.scan_segment(start) {
+                Some(segment) => segment,
+                None => {
+                    // Rather than swallowing the rest of the argument, the bracket is taken
+                    // literally
+                    if self.emit_warnings {
+                        warn(SubOptWarning::UnclosedBracket {
+                            span: start..self.s.len(),
+                        });
+                    }
+                    self.scan_unprotected(start)
+                }
+            };
+            self.start = (end < self.s.len()).then(|| end + self.sep.len_utf8());
+            let span = start..end;
+            if span.is_empty() && !self.keep_empty {
+                if self.emit_warnings && !self.sep.is_whitespace() {
+                    warn(SubOptWarning::EmptySegment { span });
+                }
+                continue;
+            }
+            return Some(match kv {
+                Some(kv) => (
+                    span,
+                    &self.s[start..kv],
+                    Some(&self.s[kv + self.kv_sep.len_utf8()..end]),
+                ),
+                None => (span, &self.s[start..end], None),
+            });
+        }
     }
 }
 
-impl<T: SubOpt> TypedValueParser for SubOptParser<T> {
+impl<T: SubOpt> SubOptParser<T> {
+    /// Parse `s`, then apply the [environment fallbacks](KeyInfo::env) and prompts for the keys
+    /// not given, recording where the values came from
+    fn parse_with_fallbacks(
+        &self,
+        s: &str,
+        update: impl FnMut(&mut T, &str, Option<&str>) -> Result<(), SubOptError>,
+    ) -> Result<(T, Provenance), SubOptError> {
+        self.parse_from(self.init.create(), Provenance::default(), s, update)
+    }
+    /// Like [`parse_with_fallbacks`](SubOptParser::parse_with_fallbacks), starting from `val`
+    /// instead of the initial value, with the keys in `provenance` already set
+    fn parse_from(
+        &self,
+        val: T,
+        provenance: Provenance,
+        s: &str,
+        update: impl FnMut(&mut T, &str, Option<&str>) -> Result<(), SubOptError>,
+    ) -> Result<(T, Provenance), SubOptError> {
+        self.parse_until(val, provenance, s, false, Fallbacks::Live, update)
+            .map(|(val, provenance, _)| (val, provenance))
+    }
+    /// Like [`parse_from`](SubOptParser::parse_from), stopping at the first unknown key if
+    /// `prefix` is set, and returning the offset of the rest of `s`.
+    ///
+    /// The sub-options from the first unknown key on are not counted or recorded, so the
+    /// fallbacks apply to the keys only given there. The values of the keys not given are taken
+    /// from `fallbacks`.
+    fn parse_until(
+        &self,
+        mut val: T,
+        mut provenance: Provenance,
+        s: &str,
+        prefix: bool,
+        mut fallbacks: Fallbacks,
+        mut update: impl FnMut(&mut T, &str, Option<&str>) -> Result<(), SubOptError>,
+    ) -> Result<(T, Provenance, Option<usize>), SubOptError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "subopt_parse",
+            r#type = std::any::type_name::<T>(),
+            len = s.len()
+        )
+        .entered();
+        if self
+            .default_keyword
+            .is_some_and(|keyword| keyword == s.trim())
+        {
+            return Ok((val, provenance, None));
+        }
+        let table = KeyTable::cached::<T>();
+        let keys = &table.keys;
+        let seps = (T::SEPARATOR, T::KV_SEPARATOR);
+        let mut index = 0;
+        let debug = debug::enabled() && !matches!(fallbacks, Fallbacks::Replay(_));
+        if debug {
+            debug::start::<T>(s.len(), seps.0, seps.1);
+        }
+        let rest = self
+            .parse_into(s, seps, &mut val, |val, k, v, position| {
+                let key = table.find(k);
+                val.set_position(position);
+                let result = update(val, k, v);
+                if prefix && matches!(result, Err(SubOptError::UnknownKey(_))) {
+                    return Ok(ControlFlow::Break(()));
+                }
+                let source = Source::Argument { index };
+                match key {
+                    Some(key) => provenance.record(key.name, source),
+                    None => provenance.record(k.to_owned(), source),
+                }
+                index += 1;
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    key = key.map_or(k, |key| key.name),
+                    value_len = v.map(str::len),
+                    ok = result.is_ok(),
+                    "sub-option {}",
+                    index - 1
+                );
+                if debug {
+                    let secret = key.is_some_and(|key| key.secret);
+                    debug::segment(index - 1, k, v, secret, &result);
+                }
+                result.map(ControlFlow::Continue)
+            })
+            .map_err(|e| {
+                e.redact(seps.0, seps.1, |k| {
+                    keys.iter().any(|key| key.secret && key.matches(k))
+                })
+            })?;
+        if index < self.min_segments || self.max_segments.is_some_and(|max| index > max) {
+            return Err(SubOptError::SegmentCount {
+                min: self.min_segments,
+                max: self.max_segments,
+                count: index,
+            });
+        }
+        for key in keys {
+            #[cfg(feature = "prompt")]
+            let fallback = key.env.is_some() || (key.required && self.prompt.is_some());
+            #[cfg(not(feature = "prompt"))]
+            let fallback = key.env.is_some();
+            // Checked first, as looking up the provenance of every key is slow for many keys
+            if !fallback || provenance.source(key.name).is_some() {
+                continue;
+            }
+            let value = match &mut fallbacks {
+                Fallbacks::Live => self.fallback_value(key),
+                Fallbacks::Record(values) => {
+                    let value = self.fallback_value(key);
+                    if let Some((value, source)) = &value {
+                        values.push((key.name, value.clone(), source.clone()));
+                    }
+                    value
+                }
+                Fallbacks::Replay(values) => values
+                    .iter()
+                    .find(|(name, ..)| *name == key.name)
+                    .map(|(_, value, source)| (value.clone(), source.clone())),
+            };
+            if let Some((value, source)) = value {
+                let value = match key.value_name {
+                    Some(_) => Some(value.as_str()),
+                    None if !value.is_empty() => None,
+                    None => continue,
+                };
+                update(&mut val, key.name, value).map_err(|e| {
+                    fallback_error(e, (key.name, key.secret), value, seps.1, &source)
+                })?;
+                provenance.record(key.name, source);
+            }
+        }
+        let val = val.finish()?;
+        val.validate()?;
+        Ok((val, provenance, rest))
+    }
+    /// The value of the [environment variable](KeyInfo::env) of `key`, or else the prompted
+    /// value if it is required
+    fn fallback_value(&self, key: &KeyInfo) -> Option<(String, Source)> {
+        let value = key.env.and_then(|var| {
+            std::env::var(var)
+                .ok()
+                .map(|value| (value, Source::Env(var)))
+        });
+        #[cfg(feature = "prompt")]
+        let value = match (value, self.prompt) {
+            (None, Some(prompt)) if key.required => {
+                prompt.prompt(key).map(|value| (value, Source::Prompt))
+            }
+            (value, _) => value,
+        };
+        value
+    }
+    /// Parse `s` with the settings of this parser, without clap.
+    ///
+    /// This applies the [environment fallbacks](KeyInfo::env), [prompts](SubOptParser::prompt),
+    /// [`allow_empty`](SubOptParser::allow_empty) and [`key_messages`](SubOptParser::key_messages),
+    /// so the configuration can be unit-tested directly. The [help key](SubOptParser::help_key)
+    /// is not handled.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_kv, SubOpt, SubOptError, SubOptParser};
+    ///
+    /// #[derive(Debug, Default, Clone)]
+    /// struct Buf {
+    ///     offset: u64,
+    /// }
+    ///
+    /// impl SubOpt for Buf {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "offset" => self.offset = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let parser = SubOptParser::<Buf>::default()
+    ///     .allow_empty(true)
+    ///     .key_messages(&[("offset", "offset must be a byte count")]);
+    /// assert_eq!(parser.parse("").unwrap().offset, 0);
+    /// assert_eq!(
+    ///     parser.parse("offset=x").unwrap_err().to_string(),
+    ///     "'offset=x': offset must be a byte count"
+    /// );
+    /// ```
+    pub fn parse(&self, s: &str) -> Result<T, SubOptError> {
+        self.parse_with_provenance(s).map(|(val, _)| val)
+    }
+    /// Parse the value of the environment variable `var` like [`parse`](SubOptParser::parse),
+    /// or return `None` if it is not set.
+    ///
+    /// Errors are [`SubOptError::InEnv`] errors naming the variable, see [`from_env`].
+    pub fn parse_env(&self, var: &str) -> Result<Option<T>, SubOptError> {
+        let in_env = |error| SubOptError::InEnv {
+            var: var.to_owned(),
+            error: Box::new(error),
+        };
+        match std::env::var(var) {
+            Ok(value) => self.parse(&value).map(Some).map_err(in_env),
+            Err(std::env::VarError::NotPresent) => Ok(None),
+            Err(e) => Err(in_env(SubOptError::Custom(e.to_string()))),
+        }
+    }
+    /// Parse each of `items` like [`parse`](SubOptParser::parse), for bulk input like the lines
+    /// of a job file.
+    ///
+    /// The results are in the order of `items`, so failures can be reported with their line.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{KvMap, SubOptParser};
+    ///
+    /// let lines = ["name=build:cpus=4", "", "name=test"];
+    /// let jobs = SubOptParser::<KvMap>::default().parse_many(&lines);
+    /// assert_eq!(jobs[0].as_ref().unwrap().pairs["cpus"], "4");
+    /// assert!(jobs[1].is_err());
+    /// assert!(jobs[2].is_ok());
+    /// ```
+    pub fn parse_many<S: AsRef<str>>(&self, items: &[S]) -> Vec<Result<T, SubOptError>> {
+        items.iter().map(|s| self.parse(s.as_ref())).collect()
+    }
+    /// Like [`parse_many`](SubOptParser::parse_many), parsing the items in parallel on the rayon
+    /// thread pool.
+    ///
+    /// [Warnings](warn) are captured on the pool's threads, and emitted on the calling thread
+    /// in the order of `items`, so [`take_warnings`] returns them like after
+    /// [`parse_many`](SubOptParser::parse_many).
+    ///
+    /// ```
+    /// use clap_subopt_parser::{take_warnings, KvMap, SubOptParser};
+    ///
+    /// let lines: Vec<_> = (0..1000).map(|i| format!("name=job{}:cpus={}", i, i % 8)).collect();
+    /// let jobs = SubOptParser::<KvMap>::default().par_parse_many(&lines);
+    /// assert_eq!(jobs[999].as_ref().unwrap().pairs["name"], "job999");
+    ///
+    /// let jobs = SubOptParser::<KvMap>::default().par_parse_many(&["name=a::cpus=2", "name=b"]);
+    /// assert!(jobs.iter().all(Result::is_ok));
+    /// assert_eq!(take_warnings().len(), 1);
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_parse_many<S: AsRef<str> + Sync>(&self, items: &[S]) -> Vec<Result<T, SubOptError>>
+    where
+        T: Send,
+    {
+        use rayon::prelude::*;
+
+        let results: Vec<_> = items
+            .par_iter()
+            .map(|s| warning::capture_warnings(|| self.parse(s.as_ref())))
+            .collect();
+        results
+            .into_iter()
+            .map(|(result, warnings)| {
+                warnings.into_iter().for_each(warn);
+                result
+            })
+            .collect()
+    }
+    /// Parse `s` like [`parse`](SubOptParser::parse), and also return which keys were set.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_kv, Source, SubOpt, SubOptError, SubOptParser};
+    ///
+    /// #[derive(Debug, Default, Clone)]
+    /// struct Buf {
+    ///     offset: u64,
+    ///     size: u64,
+    /// }
+    ///
+    /// impl SubOpt for Buf {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "offset" => self.offset = parse_kv(k, v)?,
+    ///             "size" => self.size = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// // Only override the configured size if it was given
+    /// let mut config = Buf { offset: 0, size: 4096 };
+    /// let (buf, provenance) = SubOptParser::<Buf>::default()
+    ///     .parse_with_provenance("offset=512")
+    ///     .unwrap();
+    /// assert_eq!(provenance.source("offset"), Some(&Source::Argument { index: 0 }));
+    /// if provenance.is_explicit("offset") {
+    ///     config.offset = buf.offset;
+    /// }
+    /// if provenance.is_explicit("size") {
+    ///     config.size = buf.size;
+    /// }
+    /// assert_eq!((config.offset, config.size), (512, 4096));
+    /// ```
+    pub fn parse_with_provenance(&self, s: &str) -> Result<(T, Provenance), SubOptError> {
+        self.parse_with_fallbacks(s, update)
+            .map_err(|e| self.with_key_messages(e))
+    }
+    /// Parse the leading sub-options of `s` that `T` recognizes, and return the rest of `s`
+    /// verbatim, starting at the first unknown key.
+    ///
+    /// This allows layered parsing, where a wrapper takes its own keys and forwards the rest to
+    /// a child process or another parser. The rest is empty if all keys are known. The
+    /// [`url_syntax`](SubOptParser::url_syntax) and [`csv_header`](SubOptParser::csv_header)
+    /// settings are not supported, as they don't split into sub-options of the argument.
+    ///
+    /// Only the sub-options before the rest count toward
+    /// [`min_segments`](SubOptParser::min_segments) and
+    /// [`max_segments`](SubOptParser::max_segments), and the
+    /// [environment fallbacks](KeyInfo::env) apply to keys that are only given in the rest.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{subopt, SubOptParser};
+    ///
+    /// subopt! {
+    ///     struct Sandbox {
+    ///         memory: u32,
+    ///         net: bool,
+    ///     }
+    /// }
+    ///
+    /// let parser = SubOptParser::<Sandbox>::default();
+    /// let (sandbox, rest) = parser.parse_prefix("memory=512:net:cmd=make:jobs=4").unwrap();
+    /// assert_eq!((sandbox.memory, sandbox.net), (512, true));
+    /// assert_eq!(rest, "cmd=make:jobs=4");
+    /// assert_eq!(parser.parse_prefix("net").unwrap().1, "");
+    /// assert!(parser.parse_prefix("memory=lots:cmd=make").is_err());
+    ///
+    /// let (_, rest) = parser.max_segments(Some(1)).parse_prefix("net:cmd=make:jobs=4").unwrap();
+    /// assert_eq!(rest, "cmd=make:jobs=4");
+    /// ```
+    pub fn parse_prefix<'s>(&self, s: &'s str) -> Result<(T, &'s str), SubOptError> {
+        if self.url_syntax || self.csv_header.is_some() {
+            return Err(SubOptError::Custom(
+                "parse_prefix doesn't support URL or CSV syntax".into(),
+            ));
+        }
+        let (val, _, rest) = self
+            .parse_until(
+                self.init.create(),
+                Provenance::default(),
+                s,
+                true,
+                Fallbacks::Live,
+                update,
+            )
+            .map_err(|e| self.with_key_messages(e))?;
+        Ok((val, rest.map_or("", |start| &s[start..])))
+    }
+    /// Parse a command line argument, handling the help key.
+    ///
+    /// The [occurrence](SubOpt::set_occurrence) is set first, if given.
+    #[cfg(feature = "clap")]
+    fn parse_arg(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+        occurrence: Option<usize>,
+        fallbacks: Fallbacks,
+    ) -> Result<(T, Provenance), clap::Error> {
+        let s = to_str(value);
+        self.handle_help_key(s)?;
+        let mut val = self.init.create();
+        if let Some(index) = occurrence {
+            val.set_occurrence(index);
+        }
+        self.parse_until(val, Provenance::default(), s, false, fallbacks, update)
+            .map(|(val, provenance, _)| (val, provenance))
+            .map_err(|e| self.error(e, cmd, arg))
+    }
+    /// Apply the [`key_messages`](SubOptParser::key_messages) template for the key of `e`
+    fn with_key_messages(&self, e: SubOptError) -> SubOptError {
+        let template = match e.root() {
+            SubOptError::UnknownKey(_) => None,
+            _ => e.key().and_then(|k| {
+                self.key_messages
+                    .iter()
+                    .find(|(key, _)| *key == k)
+                    .map(|(_, template)| *template)
+            }),
+        };
+        match template {
+            Some(template) => e.with_template(template),
+            None => e,
+        }
+    }
+    #[cfg(feature = "clap")]
+    fn error(&self, e: SubOptError, cmd: &clap::Command, arg: Option<&clap::Arg>) -> clap::Error {
+        let e = self.with_key_messages(e);
+        #[cfg(feature = "json")]
+        if self.json_errors {
+            return clap::Error::raw(e.kind(), format!("{}\n", e.to_json()));
+        }
+        e.format_full(self.messages, cmd, arg, T::example())
+    }
+    /// Return the key table as a help error, if `s` is the help key
+    #[cfg(feature = "clap")]
+    fn handle_help_key(&self, s: &str) -> Result<(), clap::Error> {
+        if self.help_key.is_some_and(|key| key == s) && !KeyTable::cached::<T>().keys.is_empty() {
+            let table = help::localized_key_table::<T>(self.messages);
+            return Err(clap::Error::raw(clap::error::ErrorKind::DisplayHelp, table));
+        }
+        Ok(())
+    }
+    /// The keys as clap possible values, see [`SubOptParser::keys_as_possible_values`]
+    #[cfg(feature = "clap")]
+    fn possible_keys(&self) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue>>> {
+        let keys = KeyTable::cached::<T>();
+        if !self.keys_as_possible_values || keys.keys.is_empty() {
+            return None;
+        }
+        let values = keys
+            .keys
+            .iter()
+            .map(|key| {
+                let value = clap::builder::PossibleValue::new(key.name)
+                    .aliases(key.aliases.iter().copied())
+                    .hide(key.hidden);
+                match self.messages.key_help(key) {
+                    Some(help) => value.help(help),
+                    None => value,
+                }
+            })
+            .collect::<Vec<_>>();
+        Some(Box::new(values.into_iter()))
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T: SubOpt + Clone + Send + Sync> TypedValueParser for SubOptParser<T> {
     type Value = T;
 
     fn parse_ref(
         &self,
-        _cmd: &clap::Command,
-        _arg: Option<&clap::Arg>,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
         value: &std::ffi::OsStr,
     ) -> Result<Self::Value, clap::Error> {
-        let s = value
-            .to_str()
-            .expect("SubOptParser requires arguments to be UTF-8");
-        let mut val = T::default();
-        let opts = s.split(':');
-        for opt in opts {
-            match opt.split_once('=') {
-                Some((k, v)) => val.update_from_kvpair(k, v)?,
-                None => val.update_from_value(opt)?,
-            }
+        self.parse_arg(cmd, arg, value, None, Fallbacks::Live)
+            .map(|(val, _)| val)
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        self.possible_keys()
+    }
+}
+
+/// What to do with an unknown key, see [`SubOptParser::on_unknown_key`]
+#[cfg(feature = "clap")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnknownKeyAction {
+    /// Fail with [`SubOptError::UnknownKey`]
+    Error,
+    /// Ignore the sub-option
+    Ignore,
+    /// Retry with a different key
+    Rename(String),
+}
+
+/// A [`SubOptParser`] with an unknown key handler, see [`SubOptParser::on_unknown_key`]
+#[cfg(feature = "clap")]
+pub struct OnUnknownKey<T, F> {
+    parser: SubOptParser<T>,
+    f: F,
+}
+
+#[cfg(feature = "clap")]
+impl<T, F: Clone> Clone for OnUnknownKey<T, F> {
+    fn clone(&self) -> Self {
+        Self {
+            parser: self.parser.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T, F> TypedValueParser for OnUnknownKey<T, F>
+where
+    T: SubOpt + Clone + Send + Sync,
+    F: Fn(&str, Option<&str>) -> UnknownKeyAction + Clone + Send + Sync + 'static,
+{
+    type Value = T;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let s = to_str(value);
+        self.parser.handle_help_key(s)?;
+        self.parser
+            .parse_with_fallbacks(s, |val, k, v| match update(val, k, v) {
+                Err(SubOptError::UnknownKey(_)) => match (self.f)(k, v) {
+                    UnknownKeyAction::Error => Err(SubOptError::UnknownKey(k.into())),
+                    UnknownKeyAction::Ignore => Ok(()),
+                    UnknownKeyAction::Rename(k) => update(val, &k, v),
+                },
+                result => result,
+            })
+            .map(|(val, _)| val)
+            .map_err(|e| self.parser.error(e, cmd, arg))
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        self.parser.possible_keys()
+    }
+}
+
+/// A [`SubOptParser`] for features that can be disabled, see [`SubOptParser::optional`]
+#[cfg(feature = "clap")]
+pub struct OptionalSubOptParser<T> {
+    parser: SubOptParser<T>,
+    none_values: &'static [&'static str],
+}
+
+#[cfg(feature = "clap")]
+impl<T> Clone for OptionalSubOptParser<T> {
+    fn clone(&self) -> Self {
+        Self {
+            parser: self.parser.clone(),
+            none_values: self.none_values,
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T> OptionalSubOptParser<T> {
+    /// Set the values that yield `None`, which are `none`, `off` and `disabled` by default.
+    ///
+    /// They are matched case-insensitively.
+    #[must_use]
+    pub fn none_values(mut self, values: &'static [&'static str]) -> Self {
+        self.none_values = values;
+        self
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T: SubOpt + Clone + Send + Sync> TypedValueParser for OptionalSubOptParser<T> {
+    type Value = Option<T>;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let s = to_str(value).trim();
+        if self.none_values.iter().any(|v| v.eq_ignore_ascii_case(s)) {
+            return Ok(None);
+        }
+        self.parser.parse_ref(cmd, arg, value).map(Some)
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        self.parser.possible_keys()
+    }
+}
+
+/// A [`SubOptParser`] with its values converted, see [`SubOptParser::map`]
+#[cfg(feature = "clap")]
+pub struct MapSubOptParser<T, F> {
+    parser: SubOptParser<T>,
+    f: F,
+}
+
+#[cfg(feature = "clap")]
+impl<T, F: Clone> Clone for MapSubOptParser<T, F> {
+    fn clone(&self) -> Self {
+        Self {
+            parser: self.parser.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T, F, U> TypedValueParser for MapSubOptParser<T, F>
+where
+    T: SubOpt + Clone + Send + Sync,
+    F: Fn(T) -> U + Clone + Send + Sync + 'static,
+    U: Clone + Send + Sync + 'static,
+{
+    type Value = U;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        self.parser.parse_ref(cmd, arg, value).map(&self.f)
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        self.parser.possible_keys()
+    }
+}
+
+/// A [`SubOptParser`] with its values converted fallibly, see [`SubOptParser::try_map`]
+#[cfg(feature = "clap")]
+pub struct TryMapSubOptParser<T, F> {
+    parser: SubOptParser<T>,
+    f: F,
+}
+
+#[cfg(feature = "clap")]
+impl<T, F: Clone> Clone for TryMapSubOptParser<T, F> {
+    fn clone(&self) -> Self {
+        Self {
+            parser: self.parser.clone(),
+            f: self.f.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T, F, U> TypedValueParser for TryMapSubOptParser<T, F>
+where
+    T: SubOpt + Clone + Send + Sync,
+    F: Fn(T) -> Result<U, SubOptError> + Clone + Send + Sync + 'static,
+    U: Clone + Send + Sync + 'static,
+{
+    type Value = U;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let val = self.parser.parse_ref(cmd, arg, value)?;
+        (self.f)(val).map_err(|e| self.parser.error(e, cmd, arg))
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        self.parser.possible_keys()
+    }
+}
+
+/// A [`SubOptParser`] that also yields the [`Provenance`] of the values, see
+/// [`SubOptParser::with_provenance`]
+#[cfg(feature = "clap")]
+pub struct ProvenanceSubOptParser<T> {
+    parser: SubOptParser<T>,
+}
+
+#[cfg(feature = "clap")]
+impl<T> Clone for ProvenanceSubOptParser<T> {
+    fn clone(&self) -> Self {
+        Self {
+            parser: self.parser.clone(),
         }
-        Ok(val)
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T: SubOpt + Clone + Send + Sync> TypedValueParser for ProvenanceSubOptParser<T> {
+    type Value = (T, Provenance);
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        self.parser
+            .parse_arg(cmd, arg, value, None, Fallbacks::Live)
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        self.parser.possible_keys()
+    }
+}
+
+/// A [`SubOptParser`] passing occurrence indices, see [`SubOptParser::with_occurrences`]
+#[cfg(feature = "clap")]
+pub struct OccurrenceSubOptParser<T> {
+    parser: SubOptParser<T>,
+    next: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[cfg(feature = "clap")]
+impl<T> Clone for OccurrenceSubOptParser<T> {
+    fn clone(&self) -> Self {
+        Self {
+            parser: self.parser.clone(),
+            next: self.next.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T> OccurrenceSubOptParser<T> {
+    /// Start counting occurrences from 0 again
+    pub fn reset(&self) {
+        self.next.store(0, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T: SubOpt + Clone + Send + Sync> TypedValueParser for OccurrenceSubOptParser<T> {
+    type Value = T;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let index = self.next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.parser
+            .parse_arg(cmd, arg, value, Some(index), Fallbacks::Live)
+            .map(|(val, _)| val)
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        self.parser.possible_keys()
+    }
+}
+
+/// A [`SubOptParser`] yielding [`Deferred`] values, see [`SubOptParser::deferred`]
+#[cfg(feature = "clap")]
+pub struct DeferredSubOptParser<T> {
+    parser: SubOptParser<T>,
+}
+
+#[cfg(feature = "clap")]
+impl<T> Clone for DeferredSubOptParser<T> {
+    fn clone(&self) -> Self {
+        Self {
+            parser: self.parser.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T: SubOpt> TypedValueParser for DeferredSubOptParser<T> {
+    type Value = Deferred<T>;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let mut fallbacks = Vec::new();
+        let record = Fallbacks::Record(&mut fallbacks);
+        self.parser.parse_arg(cmd, arg, value, None, record)?;
+        Ok(Deferred {
+            arg: to_str(value).to_owned(),
+            fallbacks,
+            parser: self.parser.clone(),
+        })
+    }
+
+    fn possible_values(
+        &self,
+    ) -> Option<Box<dyn Iterator<Item = clap::builder::PossibleValue> + '_>> {
+        self.parser.possible_keys()
+    }
+}
+
+/// A validated argument for `T`, which is parsed on access, see [`SubOptParser::deferred`]
+#[cfg(feature = "clap")]
+pub struct Deferred<T> {
+    arg: String,
+    /// The fallback values applied when clap parsed the argument
+    fallbacks: Vec<FallbackValue>,
+    parser: SubOptParser<T>,
+}
+
+#[cfg(feature = "clap")]
+impl<T> Clone for Deferred<T> {
+    fn clone(&self) -> Self {
+        Self {
+            arg: self.arg.clone(),
+            fallbacks: self.fallbacks.clone(),
+            parser: self.parser.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T> std::fmt::Debug for Deferred<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Deferred")
+            .field("type", &std::any::type_name::<T>())
+            .field("arg", &self.arg)
+            .finish()
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<T: SubOpt> Deferred<T> {
+    /// Parse the argument.
+    ///
+    /// It was already parsed successfully by clap, so this only fails if the [`SubOpt`]
+    /// implementation gives a different outcome this time. The values that clap's pass took
+    /// from [environment variables](KeyInfo::env) and [prompts](SubOptParser::prompt) are
+    /// reused, and its [warnings](warn) and debug output are not repeated.
+    pub fn parse(&self) -> Result<T, SubOptError> {
+        let (result, _) = warning::capture_warnings(|| {
+            self.parser.parse_until(
+                self.parser.init.create(),
+                Provenance::default(),
+                &self.arg,
+                false,
+                Fallbacks::Replay(&self.fallbacks),
+                update,
+            )
+        });
+        result
+            .map(|(val, ..)| val)
+            .map_err(|e| self.parser.with_key_messages(e))
+    }
+    /// The argument, as given on the command line
+    pub fn arg(&self) -> &str {
+        &self.arg
+    }
+}
+
+/// The flag of `arg`, like `--buf`, for use in examples
+#[cfg(feature = "clap")]
+fn flag(arg: &clap::Arg) -> Option<String> {
+    match (arg.get_long(), arg.get_short()) {
+        (Some(long), _) => Some(format!("--{}", long)),
+        (None, Some(short)) => Some(format!("-{}", short)),
+        (None, None) => None,
+    }
+}
+
+/// Parse `s` as `T`, with the default parser settings.
+///
+/// This doesn't need a [`clap::Command`], so the same syntax can be used for values from
+/// configuration files, environment variables, or tests.
+///
+/// ```
+/// use clap_subopt_parser::{parse_kv, parse_str, SubOpt, SubOptError};
+///
+/// #[derive(Default)]
+/// struct Buf {
+///     offset: u64,
+///     sync: bool,
+/// }
+///
+/// impl SubOpt for Buf {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "sync" => self.sync = true,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "offset" => self.offset = parse_kv(k, v)?,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let buf: Buf = parse_str("offset=512:sync").unwrap();
+/// assert_eq!(buf.offset, 512);
+/// assert!(buf.sync);
+/// assert!(parse_str::<Buf>("size=1").is_err());
+/// ```
+pub fn parse_str<T: SubOpt + Default>(s: &str) -> Result<T, SubOptError> {
+    SubOptParser::<T>::default().parse(s)
+}
+
+/// Parse each of `items` as `T`, with the default parser settings, see
+/// [`SubOptParser::parse_many`].
+///
+/// ```
+/// use clap_subopt_parser::{parse_many, KvMap};
+///
+/// let jobs = parse_many::<KvMap, _>(&["name=build", "name=test:cpus=2"]);
+/// assert!(jobs.iter().all(Result::is_ok));
+/// ```
+pub fn parse_many<T: SubOpt + Default, S: AsRef<str>>(items: &[S]) -> Vec<Result<T, SubOptError>> {
+    SubOptParser::<T>::default().parse_many(items)
+}
+
+/// Parse the value of the environment variable `var` as `T`, with the default parser settings.
+///
+/// This allows configuring an option with the same syntax as on the command line, like
+/// `MYAPP_BUF=source=0:offset=4`. Returns `None` if the variable is not set. Errors are
+/// [`SubOptError::InEnv`] errors naming the variable.
+///
+/// ```
+/// use clap_subopt_parser::{from_env, KvMap};
+///
+/// std::env::set_var("MYAPP_BUF", "source=0:offset=4");
+/// let buf = from_env::<KvMap>("MYAPP_BUF").unwrap().unwrap();
+/// assert_eq!(buf.pairs["offset"], "4");
+/// assert!(from_env::<KvMap>("MYAPP_UNSET").unwrap().is_none());
+///
+/// std::env::set_var("MYAPP_BUF", "");
+/// assert_eq!(
+///     from_env::<KvMap>("MYAPP_BUF").unwrap_err().to_string(),
+///     "In environment variable MYAPP_BUF: No sub-options given"
+/// );
+/// ```
+pub fn from_env<T: SubOpt + Default>(var: &str) -> Result<Option<T>, SubOptError> {
+    SubOptParser::<T>::default().parse_env(var)
+}
+
+/// Locate the error `e` in the sub-option that the fallback value `value` of the key `name`
+/// stands for, with the value hidden if the key is [secret](KeyInfo::secret), and name the
+/// environment variable or configuration file it came from
+pub(crate) fn fallback_error(
+    e: SubOptError,
+    (name, secret): (&str, bool),
+    value: Option<&str>,
+    kv_sep: char,
+    source: &Source,
+) -> SubOptError {
+    let arg = match value {
+        Some(_) if secret => format!("{}{}***", name, kv_sep),
+        Some(value) => format!("{}{}{}", name, kv_sep, value),
+        None => name.to_owned(),
+    };
+    let e = e.in_segment(&arg, 0..arg.len(), kv_sep);
+    match source {
+        Source::Env(var) => SubOptError::InEnv {
+            var: (*var).to_owned(),
+            error: Box::new(e),
+        },
+        Source::File(path) => SubOptError::InFile {
+            path: path.clone(),
+            error: Box::new(e),
+        },
+        _ => e,
+    }
+}
+
+#[cfg(feature = "clap")]
+fn to_str(value: &std::ffi::OsStr) -> &str {
+    value
+        .to_str()
+        .expect("SubOptParser requires arguments to be UTF-8")
+}
+
+fn update<T: SubOpt>(val: &mut T, k: &str, v: Option<&str>) -> Result<(), SubOptError> {
+    match v {
+        Some(v) => val.update_from_kvpair(k, v),
+        None => val.update_from_value(k),
     }
 }
 
 /// An argument that has sub-options.
 ///
-/// The implementor must also implement [`std::default::Default`] with sensible defaults.
+/// The implementor should also implement [`std::default::Default`] with sensible defaults,
+/// otherwise the parser needs a [factory](SubOptParser::with_factory).
 ///
 /// It will then be built up from the sub-options given as arguments to its methods.
-pub trait SubOpt: Default + Send + Sync + 'static {
+///
+/// Implementors don't have to be `Send` or `Sync`, but clap requires it for values stored in
+/// its matches. Types containing [`Rc`](std::rc::Rc) or other thread-local data can use
+/// [`SubOptParser::deferred`] or [`SubOptParser::map`] instead.
+pub trait SubOpt: 'static {
     /// Update from a single value, like in the example `--foo value1:value2:value3`.
     ///
     /// Each sub-option is a value without a key in the above example.
@@ -108,34 +2278,209 @@ pub trait SubOpt: Default + Send + Sync + 'static {
     ///
     /// Each sub-option is a key-value pair in the above example.
     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError>;
-}
-
-/// An error that can happen when parsing a sub-option.
-#[derive(Debug)]
-pub enum SubOptError {
-    /// Unknown key
-    UnknownKey(String),
-    /// Missing value for key
-    MissingValueForKey(String),
-    /// Custom error, for example parse errors. Convert these errors into a string.
-    Custom(String),
-}
-
-impl From<SubOptError> for clap::Error {
-    fn from(sub: SubOptError) -> Self {
-        match sub {
-            SubOptError::UnknownKey(k) => clap::Error::raw(
-                clap::ErrorKind::UnknownArgument,
-                format!("Unknown key: {}\n", k),
-            ),
-            SubOptError::MissingValueForKey(k) => clap::Error::raw(
-                clap::ErrorKind::EmptyValue,
-                format!("Missing value for key '{}'\n", k),
-            ),
-            SubOptError::Custom(s) => clap::Error::raw(
-                clap::ErrorKind::InvalidValue,
-                format!("Custom error: {}\n", s),
-            ),
-        }
+    /// Metadata about the recognized keys.
+    ///
+    /// This is optional. It is the shared source for help, completion, suggestions and
+    /// documentation. The keys are not reported to clap as possible values, as clap's help would
+    /// list them as the only valid values of the argument.
+    ///
+    /// The order of the keys is their declaration order. All generated help, documentation and
+    /// completions list keys in this order, so the output is stable and easy to diff.
+    /// [Categories](KeyInfo::category) are ordered by their first key. The only exception is
+    /// the JSON Schema, whose properties are sorted by name.
+    fn keys() -> Vec<KeyInfo> {
+        Vec::new()
+    }
+    /// Completion candidates for the value of `key`, see [`completion::complete`].
+    ///
+    /// This is optional, and can suggest things like device names or enum variants.
+    fn value_candidates(_key: &str) -> Vec<completion::CompletionCandidate> {
+        Vec::new()
     }
+    /// An example value, like `source=0:offset=1000`.
+    ///
+    /// This is optional. It is appended to parse errors and to generated help.
+    fn example() -> Option<&'static str> {
+        None
+    }
+    /// Finish the value after all sub-options are applied, for checking required keys or
+    /// computing derived fields.
+    ///
+    /// This is optional. It is called after the [environment fallbacks](KeyInfo::env), but not
+    /// for the [default keyword](SubOptParser::default_keyword).
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_kv, parse_str, SubOpt, SubOptError};
+    ///
+    /// #[derive(Default)]
+    /// struct Buf {
+    ///     source: Option<u32>,
+    ///     len: u64,
+    ///     end: u64,
+    /// }
+    ///
+    /// impl SubOpt for Buf {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "source" => self.source = Some(parse_kv(k, v)?),
+    ///             "len" => self.len = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///     fn finish(mut self) -> Result<Self, SubOptError> {
+    ///         if self.source.is_none() {
+    ///             return Err(SubOptError::MissingKey("source".into()));
+    ///         }
+    ///         self.end = self.len * 2;
+    ///         Ok(self)
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(parse_str::<Buf>("source=1:len=4").unwrap().end, 8);
+    /// assert!(parse_str::<Buf>("len=4").is_err());
+    /// ```
+    fn finish(self) -> Result<Self, SubOptError>
+    where
+        Self: Sized,
+    {
+        Ok(self)
+    }
+    /// Check the finished value, for consistency checks across keys.
+    ///
+    /// This is optional. It is called after [`SubOpt::finish`].
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_kv, parse_str, SubOpt, SubOptError};
+    ///
+    /// #[derive(Debug, Default)]
+    /// struct Range {
+    ///     start: u64,
+    ///     end: u64,
+    /// }
+    ///
+    /// impl SubOpt for Range {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "start" => self.start = parse_kv(k, v)?,
+    ///             "end" => self.end = parse_kv(k, v)?,
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///     fn validate(&self) -> Result<(), SubOptError> {
+    ///         if self.end < self.start {
+    ///             return Err(SubOptError::Custom("end is before start".into()));
+    ///         }
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// assert!(parse_str::<Range>("start=1:end=4").is_ok());
+    /// assert_eq!(
+    ///     parse_str::<Range>("start=4:end=1").unwrap_err().to_string(),
+    ///     "end is before start"
+    /// );
+    /// ```
+    fn validate(&self) -> Result<(), SubOptError> {
+        Ok(())
+    }
+    /// Receive the index of the occurrence of the argument, starting at 0 for the first one.
+    ///
+    /// This is only called by parsers created with [`SubOptParser::with_occurrences`], before
+    /// the sub-options are parsed. It allows values that refer to earlier occurrences by index.
+    ///
+    /// ```
+    /// # #[cfg(feature = "clap")] {
+    /// use clap::{Arg, ArgAction, Command};
+    /// use clap_subopt_parser::{SubOpt, SubOptError, SubOptParser};
+    ///
+    /// #[derive(Debug, Default, Clone)]
+    /// struct Layer {
+    ///     index: usize,
+    ///     name: String,
+    /// }
+    ///
+    /// impl SubOpt for Layer {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         self.name = k.into();
+    ///         Ok(())
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, _: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::UnknownKey(k.into()))
+    ///     }
+    ///     fn set_occurrence(&mut self, index: usize) {
+    ///         self.index = index;
+    ///     }
+    /// }
+    ///
+    /// let parser = SubOptParser::<Layer>::default().with_occurrences();
+    /// let m = Command::new("myapp")
+    ///     .arg(
+    ///         Arg::new("layer")
+    ///             .long("layer")
+    ///             .action(ArgAction::Append)
+    ///             .value_parser(parser),
+    ///     )
+    ///     .get_matches_from(["myapp", "--layer", "base", "--layer", "overlay"]);
+    /// let layers: Vec<_> = m.get_many::<Layer>("layer").unwrap().collect();
+    /// assert_eq!((layers[1].index, layers[1].name.as_str()), (1, "overlay"));
+    /// # }
+    /// ```
+    fn set_occurrence(&mut self, _index: usize) {}
+    /// Receive the position of the sub-option that is passed next to
+    /// [`update_from_value`](SubOpt::update_from_value) or
+    /// [`update_from_kvpair`](SubOpt::update_from_kvpair).
+    ///
+    /// This allows keys whose meaning depends on where they are given, like a key that must come
+    /// last. Values from [environment fallbacks](KeyInfo::env) and prompts are not sub-options of
+    /// the argument, and are passed after all of them without calling this.
+    ///
+    /// ```
+    /// use clap_subopt_parser::{parse_str, SegmentPosition, SubOpt, SubOptError};
+    ///
+    /// #[derive(Default)]
+    /// struct Exec {
+    ///     env: Vec<String>,
+    ///     cmd: String,
+    ///     position: Option<SegmentPosition>,
+    /// }
+    ///
+    /// impl SubOpt for Exec {
+    ///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+    ///         Err(SubOptError::MissingValueForKey(k.into()))
+    ///     }
+    ///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+    ///         match k {
+    ///             "env" => self.env.push(v.into()),
+    ///             "cmd" if self.position.is_some_and(|p| !p.is_last()) => {
+    ///                 return Err(SubOptError::Custom("cmd must be the last key".into()))
+    ///             }
+    ///             "cmd" => self.cmd = v.into(),
+    ///             _ => return Err(SubOptError::UnknownKey(k.into())),
+    ///         }
+    ///         Ok(())
+    ///     }
+    ///     fn set_position(&mut self, position: SegmentPosition) {
+    ///         self.position = Some(position);
+    ///     }
+    /// }
+    ///
+    /// let exec = parse_str::<Exec>("env=HOME=/root:cmd=ls").unwrap();
+    /// assert_eq!((exec.env.len(), exec.cmd.as_str()), (1, "ls"));
+    /// assert!(parse_str::<Exec>("cmd=ls:env=HOME=/root").is_err());
+    /// ```
+    fn set_position(&mut self, _position: SegmentPosition) {}
+    /// The separator between sub-options, [`DEFAULT_SEPARATOR`] by default.
+    ///
+    /// See [`Delimited`] for using different separators with an existing type.
+    const SEPARATOR: char = DEFAULT_SEPARATOR;
+    /// The separator between keys and values, `=` by default
+    const KV_SEPARATOR: char = '=';
 }
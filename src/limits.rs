@@ -0,0 +1,150 @@
+use crate::{
+    split_protected, update, warning::capture_warnings, SubOpt, SubOptError, SubOptParser,
+};
+
+/// Limits for [`parse_untrusted`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Maximum length of the whole argument, in bytes
+    pub max_len: usize,
+    /// Maximum number of segments
+    pub max_segments: usize,
+    /// Maximum length of a key, in bytes
+    pub max_key_len: usize,
+    /// Maximum length of a value, in bytes
+    pub max_value_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_len: 4096,
+            max_segments: 64,
+            max_key_len: 64,
+            max_value_len: 1024,
+        }
+    }
+}
+
+/// Parse untrusted input, for example sub-option strings received over the network.
+///
+/// This upholds the following guarantees:
+/// - Panics in the [`SubOpt`] implementation are caught, and reported as
+///   [`SubOptError::Custom`] errors. This relies on unwinding, so with `panic = "abort"` they
+///   still abort the process. The panic hook runs before the panic is caught, so the default
+///   hook still prints the panic message to stderr.
+/// - Input exceeding `limits` is rejected with [`SubOptError::LimitExceeded`] before it
+///   reaches the [`SubOpt`] implementation, so allocations are bounded by the limits.
+/// - [Warnings](crate::SubOptWarning) are discarded instead of being left for
+///   [`take_warnings`](crate::take_warnings).
+/// - All failures are reported as structured [`SubOptError`]s.
+///
+/// Otherwise, it parses like [`SubOptParser`] with the default settings.
+///
+/// ```
+/// use clap_subopt_parser::{parse_untrusted, subopt, take_warnings, Limits, SubOptError};
+///
+/// subopt! {
+///     struct Buf {
+///         size: usize,
+///     }
+/// }
+///
+/// assert_eq!(parse_untrusted::<Buf>("::size=4", Limits::default()).unwrap().size, 4);
+/// assert!(take_warnings().is_empty());
+/// let long = "size=4:".repeat(100);
+/// assert!(matches!(
+///     parse_untrusted::<Buf>(&long, Limits::default()),
+///     Err(SubOptError::LimitExceeded { limit: "max_segments", .. })
+/// ));
+/// ```
+pub fn parse_untrusted<T: SubOpt + Default>(s: &str, limits: Limits) -> Result<T, SubOptError> {
+    if s.len() > limits.max_len {
+        return Err(SubOptError::LimitExceeded {
+            limit: "max_len",
+            max: limits.max_len,
+        });
+    }
+    if split_protected(s, T::SEPARATOR).count() > limits.max_segments {
+        return Err(SubOptError::LimitExceeded {
+            limit: "max_segments",
+            max: limits.max_segments,
+        });
+    }
+    let (result, _) = capture_warnings(|| {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            SubOptParser::<T>::default()
+                .parse_with_fallbacks(s, |val, k, v| {
+                    if k.len() > limits.max_key_len {
+                        return Err(SubOptError::LimitExceeded {
+                            limit: "max_key_len",
+                            max: limits.max_key_len,
+                        });
+                    }
+                    if v.map_or(0, str::len) > limits.max_value_len {
+                        return Err(SubOptError::LimitExceeded {
+                            limit: "max_value_len",
+                            max: limits.max_value_len,
+                        });
+                    }
+                    update(val, k, v)
+                })
+                .map(|(val, _)| val)
+        }))
+    });
+    result.unwrap_or_else(|_| Err(SubOptError::Custom("Sub-option parser panicked".into())))
+}
+
+/// Parse arbitrary bytes, for fuzz targets.
+///
+/// This is [`parse_untrusted`] with the default [`Limits`], taking bytes instead of a string,
+/// so it can be called directly from a cargo-fuzz target. It upholds the following invariants,
+/// for any input:
+/// - Panics in the [`SubOpt`] implementation are caught, with the same caveats as with
+///   [`parse_untrusted`]. Fuzzers usually install a panic hook that aborts, which still
+///   reports them as crashes, as they are bugs in the implementation.
+/// - Warnings are discarded, like with [`parse_untrusted`].
+/// - Input that is not valid UTF-8 is rejected with a [`SubOptError::Custom`] error, without
+///   replacing invalid bytes, and never reaches the [`SubOpt`] implementation.
+/// - Input exceeding the default [`Limits`] is rejected with [`SubOptError::LimitExceeded`],
+///   so the time and memory spent on each input are bounded.
+///
+/// Like [`parse_untrusted`], [environment fallbacks](crate::KeyInfo::env) are applied, so the
+/// environment of the fuzzer should not set them.
+///
+/// ```
+/// use clap_subopt_parser::{fuzz_parse, subopt, SubOptError};
+///
+/// subopt! {
+///     struct Buf {
+///         source: usize,
+///         offset: usize,
+///     }
+/// }
+///
+/// // The body of `fuzz_target!(|data: &[u8]| { ... })`
+/// fn fuzz(data: &[u8]) {
+///     let _ = fuzz_parse::<Buf>(data);
+/// }
+///
+/// fuzz(b"source=1:offset=[4");
+/// fuzz(&[0xff, b'=', 0xfe]);
+/// assert!(matches!(fuzz_parse::<Buf>(b"source=\xff"), Err(SubOptError::Custom(_))));
+/// assert_eq!(fuzz_parse::<Buf>(b"source=1:offset=4").unwrap().offset, 4);
+/// ```
+pub fn fuzz_parse<T: SubOpt + Default>(data: &[u8]) -> Result<T, SubOptError> {
+    let limits = Limits::default();
+    if data.len() > limits.max_len {
+        return Err(SubOptError::LimitExceeded {
+            limit: "max_len",
+            max: limits.max_len,
+        });
+    }
+    match std::str::from_utf8(data) {
+        Ok(s) => parse_untrusted(s, limits),
+        Err(e) => Err(SubOptError::Custom(format!(
+            "Argument is not valid UTF-8: {}",
+            e
+        ))),
+    }
+}
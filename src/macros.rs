@@ -0,0 +1,118 @@
+/// Define a struct with a [`SubOpt`](crate::SubOpt) implementation, without writing the
+/// implementation by hand.
+///
+/// Each field is a key parsed with [`FromStr`](std::str::FromStr), and can have a default value
+/// after `=`. Fields without one start with their [`Default`] value. `bool` fields can also be
+/// given as bare values, which sets them to `true`.
+///
+/// ```
+/// use clap_subopt_parser::{parse_str, subopt};
+///
+/// subopt! {
+///     #[derive(Debug, Clone)]
+///     pub struct Buf {
+///         source: usize,
+///         offset: usize = 4096,
+///         ro: bool,
+///     }
+/// }
+///
+/// let buf = parse_str::<Buf>("source=1:ro").unwrap();
+/// assert_eq!((buf.source, buf.offset, buf.ro), (1, 4096, true));
+/// assert!(parse_str::<Buf>("offset").is_err());
+/// ```
+///
+/// Keys are the field names, so a key declared twice is a compile error pointing at the second
+/// field, instead of an unreachable key.
+///
+/// ```compile_fail,E0062
+/// clap_subopt_parser::subopt! {
+///     struct Buf {
+///         offset: usize,
+///         offset: u64,
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! subopt {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident: $ty:ty $(= $default:expr)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        impl ::std::default::Default for $name {
+            fn default() -> Self {
+                Self {
+                    $($field: $crate::__subopt_default!($($default)?),)*
+                }
+            }
+        }
+
+        impl $crate::SubOpt for $name {
+            fn update_from_value(&mut self, k: &str) -> Result<(), $crate::SubOptError> {
+                match k {
+                    $(
+                        stringify!($field) if $crate::__is_bool::<$ty>() => {
+                            self.$field = $crate::parse_kv(k, "true")?;
+                            Ok(())
+                        }
+                        stringify!($field) => Err($crate::SubOptError::MissingValueForKey(k.into())),
+                    )*
+                    _ => Err($crate::SubOptError::UnknownKey(k.into())),
+                }
+            }
+            fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), $crate::SubOptError> {
+                match k {
+                    $(
+                        stringify!($field) => {
+                            self.$field = $crate::parse_kv(k, v)?;
+                            Ok(())
+                        }
+                    )*
+                    _ => Err($crate::SubOptError::UnknownKey(k.into())),
+                }
+            }
+            fn keys() -> Vec<$crate::KeyInfo> {
+                vec![$({
+                    let key = $crate::KeyInfo::new(stringify!($field))
+                        .type_name(stringify!($ty))
+                        $(.default(stringify!($default)))?;
+                    if $crate::__is_bool::<$ty>() {
+                        key.kind($crate::ValueKind::Bool)
+                    } else {
+                        key.value_name("VALUE")
+                    }
+                },)*]
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __subopt_default {
+    () => {
+        ::std::default::Default::default()
+    };
+    ($default:expr) => {
+        $default
+    };
+}
+
+/// Whether `T` is `bool`, for [`subopt!`]
+#[doc(hidden)]
+pub fn __is_bool<T: 'static>() -> bool {
+    std::any::TypeId::of::<T>() == std::any::TypeId::of::<bool>()
+}
@@ -0,0 +1,116 @@
+/// Provides the text of error messages, for localization.
+///
+/// Every method has a default implementation returning the English message, so an
+/// implementation only needs to override what it translates.
+///
+/// ```
+/// use clap_subopt_parser::{Messages, SubOptError, SubOptParser};
+///
+/// struct German;
+///
+/// impl Messages for German {
+///     fn unknown_key(&self, key: &str) -> String {
+///         format!("Unbekannter Schlüssel '{}'", key)
+///     }
+/// }
+///
+/// let err = SubOptError::UnknownKey("foo".into());
+/// assert_eq!(err.localized(&German), "Unbekannter Schlüssel 'foo'");
+/// # #[derive(Default)] struct Buf;
+/// # impl clap_subopt_parser::SubOpt for Buf {
+/// #     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> { Ok(()) }
+/// #     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> { Ok(()) }
+/// # }
+/// let parser = SubOptParser::<Buf>::default().messages(&German);
+/// ```
+pub trait Messages: Send + Sync {
+    /// Message for [`SubOptError::UnknownKey`](crate::SubOptError::UnknownKey)
+    fn unknown_key(&self, key: &str) -> String {
+        format!("Unknown key: {}", key)
+    }
+    /// Message for [`SubOptError::MissingValueForKey`](crate::SubOptError::MissingValueForKey)
+    fn missing_value(&self, key: &str) -> String {
+        format!("Missing value for key '{}'", key)
+    }
+    /// Message for [`SubOptError::MissingKey`](crate::SubOptError::MissingKey)
+    fn missing_key(&self, key: &str) -> String {
+        format!("Missing required key '{}'", key)
+    }
+    /// Message for [`SubOptError::InvalidValue`](crate::SubOptError::InvalidValue)
+    fn invalid_value(
+        &self,
+        key: &str,
+        reason: &str,
+        expected: Option<&str>,
+        example: Option<&str>,
+    ) -> String {
+        let mut msg = format!("Invalid value for key '{}': {}", key, reason);
+        match (expected, example) {
+            (Some(expected), Some(example)) => {
+                msg += &format!(" (expected {}, e.g. {})", expected, example)
+            }
+            (Some(expected), None) => msg += &format!(" (expected {})", expected),
+            (None, Some(example)) => msg += &format!(" (e.g. {})", example),
+            (None, None) => {}
+        }
+        msg
+    }
+    /// Message for [`SubOptError::EmptyArgument`](crate::SubOptError::EmptyArgument)
+    fn empty_argument(&self) -> String {
+        "No sub-options given".into()
+    }
+    /// Message for [`SubOptError::SegmentCount`](crate::SubOptError::SegmentCount)
+    fn segment_count(&self, min: usize, max: Option<usize>, count: usize) -> String {
+        match max {
+            Some(max) if max == min => {
+                format!("Expected exactly {} sub-options, got {}", min, count)
+            }
+            Some(max) if count > max => {
+                format!("Expected at most {} sub-options, got {}", max, count)
+            }
+            _ => format!("Expected at least {} sub-options, got {}", min, count),
+        }
+    }
+    /// Message for [`SubOptError::LimitExceeded`](crate::SubOptError::LimitExceeded)
+    fn limit_exceeded(&self, limit: &str, max: usize) -> String {
+        format!("Limit exceeded: {} is {}", limit, max)
+    }
+    /// Message for a [`SubOptError::Custom`](crate::SubOptError::Custom) error in `segment`
+    fn custom_in_segment(&self, segment: &str, message: &str) -> String {
+        format!("'{}': {}", segment, message)
+    }
+    /// Message for a [`SubOptError::InEnv`](crate::SubOptError::InEnv) error in the environment
+    /// variable `var`
+    fn in_env(&self, var: &str, message: &str) -> String {
+        format!("In environment variable {}: {}", var, message)
+    }
+    /// Message for a [`SubOptError::InFile`](crate::SubOptError::InFile) error in the
+    /// configuration file at `path`
+    fn in_file(&self, path: &std::path::Path, message: &str) -> String {
+        format!("In config file '{}': {}", path.display(), message)
+    }
+    /// Message passed to clap for an error in the argument `arg` with the given `value`
+    fn invalid_arg(&self, value: Option<&str>, arg: &str, message: &str) -> String {
+        match value {
+            Some(value) => format!("Invalid value \"{}\" for '{}': {}", value, arg, message),
+            None => format!("Invalid value for '{}': {}", arg, message),
+        }
+    }
+    /// Help text for `key`, for translating [`KeyInfo::help`](crate::KeyInfo::help)
+    fn key_help(&self, key: &crate::KeyInfo) -> Option<String> {
+        key.help.map(Into::into)
+    }
+    /// Line with an [example](crate::SubOpt::example) appended to errors, for `flag` like `--buf`
+    fn example(&self, flag: Option<&str>, example: &str) -> String {
+        match flag {
+            Some(flag) => format!("example: {} {}", flag, example),
+            None => format!("example: {}", example),
+        }
+    }
+}
+
+/// The default, English [`Messages`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct English;
+
+impl Messages for English {}
@@ -0,0 +1,25 @@
+/// The position of a sub-option in the argument, see [`SubOpt::set_position`].
+///
+/// Empty sub-options are not counted, like with [`Source::Argument`], so the first key given
+/// has index 0.
+///
+/// [`SubOpt::set_position`]: crate::SubOpt::set_position
+/// [`Source::Argument`]: crate::Source::Argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SegmentPosition {
+    /// The index of the sub-option
+    pub index: usize,
+    /// The number of sub-options in the argument
+    pub count: usize,
+}
+
+impl SegmentPosition {
+    /// Whether this is the first sub-option
+    pub fn is_first(&self) -> bool {
+        self.index == 0
+    }
+    /// Whether this is the last sub-option
+    pub fn is_last(&self) -> bool {
+        self.index + 1 == self.count
+    }
+}
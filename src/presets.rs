@@ -0,0 +1,639 @@
+//! Ready-made [`SubOpt`] types for common argument formats, for drop-in parity with the flags
+//! of well-known tools.
+
+use crate::completion::CompletionCandidate;
+use crate::{
+    parse_kv, values, KeyInfo, SegmentPosition, SubOpt, SubOptError, SubOptParser, ValueKind,
+};
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+/// The kind of a [`Mount`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum MountType {
+    /// A host directory or file
+    Bind,
+    /// A named or anonymous volume
+    #[default]
+    Volume,
+    /// A temporary in-memory file system
+    Tmpfs,
+}
+
+impl MountType {
+    const NAMES: &'static [&'static str] = &["bind", "volume", "tmpfs"];
+}
+
+impl std::str::FromStr for MountType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bind" => Ok(Self::Bind),
+            "volume" => Ok(Self::Volume),
+            "tmpfs" => Ok(Self::Tmpfs),
+            _ => Err(format!("unknown mount type '{}'", s)),
+        }
+    }
+}
+
+/// A mount, like Docker's `--mount type=bind,source=/srv/data,target=/data,readonly`.
+///
+/// The sub-options are separated by `,`. `src` is an alias of `source`, `dst` and
+/// `destination` are aliases of `target`, and `ro` is an alias of `readonly`, which can also
+/// be given a boolean value. The type is `volume` if not given, the target is required, and
+/// bind mounts require a source.
+///
+/// ```
+/// use clap_subopt_parser::presets::{Mount, MountType};
+/// use clap_subopt_parser::parse_str;
+///
+/// let mount: Mount = parse_str("type=bind,src=/srv/data,dst=/data,ro").unwrap();
+/// assert_eq!(mount.kind, MountType::Bind);
+/// assert_eq!(mount.source.as_deref(), Some("/srv/data"));
+/// assert_eq!(mount.target, std::path::Path::new("/data"));
+/// assert!(mount.readonly);
+///
+/// let mount: Mount = parse_str("target=/cache").unwrap();
+/// assert_eq!((mount.kind, mount.source), (MountType::Volume, None));
+/// assert_eq!(
+///     parse_str::<Mount>("type=bind,target=/data").unwrap_err().to_string(),
+///     "Missing required key 'source'"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mount {
+    /// The `type` of the mount
+    pub kind: MountType,
+    /// The host path for bind mounts, or the volume name
+    pub source: Option<String>,
+    /// The path in the container
+    pub target: PathBuf,
+    /// Whether the mount is read-only
+    pub readonly: bool,
+}
+
+impl SubOpt for Mount {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        match k {
+            "readonly" | "ro" => self.readonly = true,
+            "type" | "source" | "src" | "target" | "dst" | "destination" => {
+                return Err(SubOptError::MissingValueForKey(k.into()))
+            }
+            _ => return Err(SubOptError::UnknownKey(k.into())),
+        }
+        Ok(())
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        match k {
+            "type" => self.kind = parse_kv(k, v)?,
+            "source" | "src" => self.source = Some(v.to_owned()),
+            "target" | "dst" | "destination" => self.target = v.into(),
+            "readonly" | "ro" => self.readonly = values::boolean(k, v)?,
+            _ => return Err(SubOptError::UnknownKey(k.into())),
+        }
+        Ok(())
+    }
+    fn keys() -> Vec<KeyInfo> {
+        vec![
+            KeyInfo::new("type")
+                .value_name("TYPE")
+                .kind(ValueKind::Enum {
+                    variants: MountType::NAMES,
+                })
+                .default("volume")
+                .help("The kind of mount"),
+            KeyInfo::new("source")
+                .aliases(&["src"])
+                .value_name("SOURCE")
+                .help("The host path for bind mounts, or the volume name"),
+            KeyInfo::new("target")
+                .aliases(&["dst", "destination"])
+                .value_name("PATH")
+                .kind(ValueKind::Path)
+                .required(true)
+                .help("The path in the container"),
+            KeyInfo::new("readonly")
+                .aliases(&["ro"])
+                .kind(ValueKind::Bool)
+                .help("Mount read-only"),
+        ]
+    }
+    fn value_candidates(key: &str) -> Vec<CompletionCandidate> {
+        match key {
+            "type" => MountType::NAMES
+                .iter()
+                .map(|&name| CompletionCandidate::new(name))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+    fn example() -> Option<&'static str> {
+        Some("type=bind,source=/srv/data,target=/data,readonly")
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        if self.target.as_os_str().is_empty() {
+            return Err(SubOptError::MissingKey("target".into()));
+        }
+        if self.kind == MountType::Bind && self.source.is_none() {
+            return Err(SubOptError::MissingKey("source".into()));
+        }
+        Ok(self)
+    }
+    const SEPARATOR: char = ',';
+}
+
+/// The transport protocol of a [`PortMapping`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    /// TCP
+    #[default]
+    Tcp,
+    /// UDP
+    Udp,
+    /// SCTP
+    Sctp,
+}
+
+impl Protocol {
+    const NAMES: &'static [&'static str] = &["tcp", "udp", "sctp"];
+}
+
+impl std::str::FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tcp" => Ok(Self::Tcp),
+            "udp" => Ok(Self::Udp),
+            "sctp" => Ok(Self::Sctp),
+            _ => Err(format!("unknown protocol '{}'", s)),
+        }
+    }
+}
+
+/// A published port, like Docker's `--publish 127.0.0.1:8080:80/udp`.
+///
+/// The bare values are `[IP:][HOST:]CONTAINER[/PROTOCOL]`, like with Docker, and IPv6
+/// addresses go in square brackets. The keys `ip`, `host`, `container` and `protocol` can be
+/// used instead. The container port is required, the protocol is TCP if not given, and
+/// without a host port, one is chosen by the runtime.
+///
+/// ```
+/// use clap_subopt_parser::presets::{PortMapping, Protocol};
+/// use clap_subopt_parser::parse_str;
+///
+/// let port: PortMapping = parse_str("8080:80").unwrap();
+/// assert_eq!((port.host, port.container, port.protocol), (Some(8080), 80, Protocol::Tcp));
+///
+/// let port: PortMapping = parse_str("127.0.0.1:5353:53/udp").unwrap();
+/// assert_eq!(port.ip, Some([127, 0, 0, 1].into()));
+/// assert_eq!((port.host, port.container, port.protocol), (Some(5353), 53, Protocol::Udp));
+///
+/// let port: PortMapping = parse_str("[::1]:8443:443").unwrap();
+/// assert_eq!(port.ip, Some(std::net::Ipv6Addr::LOCALHOST.into()));
+///
+/// let port: PortMapping = parse_str("container=80:protocol=sctp").unwrap();
+/// assert_eq!((port.host, port.container, port.protocol), (None, 80, Protocol::Sctp));
+/// assert_eq!(
+///     parse_str::<PortMapping>("1:2:3:4").unwrap_err().to_string(),
+///     "Unknown key: 3"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortMapping {
+    /// The host address to bind to, or all addresses
+    pub ip: Option<IpAddr>,
+    /// The host port, or one chosen by the runtime
+    pub host: Option<u16>,
+    /// The container port
+    pub container: u16,
+    /// The protocol
+    pub protocol: Protocol,
+}
+
+impl SubOpt for PortMapping {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        let addr = k.strip_prefix('[').and_then(|k| k.strip_suffix(']'));
+        if let (Ok(ip), None, 0) = (addr.unwrap_or(k).parse(), self.host, self.container) {
+            self.ip = Some(ip);
+            return Ok(());
+        }
+        let (port, protocol) = match k.split_once('/') {
+            Some((port, protocol)) => (port, Some(protocol)),
+            None => (k, None),
+        };
+        if self.host.is_some() || port.parse::<u16>().is_err() {
+            return Err(SubOptError::UnknownKey(k.into()));
+        }
+        if self.container != 0 {
+            self.host = Some(self.container);
+        }
+        self.container = parse_kv("container", port)?;
+        if let Some(protocol) = protocol {
+            self.protocol = parse_kv("protocol", protocol)?;
+        }
+        Ok(())
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        match k {
+            "ip" => self.ip = Some(values::ip_addr(k, v)?),
+            "host" => self.host = Some(parse_kv(k, v)?),
+            "container" => self.container = parse_kv(k, v)?,
+            "protocol" => self.protocol = parse_kv(k, v)?,
+            _ => return Err(SubOptError::UnknownKey(k.into())),
+        }
+        Ok(())
+    }
+    fn keys() -> Vec<KeyInfo> {
+        let port = ValueKind::Int {
+            min: Some(1),
+            max: Some(i64::from(u16::MAX)),
+        };
+        vec![
+            KeyInfo::new("ip")
+                .value_name("IP")
+                .help("The host address to bind to"),
+            KeyInfo::new("host")
+                .value_name("PORT")
+                .kind(port)
+                .help("The host port"),
+            KeyInfo::new("container")
+                .value_name("PORT")
+                .kind(port)
+                .required(true)
+                .help("The container port"),
+            KeyInfo::new("protocol")
+                .value_name("PROTOCOL")
+                .kind(ValueKind::Enum {
+                    variants: Protocol::NAMES,
+                })
+                .default("tcp")
+                .help("The transport protocol"),
+        ]
+    }
+    fn value_candidates(key: &str) -> Vec<CompletionCandidate> {
+        match key {
+            "protocol" => Protocol::NAMES
+                .iter()
+                .map(|&name| CompletionCandidate::new(name))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+    fn example() -> Option<&'static str> {
+        Some("127.0.0.1:8080:80/tcp")
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        if self.container == 0 {
+            return Err(SubOptError::MissingKey("container".into()));
+        }
+        Ok(self)
+    }
+}
+
+/// The error of parsing a [`KeyVal`], [`HostPort`] or [`UserGroup`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresetError(String);
+
+impl std::fmt::Display for PresetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+/// A `NAME=VALUE` pair, like the values of `--env` or `--label`.
+///
+/// The value is everything after the first `=`, and can be empty. The name can't.
+///
+/// ```
+/// use clap_subopt_parser::presets::KeyVal;
+/// use clap_subopt_parser::parse_kv;
+///
+/// let var: KeyVal = "PATH=/usr/bin:/bin".parse().unwrap();
+/// assert_eq!((var.key.as_str(), var.value.as_str()), ("PATH", "/usr/bin:/bin"));
+/// assert_eq!(var.to_string(), "PATH=/usr/bin:/bin");
+/// assert_eq!(
+///     parse_kv::<KeyVal>("env", "PATH").unwrap_err().to_string(),
+///     "Invalid value for key 'env': missing '=' (expected KeyVal)"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyVal {
+    /// The name
+    pub key: String,
+    /// The value
+    pub value: String,
+}
+
+impl std::str::FromStr for KeyVal {
+    type Err = PresetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| PresetError("missing '='".into()))?;
+        if key.is_empty() {
+            return Err(PresetError("missing name".into()));
+        }
+        Ok(Self {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+}
+
+impl std::fmt::Display for KeyVal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+/// A host name or address and a port, like `example.com:443` or `[::1]:80`, without resolving
+/// it.
+///
+/// As a [`SubOpt`], the bare values are `HOST:PORT`, and the keys `host` and `port` can be used
+/// instead. Both are required.
+///
+/// ```
+/// use clap_subopt_parser::presets::HostPort;
+/// use clap_subopt_parser::{parse_kv, parse_str};
+///
+/// let upstream: HostPort = parse_str("example.com:443").unwrap();
+/// assert_eq!((upstream.host.as_str(), upstream.port), ("example.com", 443));
+/// let upstream: HostPort = parse_str("port=80:host=[::1]").unwrap();
+/// assert_eq!(upstream.to_string(), "[::1]:80");
+///
+/// let upstream: HostPort = "[::1]:80".parse().unwrap();
+/// assert_eq!((upstream.host.as_str(), upstream.port), ("::1", 80));
+/// assert_eq!(
+///     parse_kv::<HostPort>("upstream", "example.com").unwrap_err().to_string(),
+///     "Invalid value for key 'upstream': missing port (expected HostPort)"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HostPort {
+    /// The host name or address, without brackets
+    pub host: String,
+    /// The port
+    pub port: u16,
+}
+
+impl std::str::FromStr for HostPort {
+    type Err = PresetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (host, port) = values::split_host_port(s).map_err(PresetError)?;
+        Ok(Self {
+            host: values::unbracket(host).to_owned(),
+            port,
+        })
+    }
+}
+
+impl std::fmt::Display for HostPort {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl SubOpt for HostPort {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        if self.host.is_empty() {
+            self.host = values::unbracket(k).to_owned();
+        } else if self.port == 0 {
+            self.port = parse_kv("port", k)?;
+        } else {
+            return Err(SubOptError::UnknownKey(k.into()));
+        }
+        Ok(())
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        match k {
+            "host" => self.host = values::unbracket(v).to_owned(),
+            "port" => self.port = parse_kv(k, v)?,
+            _ => return Err(SubOptError::UnknownKey(k.into())),
+        }
+        Ok(())
+    }
+    fn keys() -> Vec<KeyInfo> {
+        vec![
+            KeyInfo::new("host")
+                .value_name("HOST")
+                .required(true)
+                .help("The host name or address"),
+            KeyInfo::new("port")
+                .value_name("PORT")
+                .kind(ValueKind::Int {
+                    min: Some(1),
+                    max: Some(i64::from(u16::MAX)),
+                })
+                .required(true)
+                .help("The port"),
+        ]
+    }
+    fn example() -> Option<&'static str> {
+        Some("example.com:443")
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        if self.host.is_empty() {
+            return Err(SubOptError::MissingKey("host".into()));
+        }
+        if self.port == 0 {
+            return Err(SubOptError::MissingKey("port".into()));
+        }
+        Ok(self)
+    }
+}
+
+/// A user and an optional group, like `www-data:www-data` or `1000`, for `--user` arguments.
+///
+/// Names and numeric IDs are kept as given. As a [`SubOpt`], the bare values are
+/// `USER[:GROUP]`, and the keys `user` and `group` can be used instead. The user is required.
+///
+/// ```
+/// use clap_subopt_parser::presets::UserGroup;
+/// use clap_subopt_parser::{parse_kv, parse_str};
+///
+/// let owner: UserGroup = parse_str("1000:100").unwrap();
+/// assert_eq!((owner.user.as_str(), owner.group.as_deref()), ("1000", Some("100")));
+/// let owner: UserGroup = "nobody".parse().unwrap();
+/// assert_eq!((owner.user.as_str(), owner.group), ("nobody", None));
+/// assert_eq!(
+///     parse_kv::<UserGroup>("owner", ":100").unwrap_err().to_string(),
+///     "Invalid value for key 'owner': missing user (expected UserGroup)"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UserGroup {
+    /// The user name or ID
+    pub user: String,
+    /// The group name or ID
+    pub group: Option<String>,
+}
+
+impl std::str::FromStr for UserGroup {
+    type Err = PresetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (user, group) = match s.split_once(':') {
+            Some((user, group)) => (user, Some(group)),
+            None => (s, None),
+        };
+        if user.is_empty() {
+            return Err(PresetError("missing user".into()));
+        }
+        if group.is_some_and(str::is_empty) {
+            return Err(PresetError("missing group".into()));
+        }
+        Ok(Self {
+            user: user.to_owned(),
+            group: group.map(str::to_owned),
+        })
+    }
+}
+
+impl std::fmt::Display for UserGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.group {
+            Some(group) => write!(f, "{}:{}", self.user, group),
+            None => f.write_str(&self.user),
+        }
+    }
+}
+
+impl SubOpt for UserGroup {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        if self.user.is_empty() {
+            self.user = k.to_owned();
+        } else if self.group.is_none() {
+            self.group = Some(k.to_owned());
+        } else {
+            return Err(SubOptError::UnknownKey(k.into()));
+        }
+        Ok(())
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        match k {
+            "user" => self.user = v.to_owned(),
+            "group" => self.group = Some(v.to_owned()),
+            _ => return Err(SubOptError::UnknownKey(k.into())),
+        }
+        Ok(())
+    }
+    fn keys() -> Vec<KeyInfo> {
+        vec![
+            KeyInfo::new("user")
+                .value_name("USER")
+                .required(true)
+                .help("The user name or ID"),
+            KeyInfo::new("group")
+                .value_name("GROUP")
+                .help("The group name or ID"),
+        ]
+    }
+    fn example() -> Option<&'static str> {
+        Some("www-data:www-data")
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        if self.user.is_empty() {
+            return Err(SubOptError::MissingKey("user".into()));
+        }
+        Ok(self)
+    }
+}
+
+/// A chain of filters in FFmpeg filtergraph syntax, like `scale=w=100:h=50,crop=10:10`, for
+/// media tools.
+///
+/// Filters are separated by `,`, and each is a name, optionally followed by `=` and its
+/// sub-options, which are parsed as `T`. A filter without sub-options is a default `T`. The
+/// filters are kept in order, and can repeat.
+///
+/// ```
+/// use clap_subopt_parser::presets::Filtergraph;
+/// use clap_subopt_parser::{parse_str, KvMap};
+///
+/// let graph: Filtergraph<KvMap> = parse_str("scale=w=100:h=50,crop=10:10,hflip").unwrap();
+/// let names: Vec<_> = graph.0.iter().map(|(name, _)| name.as_str()).collect();
+/// assert_eq!(names, ["scale", "crop", "hflip"]);
+/// assert_eq!(graph.0[0].1.pairs["w"], "100");
+/// assert_eq!(graph.0[1].1.values, ["10", "10"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filtergraph<T>(pub Vec<(String, T)>);
+
+impl<T> Default for Filtergraph<T> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<T: SubOpt + Default> SubOpt for Filtergraph<T> {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        self.update_from_kvpair(k, "")
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        let filter = SubOptParser::<T>::default().allow_empty(true).parse(v)?;
+        self.0.push((k.to_owned(), filter));
+        Ok(())
+    }
+    const SEPARATOR: char = ',';
+}
+
+/// A [`SubOpt`] wrapper for `T` in systemd property syntax, with spaces between sub-options,
+/// like `--property "CPUQuota=20% MemoryMax=1G"`, matching `systemd-run -p`.
+///
+/// The argument is usually quoted on the command line, so that it is a single argument. Runs of
+/// spaces separate like one, and values containing spaces can be put in square brackets.
+///
+/// ```
+/// use clap_subopt_parser::presets::SystemdProperties;
+/// use clap_subopt_parser::{parse_str, KvMap};
+///
+/// let props: SystemdProperties<KvMap> =
+///     parse_str("CPUQuota=20%  MemoryMax=1G Description=[web server]").unwrap();
+/// assert_eq!(props.0.pairs["CPUQuota"], "20%");
+/// assert_eq!(props.0.pairs["MemoryMax"], "1G");
+/// assert_eq!(props.0.pairs["Description"], "[web server]");
+/// assert!(clap_subopt_parser::take_warnings().is_empty());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SystemdProperties<T>(pub T);
+
+impl<T: SubOpt> SubOpt for SystemdProperties<T> {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        self.0.update_from_value(k)
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        self.0.update_from_kvpair(k, v)
+    }
+    fn keys() -> Vec<KeyInfo> {
+        T::keys()
+    }
+    fn value_candidates(key: &str) -> Vec<CompletionCandidate> {
+        T::value_candidates(key)
+    }
+    fn example() -> Option<&'static str> {
+        T::example()
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        self.0.finish().map(Self)
+    }
+    fn validate(&self) -> Result<(), SubOptError> {
+        self.0.validate()
+    }
+    fn set_occurrence(&mut self, index: usize) {
+        self.0.set_occurrence(index)
+    }
+    fn set_position(&mut self, position: SegmentPosition) {
+        self.0.set_position(position)
+    }
+    const SEPARATOR: char = ' ';
+}
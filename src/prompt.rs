@@ -0,0 +1,37 @@
+use crate::KeyInfo;
+use std::io::{BufRead, IsTerminal, Write};
+
+/// Asks for the values of missing [required](KeyInfo::required) keys, see
+/// [`SubOptParser::prompt`](crate::SubOptParser::prompt)
+pub trait Prompt: Send + Sync {
+    /// Ask for the value of `key`.
+    ///
+    /// Returns `None` if no value could be obtained, in which case the key stays missing.
+    fn prompt(&self, key: &KeyInfo) -> Option<String>;
+}
+
+/// A [`Prompt`] that asks on the terminal.
+///
+/// The question goes to stderr, and the answer is read from stdin. Nothing is asked if stdin
+/// is not a terminal, so scripts behave the same as without prompting.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Terminal;
+
+impl Prompt for Terminal {
+    fn prompt(&self, key: &KeyInfo) -> Option<String> {
+        let stdin = std::io::stdin();
+        if !stdin.is_terminal() {
+            return None;
+        }
+        let mut stderr = std::io::stderr();
+        let mut question = key.name.to_owned();
+        if let Some(help) = key.help {
+            question += &format!(" ({})", help);
+        }
+        write!(stderr, "{}: ", question).ok()?;
+        stderr.flush().ok()?;
+        let mut line = String::new();
+        stdin.lock().read_line(&mut line).ok()?;
+        Some(line.trim_end_matches(['\r', '\n']).to_owned())
+    }
+}
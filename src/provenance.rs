@@ -0,0 +1,61 @@
+use std::borrow::Cow;
+
+/// Where the value of a key came from, see [`Provenance`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Source {
+    /// The key was given in the argument, in the sub-option at `index`.
+    ///
+    /// Empty sub-options are not counted, so the first key given has index 0.
+    Argument {
+        /// The index of the sub-option
+        index: usize,
+    },
+    /// The key was taken from this [environment variable](crate::KeyInfo::env)
+    Env(&'static str),
+    /// The key was [prompted](crate::SubOptParser::prompt) for
+    Prompt,
+    /// The key was taken from this
+    /// [configuration file](crate::SubOptParser::parse_with_config)
+    File(std::path::PathBuf),
+}
+
+/// The keys that were set while parsing, and where their values came from.
+///
+/// Keys that match a [`KeyInfo`](crate::KeyInfo) are recorded under its name, even if an alias
+/// was given. This is useful for merging with a configuration file, where only the keys the user
+/// actually set should override the configured values.
+///
+/// Returned by [`SubOptParser::parse_with_provenance`](crate::SubOptParser::parse_with_provenance),
+/// [`SubOptParser::with_provenance`](crate::SubOptParser::with_provenance) and
+/// `SubOptParser::parse_with_config`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    /// The keys and their sources. Keys matching a [`KeyInfo`](crate::KeyInfo) borrow its
+    /// name, so recording them doesn't allocate.
+    entries: Vec<(Cow<'static, str>, Source)>,
+}
+
+impl Provenance {
+    pub(crate) fn record(&mut self, key: impl Into<Cow<'static, str>>, source: Source) {
+        self.entries.push((key.into(), source));
+    }
+    /// Where the value of `key` came from, or `None` if it wasn't set.
+    ///
+    /// If the key was set more than once, this is where the last value came from.
+    pub fn source(&self, key: &str) -> Option<&Source> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, source)| source)
+    }
+    /// Whether `key` was given in the argument
+    pub fn is_explicit(&self, key: &str) -> bool {
+        matches!(self.source(key), Some(Source::Argument { .. }))
+    }
+    /// The keys that were set, in the order they were set
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Source)> {
+        self.entries.iter().map(|(k, source)| (&**k, source))
+    }
+}
@@ -0,0 +1,99 @@
+use crate::tokenize;
+use figment::value::{Dict, Map, Value};
+use figment::{Error, Metadata, Profile, Provider};
+
+/// A [figment](figment) [`Provider`] for sub-options, so an argument like
+/// `--config level=3:verbose` can be merged into the configuration like any other source.
+///
+/// Values are parsed like figment parses environment variables, so `3` is an integer and
+/// `[1, 2]` is an array. Bare values are `true`, and later values win for repeated keys.
+///
+/// ```
+/// use clap_subopt_parser::SubOptProvider;
+/// use figment::{providers::Serialized, Figment};
+/// use serde::Deserialize;
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Buf {
+///     source: usize,
+///     offset: u64,
+///     sync: bool,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Config {
+///     buf: Buf,
+/// }
+///
+/// let config: Config = Figment::new()
+///     .merge(Serialized::default("buf.offset", 512))
+///     .merge(SubOptProvider::new("source=1:offset=4096:sync").key("buf"))
+///     .extract()
+///     .unwrap();
+/// assert_eq!((config.buf.source, config.buf.offset, config.buf.sync), (1, 4096, true));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SubOptProvider {
+    arg: String,
+    key: Option<String>,
+    profile: Profile,
+}
+
+impl SubOptProvider {
+    /// Provide the sub-options of `arg`
+    pub fn new(arg: impl Into<String>) -> Self {
+        Self {
+            arg: arg.into(),
+            key: None,
+            profile: Profile::Default,
+        }
+    }
+    /// Put the sub-options under `key`, instead of at the top level
+    #[must_use]
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+    /// Provide the sub-options for `profile`, instead of the default profile.
+    ///
+    /// With [`Profile::Global`], they override the values of every profile.
+    ///
+    /// ```
+    /// use clap_subopt_parser::SubOptProvider;
+    /// use figment::Figment;
+    ///
+    /// let figment = Figment::new().merge(SubOptProvider::new("level=3").profile("debug"));
+    /// assert!(figment.extract_inner::<u8>("level").is_err());
+    /// assert_eq!(figment.select("debug").extract_inner::<u8>("level").unwrap(), 3);
+    /// ```
+    #[must_use]
+    pub fn profile(mut self, profile: impl Into<Profile>) -> Self {
+        self.profile = profile.into();
+        self
+    }
+}
+
+impl Provider for SubOptProvider {
+    fn metadata(&self) -> Metadata {
+        let name = match &self.key {
+            Some(key) => format!("`{}` sub-options", key),
+            None => "sub-options".to_owned(),
+        };
+        Metadata::named(name).source(self.arg.clone())
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, Error> {
+        let mut dict = Dict::new();
+        for (_, k, v) in tokenize(&self.arg, crate::DEFAULT_SEPARATOR, '=') {
+            let value = match v {
+                Some(v) => v.parse().unwrap_or_else(|e| match e {}),
+                None => Value::from(true),
+            };
+            dict.insert(k.to_owned(), value);
+        }
+        if let Some(key) = &self.key {
+            dict = Dict::from([(key.clone(), dict.into())]);
+        }
+        Ok(Map::from([(self.profile.clone(), dict)]))
+    }
+}
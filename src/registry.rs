@@ -0,0 +1,159 @@
+use crate::{SubOpt, SubOptError, SubOptParser};
+#[cfg(feature = "clap")]
+use clap::builder::TypedValueParser;
+use std::sync::Arc;
+
+type ParseFn<D> = dyn Fn(&str) -> Result<Box<D>, SubOptError> + Send + Sync;
+
+/// A [`TypedValueParser`] for sub-option types registered at runtime under a name, for
+/// extensible CLIs where plugins provide kinds of outputs or backends.
+///
+/// The first segment of the argument selects the type, and the rest is parsed as that type,
+/// so `tcp:port=80` is parsed as the type registered as `tcp` from `port=80`. Each type is
+/// converted to the trait object `D`, and the values are of type `Arc<D>`.
+///
+/// ```
+/// use clap_subopt_parser::{parse_kv, SubOpt, SubOptError, SubOptRegistry};
+///
+/// trait Output: Send + Sync {
+///     fn describe(&self) -> String;
+/// }
+///
+/// #[derive(Default)]
+/// struct Tcp {
+///     port: u16,
+/// }
+///
+/// impl Output for Tcp {
+///     fn describe(&self) -> String {
+///         format!("tcp on port {}", self.port)
+///     }
+/// }
+///
+/// impl SubOpt for Tcp {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         Err(SubOptError::UnknownKey(k.into()))
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "port" => self.port = parse_kv(k, v)?,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let registry = SubOptRegistry::<dyn Output>::new()
+///     .register::<Tcp>("tcp", |tcp| Box::new(tcp));
+/// assert_eq!(registry.parse("tcp:port=80").unwrap().describe(), "tcp on port 80");
+/// assert!(registry.parse("udp:port=80").is_err());
+/// assert_eq!(registry.names().collect::<Vec<_>>(), ["tcp"]);
+///
+/// # #[cfg(feature = "clap")] {
+/// use clap::{Arg, Command};
+/// use std::sync::Arc;
+///
+/// let m = Command::new("myapp")
+///     .arg(Arg::new("output").long("output").value_parser(registry))
+///     .get_matches_from(["myapp", "--output", "tcp"]);
+/// let output = m.get_one::<Arc<dyn Output>>("output").unwrap();
+/// assert_eq!(output.describe(), "tcp on port 0");
+/// # }
+/// ```
+pub struct SubOptRegistry<D: ?Sized> {
+    types: Vec<Registered<D>>,
+}
+
+struct Registered<D: ?Sized> {
+    name: &'static str,
+    sep: char,
+    parse: Arc<ParseFn<D>>,
+}
+
+impl<D: ?Sized> Clone for Registered<D> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name,
+            sep: self.sep,
+            parse: self.parse.clone(),
+        }
+    }
+}
+
+impl<D: ?Sized> Clone for SubOptRegistry<D> {
+    fn clone(&self) -> Self {
+        Self {
+            types: self.types.clone(),
+        }
+    }
+}
+
+impl<D: ?Sized> Default for SubOptRegistry<D> {
+    fn default() -> Self {
+        Self { types: Vec::new() }
+    }
+}
+
+impl<D: ?Sized + 'static> SubOptRegistry<D> {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Register `T` as `name`, converting its values to `D` with `into`.
+    ///
+    /// `T` is parsed with the default [`SubOptParser`] settings, except that the rest of the
+    /// argument can be empty.
+    #[must_use]
+    pub fn register<T: SubOpt + Default>(
+        mut self,
+        name: &'static str,
+        into: fn(T) -> Box<D>,
+    ) -> Self {
+        let parser = SubOptParser::<T>::default().allow_empty(true);
+        self.types.push(Registered {
+            name,
+            sep: T::SEPARATOR,
+            parse: Arc::new(move |s| parser.parse(s).map(into)),
+        });
+        self
+    }
+    /// The names of the registered types, in the order they were registered.
+    ///
+    /// These are not reported to clap as possible values, as clap's help would list them as the
+    /// only valid values of the argument, which also takes the sub-options of the type.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.types.iter().map(|ty| ty.name)
+    }
+    /// Parse `s`, without clap
+    pub fn parse(&self, s: &str) -> Result<Box<D>, SubOptError> {
+        if s.trim().is_empty() {
+            return Err(SubOptError::EmptyArgument);
+        }
+        for ty in &self.types {
+            let (name, rest) = s.split_once(ty.sep).unwrap_or((s, ""));
+            if name == ty.name {
+                return (ty.parse)(rest);
+            }
+        }
+        let name = s
+            .split_once(crate::DEFAULT_SEPARATOR)
+            .map_or(s, |(name, _)| name);
+        Err(SubOptError::UnknownKey(name.into()))
+    }
+}
+
+#[cfg(feature = "clap")]
+impl<D: ?Sized + Send + Sync + 'static> TypedValueParser for SubOptRegistry<D> {
+    type Value = Arc<D>;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        self.parse(crate::to_str(value))
+            .map(Arc::from)
+            .map_err(|e| e.format(cmd, arg))
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{tokenize, values, KeyInfo, SubOptError, ValueKind, DEFAULT_SEPARATOR};
+#[cfg(feature = "clap")]
+use clap::builder::TypedValueParser;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::path::PathBuf;
+
+/// Key definitions as data, for parsing sub-options without a [`SubOpt`](crate::SubOpt) type.
+///
+/// Each value is parsed according to the [kind](KeyInfo::kind) of its key, and stored under the
+/// name of the key, even if an alias was given. Keys that aren't given take their
+/// [environment](KeyInfo::env) or [default](KeyInfo::default) value, and missing
+/// [required](KeyInfo::required) keys are an error. Keys without a
+/// [value name](KeyInfo::value_name) and keys of kind [`ValueKind::Bool`] can be given as bare
+/// values, which sets them to `true`.
+///
+/// Use [`SchemaSubOptParser`] to parse arguments with it.
+///
+/// ```
+/// use clap_subopt_parser::{KeyInfo, KeySchema, SchemaValue, ValueKind};
+///
+/// let schema = KeySchema::new(vec![
+///     KeyInfo::new("source").value_name("INDEX").required(true)
+///         .kind(ValueKind::Int { min: Some(0), max: None }),
+///     KeyInfo::new("offset").value_name("BYTES").default("0")
+///         .kind(ValueKind::Int { min: Some(0), max: None }),
+///     KeyInfo::new("ro").kind(ValueKind::Bool),
+/// ]);
+/// let map = schema.parse("source=1:ro").unwrap();
+/// assert_eq!(map["source"], SchemaValue::Int(1));
+/// assert_eq!(map["offset"], SchemaValue::Int(0));
+/// assert_eq!(map["ro"], SchemaValue::Bool(true));
+/// assert_eq!(
+///     schema.parse("offset=4").unwrap_err().to_string(),
+///     "Missing required key 'source'"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySchema {
+    keys: Vec<KeyInfo>,
+}
+
+/// A value parsed by a [`KeySchema`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SchemaValue {
+    /// A value of kind [`ValueKind::Int`]
+    Int(i64),
+    /// A value of kind [`ValueKind::Bool`], or a bare value
+    Bool(bool),
+    /// A value of kind [`ValueKind::Path`]
+    Path(PathBuf),
+    /// A value of kind [`ValueKind::String`] or [`ValueKind::Enum`], or of a key without a kind
+    String(String),
+}
+
+impl KeySchema {
+    /// Create a schema with the keys `keys`
+    pub fn new(keys: Vec<KeyInfo>) -> Self {
+        Self { keys }
+    }
+    /// The keys
+    pub fn keys(&self) -> &[KeyInfo] {
+        &self.keys
+    }
+    /// Parse `s` into a map from key names to values
+    pub fn parse(&self, s: &str) -> Result<BTreeMap<String, SchemaValue>, SubOptError> {
+        if s.trim().is_empty() {
+            return Err(SubOptError::EmptyArgument);
+        }
+        let mut map = BTreeMap::new();
+        for (span, k, v) in tokenize(s, DEFAULT_SEPARATOR, '=') {
+            let value = match self.keys.iter().find(|key| key.matches(k)) {
+                Some(key) => parse_value(key, v).map(|value| (key.name, value)),
+                None => Err(SubOptError::UnknownKey(k.into())),
+            };
+            let (name, value) = value.map_err(|e| {
+                e.in_segment(s, span, '=')
+                    .redact(DEFAULT_SEPARATOR, '=', |k| {
+                        self.keys.iter().any(|key| key.secret && key.matches(k))
+                    })
+            })?;
+            map.insert(name.to_owned(), value);
+        }
+        for key in &self.keys {
+            if map.contains_key(key.name) {
+                continue;
+            }
+            let env = key.env.and_then(|var| std::env::var(var).ok());
+            let value = match (env, key.default) {
+                (Some(env), _) if key.value_name.is_some() || key.kind == Some(ValueKind::Bool) => {
+                    Some(parse_value(key, Some(&env))?)
+                }
+                (Some(env), _) if !env.is_empty() => Some(parse_value(key, None)?),
+                (_, Some(default)) => Some(parse_value(key, Some(default))?),
+                _ if key.required => return Err(SubOptError::MissingKey(key.name.into())),
+                _ => None,
+            };
+            if let Some(value) = value {
+                map.insert(key.name.to_owned(), value);
+            }
+        }
+        Ok(map)
+    }
+}
+
+pub(crate) fn parse_value(key: &KeyInfo, value: Option<&str>) -> Result<SchemaValue, SubOptError> {
+    let value = match (key.kind, value) {
+        (Some(ValueKind::Bool), None) => return Ok(SchemaValue::Bool(true)),
+        (None, None) if key.value_name.is_none() => return Ok(SchemaValue::Bool(true)),
+        (_, None) => return Err(SubOptError::MissingValueForKey(key.name.into())),
+        (_, Some(value)) => value,
+    };
+    Ok(match key.kind {
+        Some(ValueKind::Int { min, max }) => {
+            let range = (
+                min.map_or(Bound::Unbounded, Bound::Included),
+                max.map_or(Bound::Unbounded, Bound::Included),
+            );
+            SchemaValue::Int(values::int_in(key.name, value, range)?)
+        }
+        Some(ValueKind::Bool) => SchemaValue::Bool(values::boolean(key.name, value)?),
+        Some(ValueKind::Enum { variants }) if !variants.contains(&value) => {
+            return Err(
+                SubOptError::invalid_value(key.name, format!("unknown value '{}'", value))
+                    .expected(format!("one of {}", variants.join(", "))),
+            )
+        }
+        Some(ValueKind::Path) => SchemaValue::Path(value.into()),
+        _ => SchemaValue::String(value.to_owned()),
+    })
+}
+
+/// The [`TypedValueParser`] for a [`KeySchema`], with values of type
+/// `BTreeMap<String, SchemaValue>`
+#[cfg(feature = "clap")]
+#[derive(Debug, Clone)]
+pub struct SchemaSubOptParser {
+    schema: KeySchema,
+}
+
+#[cfg(feature = "clap")]
+impl SchemaSubOptParser {
+    /// Create a parser for `schema`
+    pub fn new(schema: KeySchema) -> Self {
+        Self { schema }
+    }
+    /// The schema of the parser
+    pub fn schema(&self) -> &KeySchema {
+        &self.schema
+    }
+}
+
+#[cfg(feature = "clap")]
+impl TypedValueParser for SchemaSubOptParser {
+    type Value = BTreeMap<String, SchemaValue>;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        self.schema
+            .parse(crate::to_str(value))
+            .map_err(|e| e.format(cmd, arg))
+    }
+}
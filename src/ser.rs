@@ -0,0 +1,119 @@
+use crate::{schema::parse_value, KeyTable, SchemaValue, SubOptDisplay};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// A [`SubOptDisplay`] value as a serde map from keys to values, for dumping the effective
+/// configuration as JSON, TOML, or any other format.
+///
+/// The values are typed according to the [kind](crate::KeyInfo::kind) of their key, so
+/// integers and booleans are serialized as such, and bare values are `true`. Other values are
+/// strings. Keys given more than once are serialized once, with the last value.
+///
+/// ```
+/// # #[cfg(feature = "json")] {
+/// use clap_subopt_parser::{KeyInfo, KeyedFields, SchemaValue, SerializeSubOpt, SubOptDisplay};
+/// use clap_subopt_parser::{SubOptError, ValueKind};
+///
+/// #[derive(Default)]
+/// struct Buf {
+///     offset: i64,
+///     ro: bool,
+///     token: String,
+/// }
+///
+/// impl KeyedFields for Buf {
+///     fn fields() -> Vec<KeyInfo> {
+///         vec![
+///             KeyInfo::new("offset").value_name("BYTES").kind(ValueKind::Int { min: None, max: None }),
+///             KeyInfo::new("ro").kind(ValueKind::Bool),
+///             KeyInfo::new("token").value_name("TOKEN").secret(true),
+///         ]
+///     }
+///     fn set(&mut self, key: &str, value: SchemaValue) -> Result<(), SubOptError> {
+///         match (key, value) {
+///             ("offset", SchemaValue::Int(v)) => self.offset = v,
+///             ("ro", SchemaValue::Bool(v)) => self.ro = v,
+///             ("token", SchemaValue::String(v)) => self.token = v,
+///             (key, _) => return Err(SubOptError::UnknownKey(key.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// impl SubOptDisplay for Buf {
+///     fn sub_options(&self) -> Vec<(&'static str, Option<String>)> {
+///         vec![
+///             ("offset", Some(self.offset.to_string())),
+///             ("ro", Some(self.ro.to_string())),
+///             ("token", Some(self.token.clone())),
+///         ]
+///     }
+/// }
+///
+/// let buf = Buf { offset: 512, ro: true, token: "hunter2".into() };
+/// assert_eq!(
+///     serde_json::to_string(&SerializeSubOpt::new(&buf).redacted(true)).unwrap(),
+///     r#"{"offset":512,"ro":true,"token":"***"}"#
+/// );
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SerializeSubOpt<'a, T> {
+    value: &'a T,
+    redacted: bool,
+}
+
+impl<T> Clone for SerializeSubOpt<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for SerializeSubOpt<'_, T> {}
+
+impl<'a, T: SubOptDisplay> SerializeSubOpt<'a, T> {
+    /// Serialize `value`
+    pub fn new(value: &'a T) -> Self {
+        Self {
+            value,
+            redacted: false,
+        }
+    }
+    /// Serialize the values of [secret](crate::KeyInfo::secret) keys as `***`
+    #[must_use]
+    pub fn redacted(mut self, yes: bool) -> Self {
+        self.redacted = yes;
+        self
+    }
+}
+
+impl<T: SubOptDisplay> Serialize for SerializeSubOpt<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let keys = &KeyTable::cached::<T>().keys;
+        let mut entries: Vec<(&str, SchemaValue)> = Vec::new();
+        for (k, v) in self.value.sub_options() {
+            let key = keys.iter().find(|key| key.matches(k));
+            let value = match (key, v) {
+                (Some(key), _) if key.secret && self.redacted => SchemaValue::String("***".into()),
+                (Some(key), v) => match parse_value(key, v.as_deref()) {
+                    Ok(value) => value,
+                    Err(_) => SchemaValue::String(v.unwrap_or_default()),
+                },
+                (None, Some(v)) => SchemaValue::String(v),
+                (None, None) => SchemaValue::Bool(true),
+            };
+            let name = key.map_or(k, |key| key.name);
+            entries.retain(|(k, _)| *k != name);
+            entries.push((name, value));
+        }
+        let mut map = serializer.serialize_map(Some(entries.len()))?;
+        for (k, v) in &entries {
+            match v {
+                SchemaValue::Int(i) => map.serialize_entry(k, i)?,
+                SchemaValue::Bool(b) => map.serialize_entry(k, b)?,
+                SchemaValue::Path(path) => map.serialize_entry(k, &path.to_string_lossy())?,
+                SchemaValue::String(s) => map.serialize_entry(k, s)?,
+            }
+        }
+        map.end()
+    }
+}
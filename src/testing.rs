@@ -0,0 +1,395 @@
+//! Helpers for testing [`SubOpt`] implementations without constructing clap commands.
+//!
+//! ```
+//! use clap_subopt_parser::{testing, SubOpt, SubOptError};
+//!
+//! #[derive(Default)]
+//! struct Buf {
+//!     offset: usize,
+//! }
+//!
+//! impl SubOpt for Buf {
+//!     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+//!         Err(SubOptError::UnknownKey(k.into()))
+//!     }
+//!     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+//!         match k {
+//!             "offset" => self.offset = v.parse().map_err(|e| SubOptError::invalid_value(k, e))?,
+//!             _ => return Err(SubOptError::UnknownKey(k.into())),
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! assert_eq!(testing::parse_ok::<Buf>("offset=42").offset, 42);
+//! testing::assert_parse_err::<Buf>("offset=x", |e| {
+//!     matches!(e, SubOptError::InvalidValue { .. })
+//! });
+//! ```
+
+use crate::{SubOpt, SubOptError};
+
+/// Parse `s` as `T`, panicking with the error message if it fails
+#[track_caller]
+pub fn parse_ok<T: SubOpt + Default>(s: &str) -> T {
+    match crate::parse_str(s) {
+        Ok(val) => val,
+        Err(e) => panic!("parsing {:?} failed: {}", s, e),
+    }
+}
+
+/// Parse `s` as `T`, and assert that it fails with an error satisfying `pred`.
+///
+/// `pred` receives the [root](SubOptError::root) error, without location information.
+/// The full error is returned for further inspection.
+#[track_caller]
+pub fn assert_parse_err<T: SubOpt + Default>(
+    s: &str,
+    pred: impl FnOnce(&SubOptError) -> bool,
+) -> SubOptError {
+    match crate::parse_str::<T>(s) {
+        Ok(_) => panic!("parsing {:?} succeeded, but an error was expected", s),
+        Err(e) => {
+            assert!(pred(e.root()), "unexpected error for {:?}: {:?}", s, e);
+            e
+        }
+    }
+}
+
+/// Check the [key metadata](SubOpt::keys) of `T` for mistakes, and return a description of
+/// each one found.
+///
+/// These are declarations that can't work as intended, which parsing doesn't report:
+/// - Keys and aliases with the same name, so one of them is unreachable
+/// - Empty names, or names containing [`SubOpt::SEPARATOR`] or [`SubOpt::KV_SEPARATOR`], which
+///   can't be given
+/// - [Required](crate::KeyInfo::required) keys with a [default](crate::KeyInfo::default)
+/// - Defaults that don't match the [kind](crate::KeyInfo::kind) of their key
+/// - Integer kinds with a minimum above the maximum, and enum kinds without variants
+/// - [Environment variables](crate::KeyInfo::env) used by more than one key
+///
+/// See [`assert_schema_valid`] for a test that fails on them.
+///
+/// ```
+/// use clap_subopt_parser::{testing, KeyInfo, SubOpt, SubOptError, ValueKind};
+///
+/// struct Buf;
+///
+/// impl SubOpt for Buf {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> { Ok(()) }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> { Ok(()) }
+///     fn keys() -> Vec<KeyInfo> {
+///         vec![
+///             KeyInfo::new("offset").aliases(&["off"]).value_name("BYTES"),
+///             KeyInfo::new("off").kind(ValueKind::Bool),
+///             KeyInfo::new("mode").value_name("MODE").required(true).default("rw"),
+///         ]
+///     }
+/// }
+///
+/// assert_eq!(
+///     testing::schema_problems::<Buf>(),
+///     [
+///         "Key 'off' is declared more than once",
+///         "Key 'mode' is required, but has a default",
+///     ]
+/// );
+/// ```
+pub fn schema_problems<T: SubOpt>() -> Vec<String> {
+    let keys = &crate::KeyTable::cached::<T>().keys;
+    let mut problems = Vec::new();
+    let mut names: Vec<&str> = Vec::new();
+    let mut envs: Vec<&str> = Vec::new();
+    for key in keys.iter() {
+        for &name in std::iter::once(&key.name).chain(key.aliases) {
+            if names.contains(&name) {
+                problems.push(format!("Key '{}' is declared more than once", name));
+            } else if name.is_empty() || name.contains([T::SEPARATOR, T::KV_SEPARATOR]) {
+                problems.push(format!("Key '{}' can't be given, due to its name", name));
+            }
+            names.push(name);
+        }
+        if key.required && key.default.is_some() {
+            problems.push(format!("Key '{}' is required, but has a default", key.name));
+        }
+        match key.kind {
+            Some(crate::ValueKind::Int {
+                min: Some(min),
+                max: Some(max),
+            }) if min > max => problems.push(format!(
+                "Key '{}' has a minimum of {}, above its maximum of {}",
+                key.name, min, max
+            )),
+            Some(crate::ValueKind::Enum { variants: [] }) => {
+                problems.push(format!("Key '{}' has no enum variants", key.name))
+            }
+            _ => {
+                if let Some(Err(e)) = key
+                    .default
+                    .map(|default| crate::schema::parse_value(key, Some(default)))
+                {
+                    problems.push(format!("Key '{}' has an invalid default: {}", key.name, e));
+                }
+            }
+        }
+        if let Some(env) = key.env {
+            if envs.contains(&env) {
+                problems.push(format!(
+                    "Environment variable {} is used by more than one key",
+                    env
+                ));
+            }
+            envs.push(env);
+        }
+    }
+    problems
+}
+
+/// Assert that the [key metadata](SubOpt::keys) of `T` has no mistakes found by
+/// [`schema_problems`], panicking with all of them otherwise.
+///
+/// Call this in a test for each [`SubOpt`] type, so mistakes fail the test instead of
+/// confusing users.
+///
+/// ```
+/// use clap_subopt_parser::{subopt, testing};
+///
+/// subopt! {
+///     struct Buf {
+///         source: usize,
+///         offset: usize = 4096,
+///     }
+/// }
+///
+/// testing::assert_schema_valid::<Buf>();
+/// ```
+#[track_caller]
+pub fn assert_schema_valid<T: SubOpt>() {
+    let problems = schema_problems::<T>();
+    assert!(
+        problems.is_empty(),
+        "the keys of {} have problems:\n{}",
+        std::any::type_name::<T>(),
+        problems.join("\n")
+    );
+}
+
+/// Generate a valid argument for `T` from its [key metadata](SubOpt::keys), with
+/// [`arbitrary`] input, like in a fuzz target.
+///
+/// Required keys are always given, and other keys sometimes, by their name or one of their
+/// aliases. Values are generated according to the [kind](crate::KeyInfo::kind) of their key,
+/// or its [type name](crate::KeyInfo::type_name) for integer and boolean types. Keys without a
+/// [value name](crate::KeyInfo::value_name) are given as bare values. Constraints between keys
+/// that are not described by the metadata, like those checked by [`SubOpt::finish`], are not
+/// taken into account. Types without keys get an empty argument.
+///
+/// ```
+/// # #[cfg(feature = "fuzzing")] {
+/// use arbitrary::Unstructured;
+/// use clap_subopt_parser::{parse_str, subopt, testing};
+///
+/// subopt! {
+///     struct Buf {
+///         source: u8,
+///         offset: i64,
+///         ro: bool,
+///     }
+/// }
+///
+/// let mut u = Unstructured::new(b"some fuzzer input of any length");
+/// let arg = testing::arbitrary_arg::<Buf>(&mut u).unwrap();
+/// assert!(parse_str::<Buf>(&arg).is_ok(), "{}", arg);
+/// # }
+/// ```
+#[cfg(feature = "fuzzing")]
+pub fn arbitrary_arg<T: SubOpt>(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<String> {
+    let keys = &crate::KeyTable::cached::<T>().keys;
+    let mut segments = Vec::new();
+    for key in keys.iter() {
+        if !key.required && !u.arbitrary::<bool>()? {
+            continue;
+        }
+        segments.push(arbitrary_segment::<T>(key, u)?);
+    }
+    if segments.is_empty() {
+        if let Some(key) = keys.first() {
+            segments.push(arbitrary_segment::<T>(key, u)?);
+        }
+    }
+    Ok(segments.join(&T::SEPARATOR.to_string()))
+}
+
+#[cfg(feature = "fuzzing")]
+fn arbitrary_segment<T: SubOpt>(
+    key: &crate::KeyInfo,
+    u: &mut arbitrary::Unstructured<'_>,
+) -> arbitrary::Result<String> {
+    use crate::ValueKind;
+
+    let name = match key.aliases {
+        [] => key.name,
+        aliases if u.ratio(1, 4)? => u.choose(aliases)?,
+        _ => key.name,
+    };
+    if key.value_name.is_none() {
+        return Ok(name.to_owned());
+    }
+    let separators = [T::SEPARATOR, T::KV_SEPARATOR];
+    let type_bounds = key.type_name.and_then(int_bounds);
+    let value = match key.kind {
+        Some(ValueKind::Int { min, max }) => {
+            let (lo, hi) = type_bounds.unwrap_or((i64::MIN, i64::MAX));
+            let lo = min.map_or(lo, |min| min.max(lo));
+            let hi = max.map_or(hi, |max| max.min(hi));
+            u.int_in_range(lo..=hi.max(lo))?.to_string()
+        }
+        Some(ValueKind::Bool) => u.arbitrary::<bool>()?.to_string(),
+        Some(ValueKind::Enum { variants }) if !variants.is_empty() => {
+            (*u.choose(variants)?).to_owned()
+        }
+        Some(ValueKind::Path) => {
+            let mut path = arbitrary_word(u, &separators)?;
+            if !separators.contains(&'/') {
+                for _ in 0..u.int_in_range(0..=3)? {
+                    path.push('/');
+                    path.push_str(&arbitrary_word(u, &separators)?);
+                }
+            }
+            path
+        }
+        _ => match (type_bounds, key.type_name) {
+            (Some((lo, hi)), _) => u.int_in_range(lo..=hi)?.to_string(),
+            (None, Some("bool")) => u.arbitrary::<bool>()?.to_string(),
+            (None, Some("f32" | "f64")) => u.arbitrary::<i32>()?.to_string(),
+            _ => arbitrary_word(u, &separators)?,
+        },
+    };
+    Ok(format!("{}{}{}", name, T::KV_SEPARATOR, value))
+}
+
+/// A short alphanumeric word, without any of `separators`
+#[cfg(feature = "fuzzing")]
+fn arbitrary_word(
+    u: &mut arbitrary::Unstructured<'_>,
+    separators: &[char],
+) -> arbitrary::Result<String> {
+    const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let len = u.int_in_range(1..=8)?;
+    let mut word = String::with_capacity(len);
+    for _ in 0..len {
+        let c = char::from(*u.choose(CHARS)?);
+        if !separators.contains(&c) {
+            word.push(c);
+        }
+    }
+    if word.is_empty() {
+        word.push('x');
+    }
+    Ok(word)
+}
+
+/// The bounds of the integer type `type_name`, clamped to `i64`
+#[cfg(feature = "fuzzing")]
+fn int_bounds(type_name: &str) -> Option<(i64, i64)> {
+    Some(match type_name {
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" | "u128" | "usize" => (0, i64::MAX),
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        "i64" | "i128" | "isize" => (i64::MIN, i64::MAX),
+        _ => return None,
+    })
+}
+
+/// A valid argument for `T`, generated with [`arbitrary_arg`].
+///
+/// This is for fuzz targets and `Arbitrary` derives, which take their input as a type.
+///
+/// ```
+/// # #[cfg(feature = "fuzzing")] {
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use clap_subopt_parser::{parse_str, subopt, testing::ValidArg};
+///
+/// subopt! {
+///     struct Net {
+///         host: String,
+///         port: u16,
+///     }
+/// }
+///
+/// let mut u = Unstructured::new(&[7; 64]);
+/// let arg = ValidArg::<Net>::arbitrary(&mut u).unwrap();
+/// assert!(parse_str::<Net>(&arg.arg).is_ok(), "{:?}", arg);
+/// # }
+/// ```
+#[cfg(feature = "fuzzing")]
+pub struct ValidArg<T> {
+    /// The argument
+    pub arg: String,
+    _type: std::marker::PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "fuzzing")]
+impl<T> std::fmt::Debug for ValidArg<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ValidArg").field(&self.arg).finish()
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+impl<T> Clone for ValidArg<T> {
+    fn clone(&self) -> Self {
+        Self {
+            arg: self.arg.clone(),
+            _type: std::marker::PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+impl<'a, T: SubOpt> arbitrary::Arbitrary<'a> for ValidArg<T> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            arg: arbitrary_arg::<T>(u)?,
+            _type: std::marker::PhantomData,
+        })
+    }
+}
+
+/// A [`proptest`] strategy for valid arguments for `T`, generated like with
+/// [`arbitrary_arg`].
+///
+/// ```
+/// # #[cfg(feature = "fuzzing")] {
+/// use clap_subopt_parser::{parse_str, subopt, testing};
+/// use proptest::test_runner::TestRunner;
+///
+/// subopt! {
+///     struct Buf {
+///         source: usize,
+///         offset: u32 = 4096,
+///         ro: bool,
+///     }
+/// }
+///
+/// TestRunner::default()
+///     .run(&testing::arg_strategy::<Buf>(), |arg| {
+///         assert!(parse_str::<Buf>(&arg).is_ok(), "{}", arg);
+///         Ok(())
+///     })
+///     .unwrap();
+/// # }
+/// ```
+#[cfg(feature = "fuzzing")]
+pub fn arg_strategy<T: SubOpt>() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::{any, Strategy};
+
+    proptest::collection::vec(any::<u8>(), 0..256).prop_map(|bytes| {
+        arbitrary_arg::<T>(&mut arbitrary::Unstructured::new(&bytes))
+            .expect("generating from exhausted input falls back to defaults")
+    })
+}
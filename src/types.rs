@@ -0,0 +1,881 @@
+use crate::{completion::CompletionCandidate, KeyInfo, SegmentPosition, SubOpt, SubOptError};
+use std::collections::BTreeMap;
+
+/// A [`SubOpt`] wrapper that collects unknown key-value pairs instead of failing.
+///
+/// This allows forward compatibility, for example when an older version of a program
+/// receives an option string meant for a newer one.
+///
+/// Unknown bare values are still an error.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Lenient<T> {
+    /// The parsed value
+    pub value: T,
+    /// The unrecognized key-value pairs
+    pub unknown: BTreeMap<String, String>,
+}
+
+impl<T: SubOpt> SubOpt for Lenient<T> {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        self.value.update_from_value(k)
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        match self.value.update_from_kvpair(k, v) {
+            Err(SubOptError::UnknownKey(_)) => {
+                self.unknown.insert(k.to_owned(), v.to_owned());
+                Ok(())
+            }
+            result => result,
+        }
+    }
+    fn keys() -> Vec<KeyInfo> {
+        T::keys()
+    }
+    fn value_candidates(key: &str) -> Vec<CompletionCandidate> {
+        T::value_candidates(key)
+    }
+    fn example() -> Option<&'static str> {
+        T::example()
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        Ok(Self {
+            value: self.value.finish()?,
+            unknown: self.unknown,
+        })
+    }
+    fn validate(&self) -> Result<(), SubOptError> {
+        self.value.validate()
+    }
+    fn set_occurrence(&mut self, index: usize) {
+        self.value.set_occurrence(index)
+    }
+    fn set_position(&mut self, position: SegmentPosition) {
+        self.value.set_position(position)
+    }
+    const SEPARATOR: char = T::SEPARATOR;
+    const KV_SEPARATOR: char = T::KV_SEPARATOR;
+}
+
+/// A secret value, like a password or a token, that is never shown.
+///
+/// [`Debug`](std::fmt::Debug) prints `***`, there is no [`Display`](std::fmt::Display), and
+/// parse errors don't include the value. Mark the key [secret](KeyInfo::secret) too, so the
+/// value is hidden in the argument echoed by errors.
+///
+/// ```
+/// use clap_subopt_parser::{parse_kv, Redacted};
+///
+/// let token: Redacted<String> = parse_kv("token", "hunter2").unwrap();
+/// assert_eq!(token.0, "hunter2");
+/// assert_eq!(format!("{:?}", token), "Redacted(***)");
+///
+/// let err = parse_kv::<Redacted<u32>>("pin", "hunter2").unwrap_err();
+/// assert!(!err.to_string().contains("hunter2"));
+/// ```
+#[derive(Default, Clone, PartialEq, Eq)]
+pub struct Redacted<T>(pub T);
+
+impl<T> std::fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Redacted(***)")
+    }
+}
+
+impl<T: std::str::FromStr> std::str::FromStr for Redacted<T> {
+    type Err = RedactedError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Redacted).map_err(|_| RedactedError)
+    }
+}
+
+/// The error of parsing a [`Redacted`] value, which doesn't say anything about the value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedactedError;
+
+impl std::fmt::Display for RedactedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("invalid secret value (not shown)")
+    }
+}
+
+impl std::error::Error for RedactedError {}
+
+/// A string that is not empty.
+///
+/// ```
+/// use clap_subopt_parser::{parse_kv, NonEmptyString};
+///
+/// let name: NonEmptyString = parse_kv("name", "disk").unwrap();
+/// assert_eq!(&*name, "disk");
+/// assert_eq!(
+///     parse_kv::<NonEmptyString>("name", "").unwrap_err().to_string(),
+///     "Invalid value for key 'name': the value is empty (expected NonEmptyString)"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NonEmptyString(String);
+
+impl NonEmptyString {
+    /// The string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+    /// Convert into a [`String`]
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl std::str::FromStr for NonEmptyString {
+    type Err = EmptyStringError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            Err(EmptyStringError)
+        } else {
+            Ok(Self(s.to_owned()))
+        }
+    }
+}
+
+impl std::ops::Deref for NonEmptyString {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for NonEmptyString {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// The error of parsing an empty [`NonEmptyString`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyStringError;
+
+impl std::fmt::Display for EmptyStringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("the value is empty")
+    }
+}
+
+impl std::error::Error for EmptyStringError {}
+
+/// A string without leading and trailing whitespace, which is removed when parsing.
+///
+/// ```
+/// use clap_subopt_parser::{parse_kv, TrimmedString};
+///
+/// let label: TrimmedString = parse_kv("label", "  boot  ").unwrap();
+/// assert_eq!(&*label, "boot");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TrimmedString(String);
+
+impl TrimmedString {
+    /// The string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+    /// Convert into a [`String`]
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+impl std::str::FromStr for TrimmedString {
+    type Err = std::convert::Infallible;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.trim().to_owned()))
+    }
+}
+
+impl std::ops::Deref for TrimmedString {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for TrimmedString {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A [`SubOpt`] type that collects all sub-options, for prototyping and pass-through arguments.
+///
+/// ```
+/// use clap_subopt_parser::{parse_str, KvMap};
+///
+/// let map: KvMap = parse_str("a=1:verbose:b=x=y").unwrap();
+/// assert_eq!(map.pairs["a"], "1");
+/// assert_eq!(map.pairs["b"], "x=y");
+/// assert_eq!(map.values, ["verbose"]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct KvMap {
+    /// The key-value pairs. Later values win for repeated keys.
+    pub pairs: BTreeMap<String, String>,
+    /// The bare values, in order
+    pub values: Vec<String>,
+}
+
+impl SubOpt for KvMap {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        self.values.push(k.to_owned());
+        Ok(())
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        self.pairs.insert(k.to_owned(), v.to_owned());
+        Ok(())
+    }
+}
+
+/// A [`SubOpt`] type that collects key-value pairs with values of type `V`, for arguments like
+/// `--define NAME=INT` with arbitrary keys.
+///
+/// Values are parsed with [`parse_kv`](crate::parse_kv), and bare values are a
+/// [`SubOptError::MissingValueForKey`] error.
+///
+/// ```
+/// use clap_subopt_parser::{parse_str, TypedKvMap};
+///
+/// let map: TypedKvMap<i32> = parse_str("width=80:height=24").unwrap();
+/// assert_eq!(map.0["width"], 80);
+/// assert_eq!(
+///     parse_str::<TypedKvMap<i32>>("width=wide").unwrap_err().to_string(),
+///     "Invalid value for key 'width': invalid digit found in string (expected i32)"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypedKvMap<V>(pub BTreeMap<String, V>);
+
+impl<V> Default for TypedKvMap<V> {
+    fn default() -> Self {
+        Self(BTreeMap::new())
+    }
+}
+
+impl<V> SubOpt for TypedKvMap<V>
+where
+    V: std::str::FromStr + Send + Sync + 'static,
+    V::Err: std::fmt::Display,
+{
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        Err(SubOptError::MissingValueForKey(k.into()))
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        self.0.insert(k.to_owned(), crate::parse_kv(k, v)?);
+        Ok(())
+    }
+}
+
+/// A [`SubOpt`] type that is either an `A` or a `B`, so two independent option sets can share
+/// one argument.
+///
+/// Sub-options go to `A` until it fails with [`SubOptError::UnknownKey`]. Then the
+/// sub-options given so far are replayed into a `B`, and if that works, the rest go to `B`.
+///
+/// ```
+/// use clap_subopt_parser::{parse_kv, parse_str, Either, SubOpt, SubOptError};
+///
+/// #[derive(Default)]
+/// struct Tcp { port: u16 }
+/// #[derive(Default)]
+/// struct Unix { path: String }
+///
+/// impl SubOpt for Tcp {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         Err(SubOptError::UnknownKey(k.into()))
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "port" => self.port = parse_kv(k, v)?,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// impl SubOpt for Unix {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         Err(SubOptError::UnknownKey(k.into()))
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "path" => self.path = v.into(),
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let listen: Either<Tcp, Unix> = parse_str("port=80").unwrap();
+/// assert_eq!(listen.left().unwrap().port, 80);
+/// let listen: Either<Tcp, Unix> = parse_str("path=/run/app.sock").unwrap();
+/// assert_eq!(listen.right().unwrap().path, "/run/app.sock");
+/// assert!(parse_str::<Either<Tcp, Unix>>("port=80:path=/run/app.sock").is_err());
+/// ```
+///
+/// The [occurrence](SubOpt::set_occurrence) and [positions](SubOpt::set_position) are passed
+/// to the type being parsed, and replayed into `B` with the sub-options when switching.
+///
+/// ```
+/// # use clap_subopt_parser::{parse_str, Either, SubOpt, SubOptError};
+/// # #[derive(Default)] struct Tcp;
+/// # impl SubOpt for Tcp {
+/// #     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+/// #         Err(SubOptError::UnknownKey(k.into()))
+/// #     }
+/// #     fn update_from_kvpair(&mut self, k: &str, _: &str) -> Result<(), SubOptError> {
+/// #         match k {
+/// #             "port" => Ok(()),
+/// #             _ => Err(SubOptError::UnknownKey(k.into())),
+/// #         }
+/// #     }
+/// # }
+/// use clap_subopt_parser::SegmentPosition;
+///
+/// #[derive(Default)]
+/// struct Any {
+///     positions: Vec<usize>,
+/// }
+///
+/// impl SubOpt for Any {
+///     fn update_from_value(&mut self, _: &str) -> Result<(), SubOptError> {
+///         Ok(())
+///     }
+///     fn update_from_kvpair(&mut self, _: &str, _: &str) -> Result<(), SubOptError> {
+///         Ok(())
+///     }
+///     fn set_position(&mut self, position: SegmentPosition) {
+///         self.positions.push(position.index);
+///     }
+/// }
+///
+/// let any = parse_str::<Either<Tcp, Any>>("port=80:mode=raw").unwrap();
+/// assert_eq!(any.right().unwrap().positions, [0, 1]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Either<A, B> {
+    value: EitherValue<A, B>,
+    /// The sub-options given to `A` and their positions, for replaying them into `B`
+    given: Vec<(String, Option<String>, Option<SegmentPosition>)>,
+    /// The occurrence given to `A`, for replaying it into `B`
+    occurrence: Option<usize>,
+    /// The position of the current sub-option
+    position: Option<SegmentPosition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum EitherValue<A, B> {
+    Left(A),
+    Right(B),
+}
+
+impl<A, B> Either<A, B> {
+    /// The `A`, if that's what was parsed
+    pub fn left(&self) -> Option<&A> {
+        match &self.value {
+            EitherValue::Left(a) => Some(a),
+            EitherValue::Right(_) => None,
+        }
+    }
+    /// The `B`, if that's what was parsed
+    pub fn right(&self) -> Option<&B> {
+        match &self.value {
+            EitherValue::Left(_) => None,
+            EitherValue::Right(b) => Some(b),
+        }
+    }
+    /// Convert into `Ok(A)` or `Err(B)`
+    pub fn into_result(self) -> Result<A, B> {
+        match self.value {
+            EitherValue::Left(a) => Ok(a),
+            EitherValue::Right(b) => Err(b),
+        }
+    }
+}
+
+impl<A: Default, B> Default for Either<A, B> {
+    fn default() -> Self {
+        Self {
+            value: EitherValue::Left(A::default()),
+            given: Vec::new(),
+            occurrence: None,
+            position: None,
+        }
+    }
+}
+
+impl<A: SubOpt, B: SubOpt + Default> Either<A, B> {
+    fn update(&mut self, k: &str, v: Option<&str>) -> Result<(), SubOptError> {
+        let a = match &mut self.value {
+            EitherValue::Left(a) => a,
+            EitherValue::Right(b) => return crate::update(b, k, v),
+        };
+        match crate::update(a, k, v) {
+            Err(SubOptError::UnknownKey(unknown)) => {
+                let mut b = B::default();
+                if let Some(index) = self.occurrence {
+                    b.set_occurrence(index);
+                }
+                for (k, v, position) in &self.given {
+                    if let Some(position) = *position {
+                        b.set_position(position);
+                    }
+                    if crate::update(&mut b, k, v.as_deref()).is_err() {
+                        return Err(SubOptError::UnknownKey(unknown));
+                    }
+                }
+                if let Some(position) = self.position {
+                    b.set_position(position);
+                }
+                crate::update(&mut b, k, v)?;
+                self.value = EitherValue::Right(b);
+                self.given.clear();
+                Ok(())
+            }
+            Ok(()) => {
+                let given = (k.to_owned(), v.map(str::to_owned), self.position);
+                self.given.push(given);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+}
+
+impl<A: SubOpt, B: SubOpt + Default> SubOpt for Either<A, B> {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        self.update(k, None)
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        self.update(k, Some(v))
+    }
+    fn keys() -> Vec<KeyInfo> {
+        let mut keys = A::keys();
+        keys.extend(B::keys());
+        keys
+    }
+    fn value_candidates(key: &str) -> Vec<CompletionCandidate> {
+        let mut candidates = A::value_candidates(key);
+        candidates.extend(B::value_candidates(key));
+        candidates
+    }
+    fn example() -> Option<&'static str> {
+        A::example().or_else(B::example)
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        let value = match self.value {
+            EitherValue::Left(a) => EitherValue::Left(a.finish()?),
+            EitherValue::Right(b) => EitherValue::Right(b.finish()?),
+        };
+        Ok(Self { value, ..self })
+    }
+    fn validate(&self) -> Result<(), SubOptError> {
+        match &self.value {
+            EitherValue::Left(a) => a.validate(),
+            EitherValue::Right(b) => b.validate(),
+        }
+    }
+    fn set_occurrence(&mut self, index: usize) {
+        self.occurrence = Some(index);
+        match &mut self.value {
+            EitherValue::Left(a) => a.set_occurrence(index),
+            EitherValue::Right(b) => b.set_occurrence(index),
+        }
+    }
+    fn set_position(&mut self, position: SegmentPosition) {
+        self.position = Some(position);
+        match &mut self.value {
+            EitherValue::Left(a) => a.set_position(position),
+            EitherValue::Right(b) => b.set_position(position),
+        }
+    }
+    const SEPARATOR: char = A::SEPARATOR;
+    const KV_SEPARATOR: char = A::KV_SEPARATOR;
+}
+
+/// A [`SubOpt`] type combining an `A` and a `B`, with each sub-option going to whichever
+/// recognizes its key, `A` first.
+///
+/// This is useful for layering common keys, like a log level, on top of specific ones.
+///
+/// ```
+/// use clap_subopt_parser::{parse_kv, parse_str, Chained, SubOpt, SubOptError};
+///
+/// #[derive(Default)]
+/// struct Common { dry_run: bool }
+/// #[derive(Default)]
+/// struct Dd { bs: usize }
+///
+/// impl SubOpt for Common {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "dry-run" => self.dry_run = true,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, _: &str) -> Result<(), SubOptError> {
+///         Err(SubOptError::UnknownKey(k.into()))
+///     }
+/// }
+///
+/// impl SubOpt for Dd {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         Err(SubOptError::UnknownKey(k.into()))
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "bs" => self.bs = parse_kv(k, v)?,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let (common, dd) = parse_str::<Chained<Common, Dd>>("bs=512:dry-run").unwrap().into_inner();
+/// assert!(common.dry_run);
+/// assert_eq!(dd.bs, 512);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Chained<A, B>(pub A, pub B);
+
+impl<A, B> Chained<A, B> {
+    /// Convert into a tuple
+    pub fn into_inner(self) -> (A, B) {
+        (self.0, self.1)
+    }
+}
+
+impl<A: SubOpt, B: SubOpt> SubOpt for Chained<A, B> {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        match self.0.update_from_value(k) {
+            Err(SubOptError::UnknownKey(_)) => self.1.update_from_value(k),
+            result => result,
+        }
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        match self.0.update_from_kvpair(k, v) {
+            Err(SubOptError::UnknownKey(_)) => self.1.update_from_kvpair(k, v),
+            result => result,
+        }
+    }
+    fn keys() -> Vec<KeyInfo> {
+        let mut keys = A::keys();
+        keys.extend(B::keys());
+        keys
+    }
+    fn value_candidates(key: &str) -> Vec<CompletionCandidate> {
+        let mut candidates = A::value_candidates(key);
+        candidates.extend(B::value_candidates(key));
+        candidates
+    }
+    fn example() -> Option<&'static str> {
+        A::example().or_else(B::example)
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        Ok(Self(self.0.finish()?, self.1.finish()?))
+    }
+    fn validate(&self) -> Result<(), SubOptError> {
+        self.0.validate()?;
+        self.1.validate()
+    }
+    fn set_occurrence(&mut self, index: usize) {
+        self.0.set_occurrence(index);
+        self.1.set_occurrence(index);
+    }
+    fn set_position(&mut self, position: SegmentPosition) {
+        self.0.set_position(position);
+        self.1.set_position(position);
+    }
+    const SEPARATOR: char = A::SEPARATOR;
+    const KV_SEPARATOR: char = A::KV_SEPARATOR;
+}
+
+/// A value that is itself a sub-option string, parsed as `T`, for nesting sub-options in a
+/// field.
+///
+/// The nested sub-options are separated by `ITEM`, and keys from values by `KV`, which are `,`
+/// and `=` by default. The nested value can be enclosed in square brackets, which is needed if
+/// it contains the outer separator `:`.
+///
+/// ```
+/// use clap_subopt_parser::{parse_kv, parse_str, Nested, SubOpt, SubOptError};
+///
+/// #[derive(Default)]
+/// struct Retry { count: u32, delay: u32 }
+///
+/// impl SubOpt for Retry {
+///     fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+///         Err(SubOptError::UnknownKey(k.into()))
+///     }
+///     fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+///         match k {
+///             "count" => self.count = parse_kv(k, v)?,
+///             "delay" => self.delay = parse_kv(k, v)?,
+///             _ => return Err(SubOptError::UnknownKey(k.into())),
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let retry: Nested<Retry> = "[count=3,delay=100]".parse().unwrap();
+/// assert_eq!((retry.0.count, retry.0.delay), (3, 100));
+/// let retry: Nested<Retry, ';', '~'> = "count~3;delay~100".parse().unwrap();
+/// assert_eq!((retry.0.count, retry.0.delay), (3, 100));
+/// assert!("tries=3".parse::<Nested<Retry>>().is_err());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Nested<T, const ITEM: char = ',', const KV: char = '='>(pub T);
+
+impl<T: SubOpt + Default, const ITEM: char, const KV: char> std::str::FromStr
+    for Nested<T, ITEM, KV>
+{
+    type Err = SubOptError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = crate::values::unbracket(s);
+        if s.trim().is_empty() {
+            return Err(SubOptError::EmptyArgument);
+        }
+        let mut val = T::default();
+        for (_, k, v) in crate::tokenize(s, ITEM, KV) {
+            crate::update(&mut val, k, v)?;
+        }
+        let val = val.finish()?;
+        val.validate()?;
+        Ok(Self(val))
+    }
+}
+
+/// A [`SubOpt`] wrapper for `T` with the separators `ITEM` between sub-options and `KV` between
+/// keys and values, instead of those of `T`.
+///
+/// The separators are part of the type, so parsers, completion and help of the argument use
+/// them. As a field, it parses like [`Nested`].
+///
+/// ```
+/// use clap_subopt_parser::{parse_str, Delimited, KvMap};
+///
+/// let map: Delimited<KvMap, ';', ':'> = parse_str("drive:C;path:C:\\Users").unwrap();
+/// assert_eq!(map.0.pairs["drive"], "C");
+/// assert_eq!(map.0.pairs["path"], "C:\\Users");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Delimited<T, const ITEM: char, const KV: char>(pub T);
+
+/// [`Delimited`] with the [`PATH_SEPARATOR`](crate::PATH_SEPARATOR) of the platform between
+/// sub-options, so Windows users can give paths with drive letters, like `C:\Temp;D:\Cache`.
+///
+/// ```
+/// use clap_subopt_parser::{parse_str, KvMap, NativeDelimited};
+///
+/// # #[cfg(windows)]
+/// let map: NativeDelimited<KvMap> = parse_str("tmp=C:\\Temp;cache=D:\\Cache").unwrap();
+/// # #[cfg(not(windows))]
+/// let map: NativeDelimited<KvMap> = parse_str("tmp=/tmp:cache=/var/cache").unwrap();
+/// assert_eq!(map.0.pairs.len(), 2);
+/// ```
+pub type NativeDelimited<T> = Delimited<T, { crate::PATH_SEPARATOR }, '='>;
+
+impl<T: SubOpt, const ITEM: char, const KV: char> SubOpt for Delimited<T, ITEM, KV> {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        self.0.update_from_value(k)
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        self.0.update_from_kvpair(k, v)
+    }
+    fn keys() -> Vec<KeyInfo> {
+        T::keys()
+    }
+    fn value_candidates(key: &str) -> Vec<CompletionCandidate> {
+        T::value_candidates(key)
+    }
+    fn example() -> Option<&'static str> {
+        T::example()
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        self.0.finish().map(Self)
+    }
+    fn validate(&self) -> Result<(), SubOptError> {
+        self.0.validate()
+    }
+    fn set_occurrence(&mut self, index: usize) {
+        self.0.set_occurrence(index)
+    }
+    fn set_position(&mut self, position: SegmentPosition) {
+        self.0.set_position(position)
+    }
+    const SEPARATOR: char = ITEM;
+    const KV_SEPARATOR: char = KV;
+}
+
+impl<T: SubOpt + Default, const ITEM: char, const KV: char> std::str::FromStr
+    for Delimited<T, ITEM, KV>
+{
+    type Err = SubOptError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<Nested<T, ITEM, KV>>().map(|n| Self(n.0))
+    }
+}
+
+/// A [`SubOpt`] wrapper for `T` in query string syntax, with `&` between sub-options and
+/// percent-encoded keys and values, like `source=0&offset=1000&name=a%20b`.
+///
+/// This allows pasting options from URLs. `+` is decoded as a space, like in HTML forms, and
+/// invalid escapes are kept as they are.
+///
+/// ```
+/// use clap_subopt_parser::{parse_str, KvMap, QueryString};
+///
+/// let map: QueryString<KvMap> = parse_str("source=0&name=a%20b&tag=x+y&ratio=1%").unwrap();
+/// assert_eq!(map.0.pairs["name"], "a b");
+/// assert_eq!(map.0.pairs["tag"], "x y");
+/// assert_eq!(map.0.pairs["ratio"], "1%");
+/// assert!(parse_str::<QueryString<KvMap>>("name=%FF").is_err());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct QueryString<T>(pub T);
+
+impl<T: SubOpt> SubOpt for QueryString<T> {
+    fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+        let k = percent_decode(k).map_err(|e| SubOptError::invalid_value(k, e))?;
+        self.0.update_from_value(&k)
+    }
+    fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+        let k = percent_decode(k).map_err(|e| SubOptError::invalid_value(k, e))?;
+        let v = percent_decode(v).map_err(|e| SubOptError::invalid_value(&*k, e))?;
+        self.0.update_from_kvpair(&k, &v)
+    }
+    fn keys() -> Vec<KeyInfo> {
+        T::keys()
+    }
+    fn value_candidates(key: &str) -> Vec<CompletionCandidate> {
+        T::value_candidates(key)
+    }
+    fn example() -> Option<&'static str> {
+        T::example()
+    }
+    fn finish(self) -> Result<Self, SubOptError> {
+        self.0.finish().map(Self)
+    }
+    fn validate(&self) -> Result<(), SubOptError> {
+        self.0.validate()
+    }
+    fn set_occurrence(&mut self, index: usize) {
+        self.0.set_occurrence(index)
+    }
+    fn set_position(&mut self, position: SegmentPosition) {
+        self.0.set_position(position)
+    }
+    const SEPARATOR: char = '&';
+}
+
+/// Decode the percent-encoding of `s`, with `+` as a space
+pub(crate) fn percent_decode(s: &str) -> Result<String, std::string::FromUtf8Error> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut rest = s.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        match (b, rest.get(..2)) {
+            (b'+', _) => bytes.push(b' '),
+            (b'%', Some(hex)) if hex.iter().all(u8::is_ascii_hexdigit) => {
+                let hex = std::str::from_utf8(hex).expect("ASCII hex digits");
+                bytes.push(u8::from_str_radix(hex, 16).expect("two hex digits"));
+                rest = &rest[2..];
+            }
+            (b, _) => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes)
+}
+
+/// A [`SubOpt`] for a tuple of [`FromStr`](std::str::FromStr) values, filled positionally from
+/// the sub-options, like `10:20` for `Positional<(u32, u32)>`.
+///
+/// Tuples of 2 to 4 values are supported, and all values must be given.
+///
+/// ```
+/// use clap_subopt_parser::{parse_str, Positional};
+///
+/// let range = parse_str::<Positional<(u32, u32)>>("10:20").unwrap();
+/// assert_eq!(range.into_inner(), (10, 20));
+/// assert!(parse_str::<Positional<(u32, u32)>>("10").is_err());
+/// assert!(parse_str::<Positional<(u32, u32)>>("10:20:30").is_err());
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Positional<T> {
+    value: T,
+    given: usize,
+}
+
+impl<T> Positional<T> {
+    /// The tuple of values
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> std::ops::Deref for Positional<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+fn parse_positional<T>(s: &str) -> Result<T, SubOptError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    s.parse().map_err(|e| {
+        SubOptError::Custom(format!(
+            "{} (expected {})",
+            e,
+            crate::error::short_type_name::<T>()
+        ))
+    })
+}
+
+macro_rules! positional_tuple {
+    ($len:literal; $($t:ident $i:tt),+) => {
+        impl<$($t),+> SubOpt for Positional<($($t,)+)>
+        where
+            $($t: std::str::FromStr + Send + Sync + 'static, $t::Err: std::fmt::Display,)+
+        {
+            fn update_from_value(&mut self, k: &str) -> Result<(), SubOptError> {
+                match self.given {
+                    $($i => self.value.$i = parse_positional(k)?,)+
+                    _ => {
+                        return Err(SubOptError::Custom(format!(
+                            "Too many values, expected {}",
+                            $len
+                        )))
+                    }
+                }
+                self.given += 1;
+                Ok(())
+            }
+            fn update_from_kvpair(&mut self, k: &str, v: &str) -> Result<(), SubOptError> {
+                self.update_from_value(&format!("{}{}{}", k, Self::KV_SEPARATOR, v))
+            }
+            fn finish(self) -> Result<Self, SubOptError> {
+                if self.given < $len {
+                    return Err(SubOptError::Custom(format!(
+                        "Expected {} values, got {}",
+                        $len, self.given
+                    )));
+                }
+                Ok(self)
+            }
+        }
+    };
+}
+
+positional_tuple!(2; A 0, B 1);
+positional_tuple!(3; A 0, B 1, C 2);
+positional_tuple!(4; A 0, B 1, C 2, D 3);
@@ -0,0 +1,124 @@
+use crate::schema::parse_value;
+use crate::warning::capture_warnings;
+use crate::{
+    parse_kv, tokenize, KeyInfo, KeyTable, Provenance, Source, SubOpt, SubOptError, SubOptWarning,
+};
+
+/// The outcome of a successful [`validate_str`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Report {
+    /// The keys that would be set, and where their values would come from
+    pub provenance: Provenance,
+    /// Warnings about the argument, like keys given more than once
+    pub warnings: Vec<SubOptWarning>,
+}
+
+/// Check `s` against the [keys](SubOpt::keys) of `T`, without parsing it into a `T`.
+///
+/// This is a dry run for `--check-args` modes and linting command lines in editors: no
+/// [`SubOpt`] method other than [`SubOpt::keys`] is called, and warnings are returned in the
+/// [`Report`] instead of being left for [`take_warnings`](crate::take_warnings). The checks are
+/// the ones the key metadata allows:
+/// - Keys must be known, by name or alias.
+/// - Values must match the [kind](KeyInfo::kind) of their key, or its
+///   [type name](KeyInfo::type_name) for numbers and `char`. Keys without a
+///   [value name](KeyInfo::value_name) can be given as bare values.
+/// - [Required](KeyInfo::required) keys must be given, or set in their
+///   [environment variable](KeyInfo::env), which is checked like the argument.
+///
+/// Constraints checked by [`SubOpt::finish`] and [`SubOpt::validate`] need the value, so they
+/// are not checked. Errors are located and redacted like parse errors.
+///
+/// ```
+/// use clap_subopt_parser::{subopt, validate_str, Source, SubOptError};
+///
+/// subopt! {
+///     struct Buf {
+///         source: u8,
+///         offset: u64,
+///         ro: bool,
+///     }
+/// }
+///
+/// let report = validate_str::<Buf>("source=1:ro:source=2").unwrap();
+/// assert_eq!(report.provenance.source("ro"), Some(&Source::Argument { index: 1 }));
+/// assert_eq!(report.warnings.len(), 1);
+///
+/// let err = validate_str::<Buf>("source=256").unwrap_err();
+/// assert!(matches!(err.root(), SubOptError::InvalidValue { .. }));
+/// assert!(validate_str::<Buf>("size=4").is_err());
+/// ```
+pub fn validate_str<T: SubOpt>(s: &str) -> Result<Report, SubOptError> {
+    if s.trim().is_empty() {
+        return Err(SubOptError::EmptyArgument);
+    }
+    let table = KeyTable::cached::<T>();
+    let (sep, kv_sep) = (T::SEPARATOR, T::KV_SEPARATOR);
+    let mut report = Report::default();
+    let (result, warnings) = capture_warnings(|| {
+        for (index, (span, k, v)) in tokenize(s, sep, kv_sep).enumerate() {
+            let key = match table.find(k) {
+                Some(key) => check_value(key, v).map(|()| key),
+                None => Err(SubOptError::UnknownKey(k.into())),
+            };
+            let key = key.map_err(|e| {
+                e.in_segment(s, span, kv_sep).redact(sep, kv_sep, |k| {
+                    table.keys.iter().any(|key| key.secret && key.matches(k))
+                })
+            })?;
+            if report.provenance.source(key.name).is_some() {
+                let message = format!("Key '{}' is given more than once", key.name);
+                report.warnings.push(SubOptWarning::Custom(message));
+            }
+            report
+                .provenance
+                .record(key.name, Source::Argument { index });
+        }
+        Ok(())
+    });
+    report.warnings.splice(0..0, warnings);
+    result?;
+    for key in &table.keys {
+        if report.provenance.source(key.name).is_some() {
+            continue;
+        }
+        let env = key
+            .env
+            .and_then(|var| std::env::var(var).ok().map(|value| (var, value)));
+        match env {
+            Some((var, value)) => {
+                let value = match key.value_name {
+                    Some(_) => Some(value.as_str()),
+                    None if !value.is_empty() => None,
+                    None => continue,
+                };
+                check_value(key, value).map_err(|error| SubOptError::InEnv {
+                    var: var.to_owned(),
+                    error: Box::new(error),
+                })?;
+                report.provenance.record(key.name, Source::Env(var));
+            }
+            None if key.required => return Err(SubOptError::MissingKey(key.name.into())),
+            None => {}
+        }
+    }
+    Ok(report)
+}
+
+/// Check `value` against the kind of `key`, or its type for primitive types
+fn check_value(key: &KeyInfo, value: Option<&str>) -> Result<(), SubOptError> {
+    macro_rules! check_typed {
+        ($($ty:ty)*) => {
+            match (key.kind, key.type_name, value) {
+                $(
+                    (None, Some(stringify!($ty)), Some(value)) => {
+                        parse_kv::<$ty>(key.name, value).map(drop)
+                    }
+                )*
+                _ => parse_value(key, value).map(drop),
+            }
+        };
+    }
+    check_typed!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64 char)
+}
@@ -0,0 +1,1335 @@
+//! Parsers for common kinds of values, for use in [`SubOpt`](crate::SubOpt) implementations.
+//!
+//! Like [`parse_kv`](crate::parse_kv), they take the key for error reporting, and fail with
+//! [`SubOptError::InvalidValue`] errors.
+
+use crate::{error::short_type_name, SubOptError};
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+
+/// Parse a byte size, like `512`, `4K`, `16KiB`, `2M` or `1G`.
+///
+/// `K`, `M`, `G` and `T` are powers of 1024, with or without `iB`. `KB`, `MB`, `GB` and `TB`
+/// are powers of 1000. A trailing `B` is allowed, and units are case-insensitive.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::bytes("size", "512").unwrap(), 512);
+/// assert_eq!(values::bytes("size", "4K").unwrap(), 4096);
+/// assert_eq!(values::bytes("size", "16KiB").unwrap(), 16384);
+/// assert_eq!(values::bytes("size", "2MB").unwrap(), 2_000_000);
+/// assert_eq!(values::bytes("size", "1g").unwrap(), 1 << 30);
+/// assert_eq!(
+///     values::bytes("size", "4X").unwrap_err().to_string(),
+///     "Invalid value for key 'size': unknown unit 'X' (expected a size like 512, 4K or 16KiB)"
+/// );
+/// ```
+pub fn bytes(key: &str, value: &str) -> Result<u64, SubOptError> {
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason).expected("a size like 512, 4K or 16KiB")
+    };
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    if number.is_empty() {
+        return Err(err("missing number".into()));
+    }
+    let number = number
+        .parse::<u64>()
+        .map_err(|_| err("number too large".into()))?;
+    let multiplier: u64 = match unit.trim_start().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        _ => return Err(err(format!("unknown unit '{}'", unit.trim_start()))),
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| err("size too large".into()))
+}
+
+/// Parse a duration, like `500ms`, `2s`, `5m` or `1h30m`, using [`humantime`].
+///
+/// ```
+/// use clap_subopt_parser::values;
+/// use std::time::Duration;
+///
+/// assert_eq!(values::duration("timeout", "500ms").unwrap(), Duration::from_millis(500));
+/// assert_eq!(values::duration("timeout", "1h30m").unwrap(), Duration::from_secs(5400));
+/// assert!(values::duration("timeout", "5").is_err());
+/// ```
+#[cfg(feature = "humantime")]
+pub fn duration(key: &str, value: &str) -> Result<std::time::Duration, SubOptError> {
+    humantime::parse_duration(value.trim()).map_err(|e| {
+        SubOptError::invalid_value(key, e).expected("a duration like 500ms, 2s or 1h30m")
+    })
+}
+
+/// Parse a percentage or fraction, like `50%` or `0.5`, into a fraction like `0.5`.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::percent("quality", "50%").unwrap(), 0.5);
+/// assert_eq!(values::percent("quality", "0.25").unwrap(), 0.25);
+/// assert!(values::percent("quality", "NaN").is_err());
+/// ```
+pub fn percent(key: &str, value: &str) -> Result<f64, SubOptError> {
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason).expected("a percentage like 50% or 0.5")
+    };
+    let value = value.trim();
+    let (number, scale) = match value.strip_suffix('%') {
+        Some(number) => (number.trim_end(), 100.0),
+        None => (value, 1.0),
+    };
+    let number = number.parse::<f64>().map_err(|e| err(e.to_string()))?;
+    if !number.is_finite() {
+        return Err(err("not a finite number".into()));
+    }
+    Ok(number / scale)
+}
+
+/// Parse a ratio, like `16/9` or `30000/1001`, into a numerator and denominator.
+///
+/// A plain number like `30` is the ratio `30/1`. The denominator can't be zero.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::ratio("aspect", "16/9").unwrap(), (16, 9));
+/// assert_eq!(values::ratio("fps", "30000/1001").unwrap(), (30000, 1001));
+/// assert_eq!(values::ratio("fps", "25").unwrap(), (25, 1));
+/// assert!(values::ratio("fps", "1/0").is_err());
+/// ```
+pub fn ratio(key: &str, value: &str) -> Result<(u64, u64), SubOptError> {
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason).expected("a ratio like 16/9 or 30000/1001")
+    };
+    let (num, den) = match value.trim().split_once('/') {
+        Some((num, den)) => (num.trim(), den.trim()),
+        None => (value.trim(), "1"),
+    };
+    let num = num.parse::<u64>().map_err(|e| err(e.to_string()))?;
+    let den = den.parse::<u64>().map_err(|e| err(e.to_string()))?;
+    if den == 0 {
+        return Err(err("denominator is zero".into()));
+    }
+    Ok((num, den))
+}
+
+/// A Kubernetes resource quantity, like `500m` or `1Gi`, returned by [`quantity`].
+///
+/// It is stored exactly, in billionths of a unit. Like in Kubernetes, [`value`](Self::value)
+/// and [`millis`](Self::millis) round up.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Quantity {
+    nanos: i128,
+}
+
+impl Quantity {
+    /// The quantity in billionths of a unit
+    pub fn nanos(self) -> i128 {
+        self.nanos
+    }
+    /// The quantity in thousandths of a unit, like millicores for CPU, rounded up
+    pub fn millis(self) -> i128 {
+        div_ceil(self.nanos, 1_000_000)
+    }
+    /// The quantity in units, like bytes for memory, rounded up
+    pub fn value(self) -> i128 {
+        div_ceil(self.nanos, 1_000_000_000)
+    }
+}
+
+fn div_ceil(n: i128, d: i128) -> i128 {
+    n / d + i128::from(n % d > 0)
+}
+
+/// Parse a Kubernetes resource quantity, like `500m`, `1Gi`, `250Mi` or `1.5`.
+///
+/// The number can have a fraction and a sign, and is followed by a decimal suffix (`n`, `u`,
+/// `m`, `k`, `M`, `G`, `T`, `P` or `E`), a binary suffix (`Ki`, `Mi`, `Gi`, `Ti`, `Pi` or
+/// `Ei`), or an exponent like `e3`. Suffixes are case-sensitive, so `1m` is a thousandth and
+/// `1M` is a million. Precision below a billionth is rounded up.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::quantity("cpu", "500m").unwrap().millis(), 500);
+/// assert_eq!(values::quantity("cpu", "1.5").unwrap().millis(), 1500);
+/// assert_eq!(values::quantity("cpu", "100u").unwrap().millis(), 1);
+/// assert_eq!(values::quantity("mem", "1Gi").unwrap().value(), 1 << 30);
+/// assert_eq!(values::quantity("mem", "250Mi").unwrap().value(), 250 << 20);
+/// assert_eq!(values::quantity("mem", "2k").unwrap().value(), 2000);
+/// assert_eq!(values::quantity("mem", "12e6").unwrap().value(), 12_000_000);
+/// assert_eq!(
+///     values::quantity("mem", "1GB").unwrap_err().to_string(),
+///     "Invalid value for key 'mem': unknown suffix 'GB' (expected a quantity like 500m, 1Gi \
+///      or 250Mi)"
+/// );
+/// ```
+pub fn quantity(key: &str, value: &str) -> Result<Quantity, SubOptError> {
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason).expected("a quantity like 500m, 1Gi or 250Mi")
+    };
+    let value = value.trim();
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let split = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    let (number, suffix) = rest.split_at(split);
+    let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+    if int.is_empty() && frac.is_empty() {
+        return Err(err("missing number".into()));
+    }
+    if frac.contains('.') {
+        return Err(err("more than one decimal point".into()));
+    }
+    let (shift, exp) = match suffix {
+        "" => (0, 0),
+        "n" => (0, -9),
+        "u" => (0, -6),
+        "m" => (0, -3),
+        "k" => (0, 3),
+        "M" => (0, 6),
+        "G" => (0, 9),
+        "T" => (0, 12),
+        "P" => (0, 15),
+        "E" => (0, 18),
+        "Ki" => (10, 0),
+        "Mi" => (20, 0),
+        "Gi" => (30, 0),
+        "Ti" => (40, 0),
+        "Pi" => (50, 0),
+        "Ei" => (60, 0),
+        _ => match suffix
+            .strip_prefix(['e', 'E'])
+            .and_then(|exp| exp.parse::<i32>().ok())
+        {
+            Some(exp) => (0, exp),
+            None => return Err(err(format!("unknown suffix '{}'", suffix))),
+        },
+    };
+    let too_large = || err("quantity too large".into());
+    let mut mantissa = format!("{}{}", int, frac)
+        .parse::<i128>()
+        .map_err(|_| too_large())?;
+    if negative {
+        mantissa = -mantissa;
+    }
+    let scaled = mantissa.checked_mul(1 << shift).ok_or_else(too_large)?;
+    let scale = i64::from(exp) + 9 - frac.len() as i64;
+    let pow = u32::try_from(scale.unsigned_abs())
+        .ok()
+        .and_then(|scale| 10i128.checked_pow(scale));
+    let nanos = match pow {
+        Some(pow) if scale >= 0 => scaled.checked_mul(pow).ok_or_else(too_large)?,
+        Some(pow) => div_ceil(scaled, pow),
+        None if scale >= 0 && scaled != 0 => return Err(too_large()),
+        None => i128::from(scaled > 0),
+    };
+    Ok(Quantity { nanos })
+}
+
+/// Integer types supported by [`int`]
+pub trait Integer: Sized {
+    /// The error returned when parsing fails
+    type Error: std::fmt::Display;
+    /// Parse `digits` in base `radix`, like [`i32::from_str_radix`]
+    fn from_str_radix(digits: &str, radix: u32) -> Result<Self, Self::Error>;
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty)*) => {
+        $(
+            impl Integer for $ty {
+                type Error = std::num::ParseIntError;
+                fn from_str_radix(digits: &str, radix: u32) -> Result<Self, Self::Error> {
+                    <$ty>::from_str_radix(digits, radix)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+/// Arbitrary-precision integers, for values that don't fit in a `u128`.
+///
+/// ```
+/// use clap_subopt_parser::values;
+/// use num_bigint::BigUint;
+///
+/// let block = values::int::<BigUint>("block", "0x1_0000_0000_0000_0000_0000_0000_0000_0000").unwrap();
+/// assert_eq!(block, BigUint::from(u128::MAX) + 1u8);
+/// assert!(values::int::<BigUint>("block", "-1").is_err());
+/// ```
+#[cfg(feature = "num-bigint")]
+impl Integer for num_bigint::BigUint {
+    type Error = num_bigint::ParseBigIntError;
+    fn from_str_radix(digits: &str, radix: u32) -> Result<Self, Self::Error> {
+        num_traits::Num::from_str_radix(digits, radix)
+    }
+}
+
+/// Arbitrary-precision signed integers
+///
+/// ```
+/// use clap_subopt_parser::values;
+/// use num_bigint::BigInt;
+///
+/// let offset = values::int::<BigInt>("offset", "-340_282_366_920_938_463_463_374_607_431_768_211_456").unwrap();
+/// assert_eq!(offset, -(BigInt::from(u128::MAX) + 1u8));
+/// ```
+#[cfg(feature = "num-bigint")]
+impl Integer for num_bigint::BigInt {
+    type Error = num_bigint::ParseBigIntError;
+    fn from_str_radix(digits: &str, radix: u32) -> Result<Self, Self::Error> {
+        num_traits::Num::from_str_radix(digits, radix)
+    }
+}
+
+/// Parse an integer in decimal, or in hexadecimal, octal or binary with a `0x`, `0o` or `0b`
+/// prefix.
+///
+/// Underscores are allowed as digit separators, and a sign can precede the prefix.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::int::<u32>("mask", "0xff_ff").unwrap(), 0xffff);
+/// assert_eq!(values::int::<u16>("mode", "0o755").unwrap(), 0o755);
+/// assert_eq!(values::int::<u8>("bits", "0b1010").unwrap(), 10);
+/// assert_eq!(values::int::<i64>("offset", "-1_000").unwrap(), -1000);
+/// assert!(values::int::<u8>("bits", "0x100").is_err());
+/// ```
+pub fn int<T: Integer>(key: &str, value: &str) -> Result<T, SubOptError> {
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason).expected("an integer like 42, 0x2a or 0b101010")
+    };
+    let value = value.trim();
+    let (sign, rest) = match value.strip_prefix(['-', '+']) {
+        Some(rest) => (&value[..1], rest),
+        None => ("", value),
+    };
+    let prefix = rest.get(..2).map(str::to_ascii_lowercase);
+    let (radix, digits) = match prefix.as_deref() {
+        Some("0x") => (16, &rest[2..]),
+        Some("0o") => (8, &rest[2..]),
+        Some("0b") => (2, &rest[2..]),
+        _ => (10, rest),
+    };
+    let digits = digits.replace('_', "");
+    if digits.is_empty() || digits.starts_with(['-', '+']) {
+        return Err(err("missing digits".into()));
+    }
+    T::from_str_radix(&format!("{}{}", sign, digits), radix).map_err(|e| err(e.to_string()))
+}
+
+/// Parse a boolean: `true`/`false`, `yes`/`no`, `on`/`off` or `1`/`0`, case-insensitively.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert!(values::boolean("sync", "Yes").unwrap());
+/// assert!(!values::boolean("sync", "off").unwrap());
+/// assert!(values::boolean("sync", "maybe").is_err());
+/// ```
+pub fn boolean(key: &str, value: &str) -> Result<bool, SubOptError> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(SubOptError::invalid_value(key, "not a boolean")
+            .expected("true, false, yes, no, on, off, 1 or 0")),
+    }
+}
+
+/// Parse a list of values separated by `sep`, like `0,2,4`, with [`FromStr`](std::str::FromStr).
+///
+/// An empty value is an empty list. Errors name the position of the bad element, counting
+/// from 0.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::list::<u32>("cpus", "0,2,4", ',').unwrap(), [0, 2, 4]);
+/// assert!(values::list::<u32>("cpus", "", ',').unwrap().is_empty());
+/// assert_eq!(
+///     values::list::<u32>("cpus", "0,x", ',').unwrap_err().to_string(),
+///     "Invalid value for key 'cpus': element 1 ('x'): invalid digit found in string \
+///      (expected a list of u32)"
+/// );
+/// ```
+pub fn list<T>(key: &str, value: &str, sep: char) -> Result<Vec<T>, SubOptError>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    if value.is_empty() {
+        return Ok(Vec::new());
+    }
+    value
+        .split(sep)
+        .enumerate()
+        .map(|(i, elem)| {
+            elem.parse().map_err(|e| {
+                SubOptError::invalid_value(key, format!("element {} ('{}'): {}", i, elem, e))
+                    .expected(format!("a list of {}", short_type_name::<T>()))
+            })
+        })
+        .collect()
+}
+
+/// Parse a range, like `10-20`, `10..=20` or `10..20`, with [`FromStr`](std::str::FromStr).
+///
+/// `10-20` and `10..=20` include the end, and `10..20` excludes it. The result implements
+/// [`RangeBounds`](std::ops::RangeBounds). Reversed bounds are an error.
+///
+/// ```
+/// use clap_subopt_parser::values;
+/// use std::ops::RangeBounds;
+///
+/// let ports = values::range::<u16>("ports", "8000-8100").unwrap();
+/// assert!(ports.contains(&8100));
+/// let ports = values::range::<u16>("ports", "8000..8100").unwrap();
+/// assert!(!ports.contains(&8100));
+/// assert!(values::range::<i32>("offsets", "-5..=5").unwrap().contains(&-5));
+/// assert!(values::range::<u16>("ports", "20-10").is_err());
+/// ```
+pub fn range<T>(key: &str, value: &str) -> Result<(Bound<T>, Bound<T>), SubOptError>
+where
+    T: std::str::FromStr + PartialOrd,
+    T::Err: std::fmt::Display,
+{
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason).expected(format!(
+            "a range of {} like 10-20, 10..=20 or 10..20",
+            short_type_name::<T>()
+        ))
+    };
+    let value = value.trim();
+    let (start, end, inclusive) = if let Some((start, end)) = value.split_once("..=") {
+        (start, end, true)
+    } else if let Some((start, end)) = value.split_once("..") {
+        (start, end, false)
+    } else if let Some(pos) = value.get(1..).and_then(|rest| rest.find('-')) {
+        (&value[..pos + 1], &value[pos + 2..], true)
+    } else {
+        return Err(err("missing range separator".into()));
+    };
+    let parse = |s: &str| s.trim().parse::<T>().map_err(|e| err(e.to_string()));
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start > end {
+        return Err(err("start is greater than end".into()));
+    }
+    let end = if inclusive {
+        Bound::Included(end)
+    } else {
+        Bound::Excluded(end)
+    };
+    Ok((Bound::Included(start), end))
+}
+
+/// Parse a map of entries separated by `sep`, with keys and values separated by `kv_sep`,
+/// like `A=1;B=2`.
+///
+/// This splits the same way the sub-options themselves are split. An empty value is an empty
+/// map, and later entries replace earlier ones with the same key.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// let headers = values::map("headers", "A=1;B=2", ';', '=').unwrap();
+/// assert_eq!(headers["A"], "1");
+/// assert_eq!(headers["B"], "2");
+/// assert!(values::map("headers", "A", ';', '=').is_err());
+/// ```
+pub fn map(
+    key: &str,
+    value: &str,
+    sep: char,
+    kv_sep: char,
+) -> Result<BTreeMap<String, String>, SubOptError> {
+    crate::tokenize(value, sep, kv_sep)
+        .map(|(_, k, v)| match v {
+            Some(v) => Ok((k.to_owned(), v.to_owned())),
+            None => Err(
+                SubOptError::invalid_value(key, format!("entry '{}' has no value", k))
+                    .expected(format!("entries like A{}1{}B{}2", kv_sep, sep, kv_sep)),
+            ),
+        })
+        .collect()
+}
+
+/// Parse a color, like `#ff8000`, `#ff800080` or a CSS named color like `rebeccapurple`.
+///
+/// Returns the red, green, blue and alpha components. The short forms `#rgb` and `#rgba` are
+/// also accepted, and names are case-insensitive.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::color("fg", "#ff8000").unwrap(), (255, 128, 0, 255));
+/// assert_eq!(values::color("fg", "#FF800080").unwrap(), (255, 128, 0, 128));
+/// assert_eq!(values::color("fg", "#f80").unwrap(), (255, 136, 0, 255));
+/// assert_eq!(values::color("fg", "RebeccaPurple").unwrap(), (102, 51, 153, 255));
+/// assert!(values::color("fg", "#ff80000").is_err());
+/// ```
+pub fn color(key: &str, value: &str) -> Result<(u8, u8, u8, u8), SubOptError> {
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason)
+            .expected("a color like #rrggbb, #rrggbbaa or a CSS color name")
+    };
+    let value = value.trim();
+    let Some(hex) = value.strip_prefix('#') else {
+        let name = value.to_ascii_lowercase();
+        return match CSS_COLORS.iter().find(|(n, _)| *n == name) {
+            Some(&(_, rgba)) => Ok(split_rgba(rgba)),
+            None => Err(err(format!("unknown color name '{}'", value))),
+        };
+    };
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(err("invalid hex digit".into()));
+    }
+    let expanded;
+    let hex = match hex.len() {
+        3 | 4 => {
+            expanded = hex.chars().flat_map(|c| [c, c]).collect::<String>();
+            &expanded
+        }
+        _ => hex,
+    };
+    let rgba = match hex.len() {
+        6 => u32::from_str_radix(hex, 16).map(|rgb| rgb << 8 | 0xff),
+        8 => u32::from_str_radix(hex, 16),
+        _ => return Err(err(format!("{} hex digits", hex.len()))),
+    };
+    rgba.map(split_rgba).map_err(|e| err(e.to_string()))
+}
+
+fn split_rgba(rgba: u32) -> (u8, u8, u8, u8) {
+    let [r, g, b, a] = rgba.to_be_bytes();
+    (r, g, b, a)
+}
+
+/// The CSS named colors, as `0xRRGGBBAA`
+const CSS_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ffff),
+    ("antiquewhite", 0xfaebd7ff),
+    ("aqua", 0x00ffffff),
+    ("aquamarine", 0x7fffd4ff),
+    ("azure", 0xf0ffffff),
+    ("beige", 0xf5f5dcff),
+    ("bisque", 0xffe4c4ff),
+    ("black", 0x000000ff),
+    ("blanchedalmond", 0xffebcdff),
+    ("blue", 0x0000ffff),
+    ("blueviolet", 0x8a2be2ff),
+    ("brown", 0xa52a2aff),
+    ("burlywood", 0xdeb887ff),
+    ("cadetblue", 0x5f9ea0ff),
+    ("chartreuse", 0x7fff00ff),
+    ("chocolate", 0xd2691eff),
+    ("coral", 0xff7f50ff),
+    ("cornflowerblue", 0x6495edff),
+    ("cornsilk", 0xfff8dcff),
+    ("crimson", 0xdc143cff),
+    ("cyan", 0x00ffffff),
+    ("darkblue", 0x00008bff),
+    ("darkcyan", 0x008b8bff),
+    ("darkgoldenrod", 0xb8860bff),
+    ("darkgray", 0xa9a9a9ff),
+    ("darkgreen", 0x006400ff),
+    ("darkgrey", 0xa9a9a9ff),
+    ("darkkhaki", 0xbdb76bff),
+    ("darkmagenta", 0x8b008bff),
+    ("darkolivegreen", 0x556b2fff),
+    ("darkorange", 0xff8c00ff),
+    ("darkorchid", 0x9932ccff),
+    ("darkred", 0x8b0000ff),
+    ("darksalmon", 0xe9967aff),
+    ("darkseagreen", 0x8fbc8fff),
+    ("darkslateblue", 0x483d8bff),
+    ("darkslategray", 0x2f4f4fff),
+    ("darkslategrey", 0x2f4f4fff),
+    ("darkturquoise", 0x00ced1ff),
+    ("darkviolet", 0x9400d3ff),
+    ("deeppink", 0xff1493ff),
+    ("deepskyblue", 0x00bfffff),
+    ("dimgray", 0x696969ff),
+    ("dimgrey", 0x696969ff),
+    ("dodgerblue", 0x1e90ffff),
+    ("firebrick", 0xb22222ff),
+    ("floralwhite", 0xfffaf0ff),
+    ("forestgreen", 0x228b22ff),
+    ("fuchsia", 0xff00ffff),
+    ("gainsboro", 0xdcdcdcff),
+    ("ghostwhite", 0xf8f8ffff),
+    ("gold", 0xffd700ff),
+    ("goldenrod", 0xdaa520ff),
+    ("gray", 0x808080ff),
+    ("green", 0x008000ff),
+    ("greenyellow", 0xadff2fff),
+    ("grey", 0x808080ff),
+    ("honeydew", 0xf0fff0ff),
+    ("hotpink", 0xff69b4ff),
+    ("indianred", 0xcd5c5cff),
+    ("indigo", 0x4b0082ff),
+    ("ivory", 0xfffff0ff),
+    ("khaki", 0xf0e68cff),
+    ("lavender", 0xe6e6faff),
+    ("lavenderblush", 0xfff0f5ff),
+    ("lawngreen", 0x7cfc00ff),
+    ("lemonchiffon", 0xfffacdff),
+    ("lightblue", 0xadd8e6ff),
+    ("lightcoral", 0xf08080ff),
+    ("lightcyan", 0xe0ffffff),
+    ("lightgoldenrodyellow", 0xfafad2ff),
+    ("lightgray", 0xd3d3d3ff),
+    ("lightgreen", 0x90ee90ff),
+    ("lightgrey", 0xd3d3d3ff),
+    ("lightpink", 0xffb6c1ff),
+    ("lightsalmon", 0xffa07aff),
+    ("lightseagreen", 0x20b2aaff),
+    ("lightskyblue", 0x87cefaff),
+    ("lightslategray", 0x778899ff),
+    ("lightslategrey", 0x778899ff),
+    ("lightsteelblue", 0xb0c4deff),
+    ("lightyellow", 0xffffe0ff),
+    ("lime", 0x00ff00ff),
+    ("limegreen", 0x32cd32ff),
+    ("linen", 0xfaf0e6ff),
+    ("magenta", 0xff00ffff),
+    ("maroon", 0x800000ff),
+    ("mediumaquamarine", 0x66cdaaff),
+    ("mediumblue", 0x0000cdff),
+    ("mediumorchid", 0xba55d3ff),
+    ("mediumpurple", 0x9370dbff),
+    ("mediumseagreen", 0x3cb371ff),
+    ("mediumslateblue", 0x7b68eeff),
+    ("mediumspringgreen", 0x00fa9aff),
+    ("mediumturquoise", 0x48d1ccff),
+    ("mediumvioletred", 0xc71585ff),
+    ("midnightblue", 0x191970ff),
+    ("mintcream", 0xf5fffaff),
+    ("mistyrose", 0xffe4e1ff),
+    ("moccasin", 0xffe4b5ff),
+    ("navajowhite", 0xffdeadff),
+    ("navy", 0x000080ff),
+    ("oldlace", 0xfdf5e6ff),
+    ("olive", 0x808000ff),
+    ("olivedrab", 0x6b8e23ff),
+    ("orange", 0xffa500ff),
+    ("orangered", 0xff4500ff),
+    ("orchid", 0xda70d6ff),
+    ("palegoldenrod", 0xeee8aaff),
+    ("palegreen", 0x98fb98ff),
+    ("paleturquoise", 0xafeeeeff),
+    ("palevioletred", 0xdb7093ff),
+    ("papayawhip", 0xffefd5ff),
+    ("peachpuff", 0xffdab9ff),
+    ("peru", 0xcd853fff),
+    ("pink", 0xffc0cbff),
+    ("plum", 0xdda0ddff),
+    ("powderblue", 0xb0e0e6ff),
+    ("purple", 0x800080ff),
+    ("rebeccapurple", 0x663399ff),
+    ("red", 0xff0000ff),
+    ("rosybrown", 0xbc8f8fff),
+    ("royalblue", 0x4169e1ff),
+    ("saddlebrown", 0x8b4513ff),
+    ("salmon", 0xfa8072ff),
+    ("sandybrown", 0xf4a460ff),
+    ("seagreen", 0x2e8b57ff),
+    ("seashell", 0xfff5eeff),
+    ("sienna", 0xa0522dff),
+    ("silver", 0xc0c0c0ff),
+    ("skyblue", 0x87ceebff),
+    ("slateblue", 0x6a5acdff),
+    ("slategray", 0x708090ff),
+    ("slategrey", 0x708090ff),
+    ("snow", 0xfffafaff),
+    ("springgreen", 0x00ff7fff),
+    ("steelblue", 0x4682b4ff),
+    ("tan", 0xd2b48cff),
+    ("teal", 0x008080ff),
+    ("thistle", 0xd8bfd8ff),
+    ("tomato", 0xff6347ff),
+    ("transparent", 0x00000000),
+    ("turquoise", 0x40e0d0ff),
+    ("violet", 0xee82eeff),
+    ("wheat", 0xf5deb3ff),
+    ("white", 0xffffffff),
+    ("whitesmoke", 0xf5f5f5ff),
+    ("yellow", 0xffff00ff),
+    ("yellowgreen", 0x9acd32ff),
+];
+
+/// Parse an IPv4 or IPv6 address
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert!(values::ip_addr("bind", "::1").unwrap().is_loopback());
+/// assert!(values::ip_addr("bind", "localhost").is_err());
+/// ```
+pub fn ip_addr(key: &str, value: &str) -> Result<std::net::IpAddr, SubOptError> {
+    value.trim().parse().map_err(|_| {
+        SubOptError::invalid_value(key, format!("'{}' is not an IP address", value.trim()))
+            .expected("an IP address like 127.0.0.1 or ::1")
+    })
+}
+
+/// Parse a socket address, like `127.0.0.1:80` or `[::1]:80`.
+///
+/// Since `:` separates sub-options, the value has to be put in square brackets on the
+/// command line, like `addr=[127.0.0.1:80]`. The brackets are removed here.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// let addr = values::socket_addr("addr", "[127.0.0.1:80]").unwrap();
+/// assert_eq!(addr.port(), 80);
+/// assert_eq!(values::socket_addr("addr", "[[::1]:80]").unwrap().port(), 80);
+/// assert_eq!(
+///     values::socket_addr("addr", "127.0.0.1").unwrap_err().to_string(),
+///     "Invalid value for key 'addr': missing port (expected an address like [127.0.0.1:80])"
+/// );
+/// ```
+pub fn socket_addr(key: &str, value: &str) -> Result<std::net::SocketAddr, SubOptError> {
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason).expected("an address like [127.0.0.1:80]")
+    };
+    let (host, port) = split_host_port(value).map_err(err)?;
+    let host = unbracket(host);
+    let ip = host
+        .parse::<std::net::IpAddr>()
+        .map_err(|_| err(format!("'{}' is not an IP address", host)))?;
+    Ok(std::net::SocketAddr::new(ip, port))
+}
+
+/// Parse a host name or address and a port, like `example.com:443`, without resolving it.
+///
+/// Like with [`socket_addr`], the value has to be put in square brackets on the command line.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(
+///     values::host_port("upstream", "[example.com:443]").unwrap(),
+///     ("example.com".to_owned(), 443)
+/// );
+/// ```
+pub fn host_port(key: &str, value: &str) -> Result<(String, u16), SubOptError> {
+    split_host_port(value)
+        .map(|(host, port)| (host.to_owned(), port))
+        .map_err(|reason| {
+            SubOptError::invalid_value(key, reason)
+                .expected("a host and port like [example.com:443]")
+        })
+}
+
+/// Remove enclosing square brackets
+pub(crate) fn unbracket(value: &str) -> &str {
+    value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value)
+}
+
+/// Split `host:port`, removing enclosing square brackets
+pub(crate) fn split_host_port(value: &str) -> Result<(&str, u16), String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .filter(|v| !v.ends_with(']'))
+        .unwrap_or(value);
+    let (host, port) = match value.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => (host, port),
+        _ => return Err("missing port".into()),
+    };
+    if host.contains(':') && !host.starts_with('[') {
+        return Err("IPv6 addresses need brackets, like [[::1]:80]".into());
+    }
+    if host.is_empty() {
+        return Err("missing host".into());
+    }
+    let port = port
+        .parse::<u16>()
+        .map_err(|_| format!("invalid port '{}'", port))?;
+    Ok((host, port))
+}
+
+/// Parse a URL, using [`url`].
+///
+/// Since `:` separates sub-options, the value has to be put in square brackets on the
+/// command line, like `endpoint=[https://example.com/api]`. The brackets are removed here.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// let url = values::url("endpoint", "[https://example.com/api]").unwrap();
+/// assert_eq!(url.host_str(), Some("example.com"));
+/// assert!(values::url("endpoint", "example.com").is_err());
+/// ```
+#[cfg(feature = "url")]
+pub fn url(key: &str, value: &str) -> Result<url::Url, SubOptError> {
+    let value = unbracket(value.trim());
+    url::Url::parse(value).map_err(|e| {
+        SubOptError::invalid_value(key, e).expected("a URL like [https://example.com]")
+    })
+}
+
+/// Parse the path of an existing file
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert!(values::existing_file("config", "Cargo.toml").is_ok());
+/// assert_eq!(
+///     values::existing_file("config", "src").unwrap_err().to_string(),
+///     "Invalid value for key 'config': 'src' is not a file"
+/// );
+/// ```
+pub fn existing_file(key: &str, value: &str) -> Result<std::path::PathBuf, SubOptError> {
+    existing(key, value, "file", std::fs::Metadata::is_file)
+}
+
+/// Parse the path of an existing directory
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert!(values::existing_dir("root", "src").is_ok());
+/// assert_eq!(
+///     values::existing_dir("root", "missing").unwrap_err().to_string(),
+///     "Invalid value for key 'root': 'missing' does not exist"
+/// );
+/// ```
+pub fn existing_dir(key: &str, value: &str) -> Result<std::path::PathBuf, SubOptError> {
+    existing(key, value, "directory", std::fs::Metadata::is_dir)
+}
+
+fn existing(
+    key: &str,
+    value: &str,
+    kind: &str,
+    is_kind: fn(&std::fs::Metadata) -> bool,
+) -> Result<std::path::PathBuf, SubOptError> {
+    let path = std::path::PathBuf::from(value);
+    match std::fs::metadata(&path) {
+        Ok(meta) if is_kind(&meta) => Ok(path),
+        Ok(_) => Err(SubOptError::invalid_value(
+            key,
+            format!("'{}' is not a {}", value, kind),
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(SubOptError::invalid_value(
+            key,
+            format!("'{}' does not exist", value),
+        )),
+        Err(e) => Err(SubOptError::invalid_value(
+            key,
+            format!("'{}': {}", value, e),
+        )),
+    }
+}
+
+/// Compile a regular expression, using [`regex`].
+///
+/// Compile errors are [`SubOptError::Custom`] errors that show the pattern with the
+/// offending part marked. Colons split sub-options unless they are inside square brackets,
+/// so use a character class like `[:]` to match a colon.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert!(values::regex("filter", "^foo[:]bar$").unwrap().is_match("foo:bar"));
+/// let err = values::regex("filter", "a(b").unwrap_err().to_string();
+/// assert!(err.starts_with("Invalid regex for key 'filter':"));
+/// assert!(err.contains("unclosed group"));
+/// ```
+#[cfg(feature = "regex")]
+pub fn regex(key: &str, value: &str) -> Result<regex::Regex, SubOptError> {
+    regex::Regex::new(value)
+        .map_err(|e| SubOptError::Custom(format!("Invalid regex for key '{}':\n{}", key, e)))
+}
+
+/// Parse an RFC 3339 timestamp, like `2024-01-01T00:00:00Z`, using [`chrono`].
+///
+/// Since `:` separates sub-options, the value has to be put in square brackets on the
+/// command line, like `since=[2024-01-01T00:00:00Z]`. The brackets are removed here.
+///
+/// ```
+/// use chrono::Datelike;
+/// use clap_subopt_parser::values;
+///
+/// let since = values::timestamp("since", "[2024-01-01T12:30:00+02:00]").unwrap();
+/// assert_eq!(since.year(), 2024);
+/// assert!(values::timestamp("since", "2024-01-01").is_err());
+/// ```
+#[cfg(feature = "chrono")]
+pub fn timestamp(
+    key: &str,
+    value: &str,
+) -> Result<chrono::DateTime<chrono::FixedOffset>, SubOptError> {
+    let value = unbracket(value.trim());
+    chrono::DateTime::parse_from_rfc3339(value).map_err(|e| {
+        SubOptError::invalid_value(key, e).expected("a timestamp like [2024-01-01T00:00:00Z]")
+    })
+}
+
+/// Parse a date, like `2024-01-01`, using [`chrono`]
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::date("until", "2024-02-29").unwrap().to_string(), "2024-02-29");
+/// assert!(values::date("until", "2023-02-29").is_err());
+/// ```
+#[cfg(feature = "chrono")]
+pub fn date(key: &str, value: &str) -> Result<chrono::NaiveDate, SubOptError> {
+    chrono::NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|e| SubOptError::invalid_value(key, e).expected("a date like 2024-01-01"))
+}
+
+/// Compile a glob pattern, like `*.rs`, using [`glob`](::glob)
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert!(values::glob("include", "src/*.rs").unwrap().matches("src/lib.rs"));
+/// assert_eq!(
+///     values::glob("include", "a**").unwrap_err().to_string(),
+///     "Invalid value for key 'include': recursive wildcards must form a single path \
+///      component at position 0 (expected a glob pattern like *.rs)"
+/// );
+/// ```
+#[cfg(feature = "glob")]
+pub fn glob(key: &str, value: &str) -> Result<::glob::Pattern, SubOptError> {
+    ::glob::Pattern::new(value).map_err(|e| {
+        SubOptError::invalid_value(key, format!("{} at position {}", e.msg, e.pos))
+            .expected("a glob pattern like *.rs")
+    })
+}
+
+/// Parse a [`clap::ValueEnum`], matching the names and aliases of its possible values.
+///
+/// This allows using the same enum for top level arguments and sub-options. Errors list the
+/// possible values that aren't hidden.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// #[derive(clap::ValueEnum, Clone, Debug, PartialEq)]
+/// enum Mode {
+///     Fast,
+///     Safe,
+/// }
+///
+/// assert_eq!(values::value_enum::<Mode>("mode", "safe").unwrap(), Mode::Safe);
+/// assert_eq!(
+///     values::value_enum::<Mode>("mode", "slow").unwrap_err().to_string(),
+///     "Invalid value for key 'mode': unknown value 'slow' (expected one of fast, safe)"
+/// );
+/// ```
+#[cfg(feature = "clap")]
+pub fn value_enum<E: clap::ValueEnum>(key: &str, value: &str) -> Result<E, SubOptError> {
+    let found = E::value_variants().iter().find(|variant| {
+        variant
+            .to_possible_value()
+            .is_some_and(|pv| pv.matches(value, false))
+    });
+    match found {
+        Some(variant) => Ok(variant.clone()),
+        None => {
+            let names = E::value_variants()
+                .iter()
+                .filter_map(E::to_possible_value)
+                .filter(|pv| !pv.is_hide_set())
+                .map(|pv| pv.get_name().to_owned())
+                .collect::<Vec<_>>();
+            Err(
+                SubOptError::invalid_value(key, format!("unknown value '{}'", value))
+                    .expected(format!("one of {}", names.join(", "))),
+            )
+        }
+    }
+}
+
+/// Parse an integer like [`int`], and check that it's within `range`.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::int_in::<u8>("level", "9", 1..=9).unwrap(), 9);
+/// assert_eq!(
+///     values::int_in::<u8>("level", "0", 1..=9).unwrap_err().to_string(),
+///     "Invalid value for key 'level': 0 is out of range (expected an integer in 1..=9)"
+/// );
+/// assert!(values::int_in::<i32>("offset", "-1", 0..).is_err());
+/// ```
+pub fn int_in<T>(key: &str, value: &str, range: impl RangeBounds<T>) -> Result<T, SubOptError>
+where
+    T: Integer + PartialOrd + std::fmt::Display,
+{
+    let n = int::<T>(key, value)?;
+    if range.contains(&n) {
+        return Ok(n);
+    }
+    let bound = |b: Bound<&T>, prefix: &str| match b {
+        Bound::Included(b) => format!("{}{}", prefix, b),
+        Bound::Excluded(b) if prefix == "=" => b.to_string(),
+        Bound::Excluded(b) => format!(">{}", b),
+        Bound::Unbounded => String::new(),
+    };
+    let expected = format!(
+        "an integer in {}..{}",
+        bound(range.start_bound(), ""),
+        bound(range.end_bound(), "=")
+    );
+    Err(SubOptError::invalid_value(key, format!("{} is out of range", n)).expected(expected))
+}
+
+/// Non-zero integer types supported by [`non_zero`], like [`std::num::NonZeroU32`]
+pub trait NonZeroInteger: Sized {
+    /// The underlying integer type
+    type Int: Integer;
+    /// Create a non-zero integer, if `n` is not zero
+    fn new(n: Self::Int) -> Option<Self>;
+}
+
+macro_rules! impl_non_zero_integer {
+    ($($ty:ident($int:ty))*) => {
+        $(
+            impl NonZeroInteger for std::num::$ty {
+                type Int = $int;
+                fn new(n: $int) -> Option<Self> {
+                    Self::new(n)
+                }
+            }
+        )*
+    };
+}
+
+impl_non_zero_integer!(
+    NonZeroU8(u8) NonZeroU16(u16) NonZeroU32(u32) NonZeroU64(u64) NonZeroU128(u128)
+    NonZeroUsize(usize) NonZeroI8(i8) NonZeroI16(i16) NonZeroI32(i32) NonZeroI64(i64)
+    NonZeroI128(i128) NonZeroIsize(isize)
+);
+
+/// Parse a non-zero integer like [`int`]
+///
+/// ```
+/// use clap_subopt_parser::values;
+/// use std::num::NonZeroUsize;
+///
+/// let threads = values::non_zero::<NonZeroUsize>("threads", "4").unwrap();
+/// assert_eq!(threads.get(), 4);
+/// assert!(values::non_zero::<NonZeroUsize>("threads", "0").is_err());
+/// ```
+pub fn non_zero<N: NonZeroInteger>(key: &str, value: &str) -> Result<N, SubOptError> {
+    N::new(int(key, value)?).ok_or_else(|| {
+        SubOptError::invalid_value(key, "must not be zero").expected("a non-zero integer")
+    })
+}
+
+/// A value made of a number and a unit suffix, like `10ms`, `3px` or `2.5x`, see [`unit_value`]
+///
+/// ```
+/// use clap_subopt_parser::values::{self, UnitValue};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Length(f64);
+///
+/// impl UnitValue for Length {
+///     const UNITS: &'static [(&'static str, f64)] = &[("px", 1.0), ("em", 16.0), ("", 1.0)];
+///     fn from_scaled(px: f64) -> Self {
+///         Length(px)
+///     }
+/// }
+///
+/// assert_eq!(values::unit_value::<Length>("width", "2.5em").unwrap(), Length(40.0));
+/// assert_eq!(values::unit_value::<Length>("width", "3").unwrap(), Length(3.0));
+/// assert_eq!(
+///     values::unit_value::<Length>("width", "3pt").unwrap_err().to_string(),
+///     "Invalid value for key 'width': unknown unit 'pt' (expected a number with one of the \
+///      units px, em)"
+/// );
+/// ```
+pub trait UnitValue: Sized {
+    /// The unit suffixes and their multipliers.
+    ///
+    /// The empty suffix allows numbers without a unit.
+    const UNITS: &'static [(&'static str, f64)];
+    /// Create the value from the number multiplied by the multiplier of its unit
+    fn from_scaled(value: f64) -> Self;
+}
+
+/// Parse a number followed by one of the units of `U`
+pub fn unit_value<U: UnitValue>(key: &str, value: &str) -> Result<U, SubOptError> {
+    let units = U::UNITS
+        .iter()
+        .map(|(unit, _)| *unit)
+        .filter(|unit| !unit.is_empty())
+        .collect::<Vec<_>>();
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason).expected(format!(
+            "a number with one of the units {}",
+            units.join(", ")
+        ))
+    };
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | '_')))
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let unit = unit.trim_start();
+    let Some(&(_, multiplier)) = U::UNITS.iter().find(|(u, _)| *u == unit) else {
+        return Err(err(if unit.is_empty() {
+            "missing unit".into()
+        } else {
+            format!("unknown unit '{}'", unit)
+        }));
+    };
+    let number = number
+        .replace('_', "")
+        .parse::<f64>()
+        .map_err(|e| err(e.to_string()))?;
+    Ok(U::from_scaled(number * multiplier))
+}
+
+/// Evaluate an integer expression, like `1024*1024+512`.
+///
+/// Supports `+`, `-`, `*`, `/`, `%` and parentheses, with integers in the forms accepted by
+/// [`int`]. Overflow, division by zero and nesting deeper than 256 levels are errors.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::expr::<u64>("offset", "1024*1024+512").unwrap(), 1_049_088);
+/// assert_eq!(values::expr::<i32>("delta", "-(0x10 - 4) / 3").unwrap(), -4);
+/// assert_eq!(
+///     values::expr::<u64>("offset", "1/0").unwrap_err().to_string(),
+///     "Invalid value for key 'offset': division by zero (expected an integer expression)"
+/// );
+/// assert!(values::expr::<u8>("level", "200+100").is_err());
+/// assert!(values::expr::<u8>("level", &"(".repeat(100_000)).is_err());
+/// assert!(values::expr::<i8>("level", &"-".repeat(100_000)).is_err());
+/// ```
+#[cfg(feature = "expr")]
+pub fn expr<T: TryFrom<i128>>(key: &str, value: &str) -> Result<T, SubOptError> {
+    let err =
+        |reason: String| SubOptError::invalid_value(key, reason).expected("an integer expression");
+    let n = crate::expr::eval(value).map_err(err)?;
+    T::try_from(n).map_err(|_| {
+        err(format!(
+            "{} is out of range for {}",
+            n,
+            short_type_name::<T>()
+        ))
+    })
+}
+
+/// Parse a UUID, using [`uuid`](::uuid).
+///
+/// Accepts the forms with and without hyphens, and with braces or a `urn:uuid:` prefix.
+/// The colons of the prefix require square brackets on the command line.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// let id = values::uuid("id", "67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+/// assert_eq!(values::uuid("id", "67e5504410b1426f9247bb680e5fe0c8").unwrap(), id);
+/// assert_eq!(values::uuid("id", "{67e55044-10b1-426f-9247-bb680e5fe0c8}").unwrap(), id);
+/// assert!(values::uuid("id", "67e55044").is_err());
+/// ```
+#[cfg(feature = "uuid")]
+pub fn uuid(key: &str, value: &str) -> Result<::uuid::Uuid, SubOptError> {
+    ::uuid::Uuid::parse_str(unbracket(value.trim()))
+        .map_err(|e| SubOptError::invalid_value(key, e).expected("a UUID"))
+}
+
+/// Parse a MIME type, like `application/json`, using [`mime`](::mime)
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// let mime = values::mime("type", "text/plain;charset=utf-8").unwrap();
+/// assert_eq!(mime.subtype(), "plain");
+/// assert_eq!(mime.get_param("charset").unwrap(), "utf-8");
+/// assert!(values::mime("type", "json").is_err());
+/// ```
+#[cfg(feature = "mime")]
+pub fn mime(key: &str, value: &str) -> Result<::mime::Mime, SubOptError> {
+    value.trim().parse().map_err(|e| {
+        SubOptError::invalid_value(key, e).expected("a MIME type like application/json")
+    })
+}
+
+/// Parse a semantic version, like `1.2.3`, using [`semver`]
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::version("version", "1.2.3").unwrap().minor, 2);
+/// assert!(values::version("version", "1.2").is_err());
+/// ```
+#[cfg(feature = "semver")]
+pub fn version(key: &str, value: &str) -> Result<semver::Version, SubOptError> {
+    semver::Version::parse(value.trim())
+        .map_err(|e| SubOptError::invalid_value(key, e).expected("a version like 1.2.3"))
+}
+
+/// Parse a semantic version requirement, like `^1.2` or `>=1.0, <2`, using [`semver`]
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// let req = values::version_req("min-version", "^1.2").unwrap();
+/// assert!(req.matches(&values::version("version", "1.4.0").unwrap()));
+/// assert!(values::version_req("min-version", "~>1").is_err());
+/// ```
+#[cfg(feature = "semver")]
+pub fn version_req(key: &str, value: &str) -> Result<semver::VersionReq, SubOptError> {
+    semver::VersionReq::parse(value.trim())
+        .map_err(|e| SubOptError::invalid_value(key, e).expected("a version requirement like ^1.2"))
+}
+
+/// Parse flags separated by `|`, like `read|write`, and combine their values from `table`.
+///
+/// The values are combined with `|`, so `T` can be an integer or a bitflags type. An empty
+/// value is [`T::default`](Default::default).
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// const PERMS: &[(&str, u32)] = &[("read", 4), ("write", 2), ("exec", 1)];
+///
+/// assert_eq!(values::flags("perms", "read|write", PERMS).unwrap(), 6);
+/// assert_eq!(
+///     values::flags("perms", "read|delete", PERMS).unwrap_err().to_string(),
+///     "Invalid value for key 'perms': unknown flag 'delete' \
+///      (expected flags separated by |, out of read, write, exec)"
+/// );
+/// ```
+pub fn flags<T>(key: &str, value: &str, table: &[(&str, T)]) -> Result<T, SubOptError>
+where
+    T: Copy + Default + std::ops::BitOr<Output = T>,
+{
+    if value.trim().is_empty() {
+        return Ok(T::default());
+    }
+    value.split('|').try_fold(T::default(), |acc, name| {
+        let name = name.trim();
+        match table.iter().find(|(n, _)| *n == name) {
+            Some(&(_, bits)) => Ok(acc | bits),
+            None => {
+                let names = table.iter().map(|(n, _)| *n).collect::<Vec<_>>();
+                Err(
+                    SubOptError::invalid_value(key, format!("unknown flag '{}'", name))
+                        .expected(format!("flags separated by |, out of {}", names.join(", "))),
+                )
+            }
+        }
+    })
+}
+
+/// Parse a single character, an escape sequence like `\t`, or a code point like `U+1F600`.
+///
+/// The escape sequences are `\t`, `\n`, `\r`, `\0`, `\\`, `\xHH` and `\u{HHHH}`.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::character("delimiter", ";").unwrap(), ';');
+/// assert_eq!(values::character("delimiter", "\\t").unwrap(), '\t');
+/// assert_eq!(values::character("delimiter", "\\x1f").unwrap(), '\x1f');
+/// assert_eq!(values::character("marker", "U+1F600").unwrap(), '😀');
+/// assert!(values::character("delimiter", "ab").is_err());
+/// ```
+pub fn character(key: &str, value: &str) -> Result<char, SubOptError> {
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason)
+            .expected("a character, an escape like \\t, or a code point like U+1F600")
+    };
+    let code_point = |hex: &str| {
+        u32::from_str_radix(hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| err(format!("invalid code point '{}'", hex)))
+    };
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => return Ok(c),
+        (None, _) => return Err(err("missing character".into())),
+        _ => {}
+    }
+    if let Some(hex) = value
+        .strip_prefix("U+")
+        .or_else(|| value.strip_prefix("u+"))
+    {
+        return code_point(hex);
+    }
+    match value {
+        "\\t" => Ok('\t'),
+        "\\n" => Ok('\n'),
+        "\\r" => Ok('\r'),
+        "\\0" => Ok('\0'),
+        "\\\\" => Ok('\\'),
+        _ => {
+            if let Some(hex) = value.strip_prefix("\\x").filter(|hex| hex.len() == 2) {
+                code_point(hex)
+            } else if let Some(hex) = value
+                .strip_prefix("\\u{")
+                .and_then(|hex| hex.strip_suffix('}'))
+            {
+                code_point(hex)
+            } else {
+                Err(err(format!("'{}' is not a single character", value)))
+            }
+        }
+    }
+}
+
+/// Parse a finite floating point number, like `1.5`, `1_000.25` or `2.5e-3`.
+///
+/// Underscores are allowed as digit separators. With `comma_decimal`, `,` is accepted as the
+/// decimal separator too, like in `1,5`.
+///
+/// ```
+/// use clap_subopt_parser::values;
+///
+/// assert_eq!(values::float("scale", "1_000.5", false).unwrap(), 1000.5);
+/// assert_eq!(values::float("scale", "2.5e-3", false).unwrap(), 0.0025);
+/// assert_eq!(values::float("scale", "1,5", true).unwrap(), 1.5);
+/// assert!(values::float("scale", "1,5", false).is_err());
+/// assert!(values::float("scale", "inf", false).is_err());
+/// ```
+pub fn float(key: &str, value: &str, comma_decimal: bool) -> Result<f64, SubOptError> {
+    let err = |reason: String| {
+        SubOptError::invalid_value(key, reason).expected("a number like 1.5 or 2.5e-3")
+    };
+    let mut number = value.trim().replace('_', "");
+    if comma_decimal {
+        number = number.replace(',', ".");
+    }
+    let n = number.parse::<f64>().map_err(|e| err(e.to_string()))?;
+    if !n.is_finite() {
+        return Err(err("not a finite number".into()));
+    }
+    Ok(n)
+}
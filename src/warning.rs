@@ -0,0 +1,88 @@
+use crate::error::{truncate, MAX_ECHO_LEN};
+use std::{cell::RefCell, ops::Range};
+
+thread_local! {
+    static WARNINGS: RefCell<Vec<SubOptWarning>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A non-fatal diagnostic that can happen when parsing a sub-option.
+///
+/// Warnings don't abort parsing. They are collected, and can be retrieved with
+/// [`take_warnings`] after parsing. At most [`MAX_WARNINGS`] are kept, later ones are dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubOptWarning {
+    /// A deprecated key was used
+    DeprecatedKey(String),
+    /// An empty segment was ignored, like in `key1=value1::key2=value2`
+    EmptySegment {
+        /// Byte range of the empty segment in the argument
+        span: Range<usize>,
+    },
+    /// A `[` was never closed, like in `key1=[value1:key2=value2`, so it was taken literally
+    UnclosedBracket {
+        /// Byte range from the sub-option containing the bracket to the end of the argument
+        span: Range<usize>,
+    },
+    /// Custom warning
+    Custom(String),
+}
+
+impl std::fmt::Display for SubOptWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SubOptWarning::DeprecatedKey(k) => {
+                write!(f, "Key '{}' is deprecated", truncate(k, MAX_ECHO_LEN))
+            }
+            SubOptWarning::EmptySegment { span } => {
+                write!(f, "Ignored empty sub-option at byte {}", span.start)
+            }
+            SubOptWarning::UnclosedBracket { span } => {
+                write!(f, "Unclosed '[' in sub-option at byte {}", span.start)
+            }
+            SubOptWarning::Custom(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// The maximum number of warnings kept until they are taken
+pub const MAX_WARNINGS: usize = 64;
+
+/// Emit a warning.
+///
+/// Call this from [`SubOpt`](crate::SubOpt) implementations, for example when a deprecated key
+/// is used. If [`MAX_WARNINGS`] warnings are already pending, the warning is dropped, so
+/// programs that never call [`take_warnings`] don't accumulate them forever.
+///
+/// ```
+/// use clap_subopt_parser::{take_warnings, warn, SubOptWarning, MAX_WARNINGS};
+///
+/// for _ in 0..MAX_WARNINGS + 1 {
+///     warn(SubOptWarning::Custom("Careful".into()));
+/// }
+/// assert_eq!(take_warnings().len(), MAX_WARNINGS);
+/// ```
+pub fn warn(warning: SubOptWarning) {
+    WARNINGS.with(|w| {
+        let mut w = w.borrow_mut();
+        if w.len() < MAX_WARNINGS {
+            w.push(warning);
+        }
+    });
+}
+
+/// Take the warnings emitted so far on the current thread.
+///
+/// Call this after parsing the command line arguments to present the warnings to the user.
+pub fn take_warnings() -> Vec<SubOptWarning> {
+    WARNINGS.with(|w| std::mem::take(&mut *w.borrow_mut()))
+}
+
+/// Call `f`, and take the warnings it emits instead of leaving them for [`take_warnings`].
+///
+/// The pending warnings are set aside meanwhile, so `f` gets the full [`MAX_WARNINGS`].
+pub(crate) fn capture_warnings<R>(f: impl FnOnce() -> R) -> (R, Vec<SubOptWarning>) {
+    let pending = take_warnings();
+    let result = f();
+    let warnings = WARNINGS.with(|w| std::mem::replace(&mut *w.borrow_mut(), pending));
+    (result, warnings)
+}